@@ -12,6 +12,8 @@ macro_rules! any {
         pub enum Any {
             $($kind($kind),)*
             $($boxed(Box<$boxed>),)*
+            /// A user-defined atom, see [Custom::register].
+            Custom(Custom),
             Unknown(FourCC, Vec<u8>),
         }
 
@@ -21,9 +23,46 @@ macro_rules! any {
                 match self {
                     $(Any::$kind(_) => $kind::KIND,)*
                     $(Any::$boxed(_) => $boxed::KIND,)*
+                    Any::Custom(custom) => custom.kind(),
                     Any::Unknown(kind, _) => *kind,
                 }
             }
+
+            /// Returns true if the atom starts with a version and flags, see [Atom::FULL_BOX].
+            pub fn is_full_box(&self) -> bool {
+                match self {
+                    $(Any::$kind(_) => $kind::FULL_BOX,)*
+                    $(Any::$boxed(_) => $boxed::FULL_BOX,)*
+                    Any::Custom(custom) => custom.is_full_box(),
+                    Any::Unknown(..) => false,
+                }
+            }
+
+            // Returns true if atoms of this kind start with a version and flags, or None if the kind isn't supported.
+            #[cfg(feature = "serde")]
+            pub(crate) fn is_full_box_kind(kind: FourCC) -> Option<bool> {
+                $(if kind == $kind::KIND { return Some($kind::FULL_BOX) })*
+                $(if kind == $boxed::KIND { return Some($boxed::FULL_BOX) })*
+                Custom::is_full_box_kind(kind)
+            }
+
+            pub(crate) fn visit_inner<'a>(&'a self, index: Option<usize>, path: &mut AtomPath, visitor: &mut dyn Visitor<'a>) {
+                match self {
+                    $(Any::$kind(inner) => visit_atom(inner, index, path, visitor),)*
+                    $(Any::$boxed(boxed) => visit_atom(boxed.as_ref(), index, path, visitor),)*
+                    Any::Custom(custom) => visit_opaque(custom.kind(), custom, index, path, visitor),
+                    Any::Unknown(kind, body) => visit_opaque(*kind, body, index, path, visitor),
+                }
+            }
+
+            pub(crate) fn visit_inner_mut(&mut self, index: Option<usize>, path: &mut AtomPath, visitor: &mut dyn VisitorMut) {
+                match self {
+                    $(Any::$kind(inner) => visit_atom_mut(inner, index, path, visitor),)*
+                    $(Any::$boxed(boxed) => visit_atom_mut(boxed.as_mut(), index, path, visitor),)*
+                    Any::Custom(custom) => visit_opaque_mut(custom.kind(), custom, index, path, visitor),
+                    Any::Unknown(kind, body) => visit_opaque_mut(*kind, body, index, path, visitor),
+                }
+            }
         }
 
         impl Decode for Any {
@@ -51,8 +90,9 @@ macro_rules! any {
                     return Ok(None);
                 }
 
-                buf.advance(remaining - peek.remaining());
-                Ok(Some(Self::decode_atom(&header, buf)?))
+                let header_size = remaining - peek.remaining();
+                buf.advance(header_size);
+                Ok(Some(Self::decode_sized(&header, header_size, buf)?))
             }
         }
 
@@ -65,6 +105,7 @@ macro_rules! any {
                 match self {
                     $(Any::$kind(inner) => Atom::encode_body(inner, buf),)*
                     $(Any::$boxed(boxed) => Atom::encode_body(boxed.as_ref(), buf),)*
+                    Any::Custom(custom) => custom.encode_body(buf),
                     Any::Unknown(_, data) => data.encode(buf),
                 }?;
 
@@ -78,40 +119,33 @@ macro_rules! any {
         impl DecodeAtom for Any {
             /// Decode the atom from a header and payload.
             fn decode_atom<B: Buf>(header: &Header, buf: &mut B) -> Result<Self> {
+                Self::decode_sized(header, header.encoded_size(), buf)
+            }
+        }
+
+        impl Any {
+            // Decode the atom from a header and payload, given the exact size of the header.
+            pub(crate) fn decode_sized<B: Buf>(header: &Header, header_size: usize, buf: &mut B) -> Result<Self> {
                 let size = header.size.unwrap_or(buf.remaining());
                 if size > buf.remaining() {
                     return Err(Error::OutOfBounds);
                 }
 
-                let mut body = &mut buf.slice(size);
+                let body = &mut buf.slice(size);
 
                 let atom = match header.kind {
                     $(_ if header.kind == $kind::KIND => {
-                        Any::$kind(match $kind::decode_body(&mut body) {
-                            Ok(atom) => atom,
-                            Err(Error::OutOfBounds) => return Err(Error::OverDecode($kind::KIND)),
-                            Err(Error::ShortRead) => return Err(Error::UnderDecode($kind::KIND)),
-                            Err(err) => return Err(err),
-                        })
+                        Any::$kind(decode_atom_body($kind::KIND, header_size, body, $kind::decode_body)?)
                     },)*
                     $(_ if header.kind == $boxed::KIND => {
-                        Any::$boxed(match $boxed::decode_body(&mut body) {
-                            Ok(atom) => Box::new(atom),
-                            Err(Error::OutOfBounds) => return Err(Error::OverDecode($boxed::KIND)),
-                            Err(Error::ShortRead) => return Err(Error::UnderDecode($boxed::KIND)),
-                            Err(err) => return Err(err),
-                        })
+                        Any::$boxed(Box::new(decode_atom_body($boxed::KIND, header_size, body, $boxed::decode_body)?))
                     },)*
-                    _ => {
-                        let body = Vec::decode(body)?;
-                        Any::Unknown(header.kind, body)
+                    _ => match Custom::decoder(header.kind) {
+                        Some(decode) => Any::Custom(decode_atom_body(header.kind, header_size, body, |body| decode(body))?),
+                        None => Any::Unknown(header.kind, Vec::decode(body)?),
                     },
                 };
 
-                if body.has_remaining() {
-                    return Err(Error::UnderDecode(header.kind));
-                }
-
                 buf.advance(size);
 
                 Ok(atom)
@@ -123,6 +157,7 @@ macro_rules! any {
                 match self {
                     $(Any::$kind(inner) => inner.fmt(f),)*
                     $(Any::$boxed(boxed) => boxed.fmt(f),)*
+                    Any::Custom(custom) => custom.fmt(f),
                     Any::Unknown(kind, body) => write!(f, "Unknown {{ kind: {:?}, size: {:?}, bytes: {:?} }}", kind, body.len(), body),
                 }
             }
@@ -318,6 +353,7 @@ any! {
                         Stts,
                         Stsc,
                         Stsz,
+                        Stz2,
                         Stss,
                         Stco,
                         Co64,
@@ -328,6 +364,10 @@ any! {
                         Subs,
                         Saio,
                         Saiz,
+                        Sdtp,
+                        Stsh,
+                        Stdp,
+                        Padb,
                     Dinf,
                         Dref,
                     Hmhd,
@@ -341,6 +381,7 @@ any! {
         Mvex,
             Mehd,
             Trex,
+            Leva,
     Emsg,
     Moof,
         Mfhd,
@@ -352,10 +393,13 @@ any! {
     Mdat,
     Free,
     Sidx,
+    Ssix,
     Prft,
     Mfra,
         Tfra,
         Mfro,
+    // Within sinf/schi, which aren't modeled yet.
+    Tenc,
     ],
     boxed: [
         Trak,
@@ -370,13 +414,13 @@ impl ReadFrom for Any {
 
 impl ReadFrom for Option<Any> {
     fn read_from<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
-        let header = match <Option<Header> as ReadFrom>::read_from(r)? {
+        let (header, header_size) = match Header::read_sized(r)? {
             Some(header) => header,
             None => return Ok(None),
         };
 
         let body = &mut header.read_body(r)?;
-        Ok(Some(Any::decode_atom(&header, body)?))
+        Ok(Some(Any::decode_sized(&header, header_size, body)?))
     }
 }
 
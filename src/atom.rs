@@ -6,6 +6,9 @@ use crate::*;
 pub trait Atom: Sized {
     const KIND: FourCC;
 
+    /// True if the body starts with a version and flags, known as a FullBox in the specification.
+    const FULL_BOX: bool = false;
+
     fn decode_body<B: Buf>(buf: &mut B) -> Result<Self>;
     fn encode_body<B: BufMut>(&self, buf: &mut B) -> Result<()>;
 
@@ -13,6 +16,14 @@ pub trait Atom: Sized {
     fn decode_unknown(atom: &crate::Any) -> Result<()> {
         crate::decode_unknown(atom, Self::KIND)
     }
+
+    /// Call the visitor for each child atom, recursively, see [Traverse].
+    ///
+    /// The path ends with this atom. Atoms without children don't need to implement this.
+    fn visit_children<'a>(&'a self, _path: &mut AtomPath, _visitor: &mut dyn Visitor<'a>) {}
+
+    /// Call the visitor for each child atom, recursively, with mutable access.
+    fn visit_children_mut(&mut self, _path: &mut AtomPath, _visitor: &mut dyn VisitorMut) {}
 }
 
 impl<T: Atom> Encode for T {
@@ -58,19 +69,11 @@ impl<T: Atom> DecodeMaybe for T {
             return Ok(None);
         }
 
-        buf.advance(remaining - peek.remaining());
+        let header_size = remaining - peek.remaining();
+        buf.advance(header_size);
         let body = &mut buf.slice(size);
 
-        let atom = match Self::decode_body(body) {
-            Ok(atom) => atom,
-            Err(Error::OutOfBounds) => return Err(Error::OverDecode(T::KIND)),
-            Err(Error::ShortRead) => return Err(Error::UnderDecode(T::KIND)),
-            Err(err) => return Err(err),
-        };
-
-        if body.has_remaining() {
-            return Err(Error::UnderDecode(T::KIND));
-        }
+        let atom = decode_atom_body(T::KIND, header_size, body, T::decode_body)?;
 
         buf.advance(size);
 
@@ -86,23 +89,13 @@ impl<T: Atom> ReadFrom for T {
 
 impl<T: Atom> ReadFrom for Option<T> {
     fn read_from<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
-        let header = match <Option<Header> as ReadFrom>::read_from(r)? {
+        let (header, header_size) = match Header::read_sized(r)? {
             Some(header) => header,
             None => return Ok(None),
         };
 
         let body = &mut header.read_body(r)?;
-
-        let atom = match T::decode_body(body) {
-            Ok(atom) => atom,
-            Err(Error::OutOfBounds) => return Err(Error::OverDecode(T::KIND)),
-            Err(Error::ShortRead) => return Err(Error::UnderDecode(T::KIND)),
-            Err(err) => return Err(err),
-        };
-
-        if body.has_remaining() {
-            return Err(Error::UnderDecode(T::KIND));
-        }
+        let atom = decode_atom_body(T::KIND, header_size, body, T::decode_body)?;
 
         Ok(Some(atom))
     }
@@ -116,11 +109,19 @@ impl<T: Atom> ReadUntil for T {
 
 impl<T: Atom> ReadUntil for Option<T> {
     fn read_until<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
-        while let Some(header) = <Option<Header> as ReadFrom>::read_from(r)? {
+        // The offset of the current atom from where reading started, for any error.
+        let mut offset = 0;
+
+        while let Some((header, header_size)) = Header::read_sized(r)? {
             if header.kind == T::KIND {
                 let body = &mut header.read_body(r)?;
-                return Ok(Some(T::decode_atom(&header, body)?));
+                let atom = decode_atom_body(T::KIND, header_size, body, T::decode_body)
+                    .map_err(|err| err.offset_by(offset))?;
+                return Ok(Some(atom));
             }
+
+            let size = header.skip_body(r)?;
+            offset += (header_size + size) as u64;
         }
 
         Ok(None)
@@ -139,17 +140,7 @@ impl<T: Atom> DecodeAtom for T {
         }
 
         let body = &mut buf.slice(size);
-
-        let atom = match T::decode_body(body) {
-            Ok(atom) => atom,
-            Err(Error::OutOfBounds) => return Err(Error::OverDecode(T::KIND)),
-            Err(Error::ShortRead) => return Err(Error::UnderDecode(T::KIND)),
-            Err(err) => return Err(err),
-        };
-
-        if body.has_remaining() {
-            return Err(Error::UnderDecode(T::KIND));
-        }
+        let atom = decode_atom_body(T::KIND, header.encoded_size(), body, T::decode_body)?;
 
         buf.advance(size);
 
@@ -168,24 +159,53 @@ impl<T: Atom> ReadAtom for T {
     }
 }
 
+/// Decode an atom body, annotating any error with the atom and how far into the body it got.
+///
+/// The entire body must be consumed.
+pub(crate) fn decode_atom_body<T, B: Buf>(
+    kind: FourCC,
+    header_size: usize,
+    body: &mut B,
+    decode: impl FnOnce(&mut B) -> Result<T>,
+) -> Result<T> {
+    let size = body.remaining();
+
+    let err = match decode(body) {
+        Ok(_) if body.has_remaining() => Error::UnderDecode(kind),
+        Ok(atom) => return Ok(atom),
+        Err(Error::OutOfBounds) => Error::OverDecode(kind),
+        Err(Error::ShortRead) => Error::UnderDecode(kind),
+        Err(err) => err,
+    };
+
+    Err(err.within(kind, header_size, size - body.remaining()))
+}
+
 // A helper for generating nested atoms.
 /* example:
 nested! {
     required: [ Mvhd ],
     optional: [ Meta, Mvex, Udta ],
     multiple: [ Trak ],
+    custom: custom,
 };
+
+Any registered custom atoms are collected in the `custom` field, so they're kept in every container.
+An optional `check: method,` calls a method on the decoded atom to validate combinations of children.
 */
 
 macro_rules! nested {
-    (required: [$($required:ident),*$(,)?], optional: [$($optional:ident),*$(,)?], multiple: [$($multiple:ident),*$(,)?],) => {
+    (required: [$($required:ident),*$(,)?], optional: [$($optional:ident),*$(,)?], multiple: [$($multiple:ident),*$(,)?], custom: $custom:ident, $(check: $check:ident,)?) => {
         pastey::paste! {
             fn decode_body<B: Buf>(buf: &mut B) -> Result<Self> {
                 $( let mut [<$required:lower>] = None;)*
                 $( let mut [<$optional:lower>] = None;)*
                 $( let mut [<$multiple:lower>] = Vec::new();)*
+                let mut $custom = Vec::new();
 
-                while let Some(atom) = Any::decode_maybe(buf)? {
+                while let Some(atom) = Any::decode_maybe(buf).map_err(|err| {
+                    err$(.with_index($multiple::KIND, [<$multiple:lower>].len()))*
+                })? {
                     match atom {
                         $(Any::$required(atom) => {
                             if [<$required:lower>].is_some() {
@@ -202,26 +222,40 @@ macro_rules! nested {
                         $(Any::$multiple(atom) => {
                             [<$multiple:lower>].push(atom.into());
                         },)*
+                        Any::Custom(atom) => $custom.push(atom),
                         Any::Skip(atom) => tracing::debug!(size = atom.zeroed.size, "skipping skip box"),
                         Any::Free(atom) => tracing::debug!(size = atom.zeroed.size, "skipping free box"),
                         unknown => Self::decode_unknown(&unknown)?,
                     }
                 }
 
-                Ok(Self {
+                let atom = Self {
                     $([<$required:lower>]: [<$required:lower>].ok_or(Error::MissingBox($required::KIND))? ,)*
                     $([<$optional:lower>],)*
                     $([<$multiple:lower>],)*
-                })
+                    $custom,
+                };
+
+                $(atom.$check()?;)?
+
+                Ok(atom)
             }
 
             fn encode_body<B: BufMut>(&self, buf: &mut B) -> Result<()> {
                 $( self.[<$required:lower>].encode(buf)?; )*
                 $( self.[<$optional:lower>].encode(buf)?; )*
                 $( self.[<$multiple:lower>].iter().map(|x| x.encode(buf)).collect::<Result<()>>()?; )*
+                self.$custom.iter().map(|x| x.encode(buf)).collect::<Result<()>>()?;
 
                 Ok(())
             }
+
+            crate::visit! {
+                required: [$([<$required:lower>]),*],
+                optional: [$([<$optional:lower>]),*],
+                multiple: [$([<$multiple:lower>]),*],
+                custom: $custom,
+            }
         }
     };
 }
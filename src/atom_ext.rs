@@ -16,10 +16,15 @@ pub(crate) trait AtomExt: Sized {
 
     fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<Self::Ext>;
     fn decode_body_ext<B: Buf>(buf: &mut B, ext: Self::Ext) -> Result<Self>;
+
+    // Only needed for the few full boxes that contain other atoms.
+    fn visit_children_ext<'a>(&'a self, _path: &mut AtomPath, _visitor: &mut dyn Visitor<'a>) {}
+    fn visit_children_mut_ext(&mut self, _path: &mut AtomPath, _visitor: &mut dyn VisitorMut) {}
 }
 
 impl<T: AtomExt> Atom for T {
     const KIND: FourCC = Self::KIND_EXT;
+    const FULL_BOX: bool = true;
 
     fn decode_body<B: Buf>(buf: &mut B) -> Result<Self> {
         let ext = Ext::decode(u32::decode(buf)?)?;
@@ -40,6 +45,14 @@ impl<T: AtomExt> Atom for T {
 
         Ok(())
     }
+
+    fn visit_children<'a>(&'a self, path: &mut AtomPath, visitor: &mut dyn Visitor<'a>) {
+        self.visit_children_ext(path, visitor)
+    }
+
+    fn visit_children_mut(&mut self, path: &mut AtomPath, visitor: &mut dyn VisitorMut) {
+        self.visit_children_mut_ext(path, visitor)
+    }
 }
 
 // Some atoms don't have any version/flags, so we provide a default implementation
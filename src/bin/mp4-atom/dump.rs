@@ -0,0 +1,49 @@
+use std::io::{Read, Seek};
+
+use mp4_atom::{AtomSpan, FourCC, LazyReader};
+
+use crate::extract::read_atom;
+
+/// Print the atom tree with offsets and sizes.
+pub fn run<R: Read + Seek>(r: &mut R) -> anyhow::Result<()> {
+    let mut reader = LazyReader::new(&mut *r)?;
+
+    // Avoid reading large bodies, like mdat, unless they contain children.
+    let mut atoms = Vec::new();
+    for atom in reader.atoms()? {
+        let container = !reader.children(&atom)?.is_empty();
+        atoms.push((atom, container));
+    }
+
+    for (atom, container) in atoms {
+        if !container {
+            print(atom.kind(), atom.offset, atom.size, 0);
+            continue;
+        }
+
+        for span in AtomSpan::scan(&read_atom(r, &atom)?, atom.offset)? {
+            tree(&span, 0);
+        }
+    }
+
+    Ok(())
+}
+
+fn tree(span: &AtomSpan, depth: usize) {
+    print(span.kind, span.offset, span.size(), depth);
+
+    for child in &span.children {
+        tree(child, depth + 1);
+    }
+}
+
+fn print(kind: FourCC, offset: u64, size: u64, depth: usize) {
+    println!(
+        "{:indent$}{} @ {} size {}",
+        "",
+        kind,
+        offset,
+        size,
+        indent = depth * 2
+    );
+}
@@ -0,0 +1,323 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use anyhow::Context;
+use mp4_atom::{
+    Atom, AtomSpan, Copyright, Desc, Encode, FourCC, Hdlr, Ilst, Layout, LazyAtom, LazyReader,
+    Meta, Moof, Moov, Name, Tool, Udta, Year,
+};
+
+use crate::extract::read_atom;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Remove every atom of this kind, either at the top level or anywhere inside the moov.
+    #[arg(long, value_parser = crate::parse_fourcc)]
+    remove: Vec<FourCC>,
+
+    /// Set the title (©nam).
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Set the description (desc).
+    #[arg(long)]
+    description: Option<String>,
+
+    /// Set the copyright (cprt).
+    #[arg(long)]
+    copyright: Option<String>,
+
+    /// Set the year (©day).
+    #[arg(long)]
+    year: Option<String>,
+
+    /// Set the encoding tool (©too).
+    #[arg(long)]
+    tool: Option<String>,
+}
+
+impl Args {
+    fn has_metadata(&self) -> bool {
+        self.name.is_some()
+            || self.description.is_some()
+            || self.copyright.is_some()
+            || self.year.is_some()
+            || self.tool.is_some()
+    }
+}
+
+pub fn run<R: Read + Seek, W: Write>(r: &mut R, w: &mut W, args: &Args) -> anyhow::Result<()> {
+    let spans: Vec<LazyAtom> = LazyReader::new(&mut *r)?
+        .atoms()?
+        .into_iter()
+        .filter(|span| !args.remove.contains(&span.kind()))
+        .collect();
+
+    let mut moov = None;
+    let mut metas = Vec::new();
+    for (index, span) in spans.iter().enumerate() {
+        match span.kind().as_ref() {
+            b"moov" => {
+                let atom = read_atom(r, span)?;
+                let body = strip(
+                    &atom[(span.body - span.offset) as usize..],
+                    span.kind(),
+                    &args.remove,
+                )?;
+
+                let mut decoded = Moov::decode_body(&mut body.as_slice())?;
+                set_metadata(&mut decoded, args);
+                moov = Some((index, decoded));
+            }
+            // The item offsets in a file-level meta are absolute too.
+            b"meta" => metas.push((index, read_atom(r, span)?)),
+            _ => {}
+        }
+    }
+
+    anyhow::ensure!(
+        moov.is_some() || !args.has_metadata(),
+        "can't set metadata without a moov"
+    );
+
+    // The offsets depend on the size of the moov and meta, which depend on the offsets.
+    // Repeat until the layout is stable, which takes at most a few passes.
+    let mut sizes: Vec<u64> = spans.iter().map(|span| span.size).collect();
+
+    for _ in 0..4 {
+        let mut layout = Layout::new();
+        for (span, &size) in spans.iter().zip(&sizes) {
+            layout.push(span.offset, span.size, size);
+        }
+
+        let mut encoded = vec![None; spans.len()];
+
+        if let Some((index, moov)) = &moov {
+            let mut moov = moov.clone();
+            layout
+                .relocate_moov(&mut moov)
+                .context("a chunk offset points into a removed atom")?;
+
+            let mut buf = Vec::new();
+            moov.encode(&mut buf)?;
+            encoded[*index] = Some(buf);
+        }
+
+        for (index, meta) in &metas {
+            let buf = layout
+                .relocate_bytes(meta)
+                .context("an item offset points into a removed atom")?;
+            encoded[*index] = Some(buf);
+        }
+
+        let next: Vec<u64> = sizes
+            .iter()
+            .zip(&encoded)
+            .map(|(&size, buf)| buf.as_ref().map_or(size, |buf| buf.len() as u64))
+            .collect();
+
+        if next == sizes {
+            return write(r, w, &spans, &layout, &encoded);
+        }
+
+        sizes = next;
+    }
+
+    anyhow::bail!("the layout didn't converge")
+}
+
+fn write<R: Read + Seek, W: Write>(
+    r: &mut R,
+    w: &mut W,
+    spans: &[LazyAtom],
+    layout: &Layout,
+    encoded: &[Option<Vec<u8>>],
+) -> anyhow::Result<()> {
+    for (span, encoded) in spans.iter().zip(encoded) {
+        if let Some(buf) = encoded {
+            w.write_all(buf)?;
+            continue;
+        }
+
+        match span.kind().as_ref() {
+            b"moof" => {
+                // Only explicit base offsets are absolute; the rest are relative to the moof.
+                let mut moof: Moof = LazyReader::new(&mut *r)?.decode(span)?;
+                for traf in &mut moof.traf {
+                    if let Some(base) = &mut traf.tfhd.base_data_offset {
+                        *base = layout
+                            .relocate(*base)
+                            .with_context(|| format!("offset {base} points into a removed atom"))?;
+                    }
+                }
+
+                let mut buf = Vec::new();
+                moof.encode(&mut buf)?;
+                w.write_all(&buf)?;
+            }
+            _ => {
+                r.seek(SeekFrom::Start(span.offset))?;
+                let n = std::io::copy(&mut r.take(span.size), w)?;
+                anyhow::ensure!(n == span.size, "{} is truncated", span.kind());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn set_metadata(moov: &mut Moov, args: &Args) {
+    if !args.has_metadata() {
+        return;
+    }
+
+    let udta = moov.udta.get_or_insert_with(Udta::default);
+    let meta = udta.meta.get_or_insert_with(|| Meta {
+        hdlr: Hdlr {
+            handler: b"mdir".into(),
+            name: String::new(),
+        },
+        items: Vec::new(),
+    });
+
+    if meta.get::<Ilst>().is_none() {
+        meta.push(Ilst::default());
+    }
+    let ilst = meta.get_mut::<Ilst>().unwrap();
+
+    if let Some(name) = &args.name {
+        ilst.name = Some(Name(name.clone()));
+    }
+
+    if let Some(text) = &args.description {
+        ilst.desc = Some(Desc {
+            country_indicator: 0,
+            language_indicator: 0,
+            text: text.clone(),
+        });
+    }
+
+    if let Some(text) = &args.copyright {
+        ilst.cprt = Some(Copyright {
+            country_indicator: 0,
+            language_indicator: 0,
+            text: text.clone(),
+        });
+    }
+
+    if let Some(year) = &args.year {
+        ilst.year = Some(Year(year.clone()));
+    }
+
+    if let Some(text) = &args.tool {
+        ilst.ctoo = Some(Tool {
+            country_indicator: 0,
+            language_indicator: 0,
+            text: text.clone(),
+        });
+    }
+}
+
+// Rebuild a container body without the removed children, at any depth.
+fn strip(body: &[u8], kind: FourCC, remove: &[FourCC]) -> anyhow::Result<Vec<u8>> {
+    let skip = match mp4_atom::children_offset(kind, body) {
+        Some(skip) if !remove.is_empty() && skip <= body.len() => skip,
+        _ => return Ok(body.to_vec()),
+    };
+
+    let mut out = body[..skip].to_vec();
+    let children = AtomSpan::scan(&body[skip..], skip as u64)?;
+
+    for child in &children {
+        // Removing an entry would invalidate the entry count.
+        let counted = matches!(kind.as_ref(), b"stsd" | b"dref");
+        if remove.contains(&child.kind) && !counted {
+            continue;
+        }
+
+        // Only containers can change, so anything else is copied with its original header.
+        let start = child.body() as usize;
+        let end = child.end() as usize;
+        if mp4_atom::children_offset(child.kind, &body[start..end]).is_none() {
+            out.extend_from_slice(&body[child.offset as usize..end]);
+            continue;
+        }
+
+        let stripped = strip(&body[start..end], child.kind, remove)?;
+
+        let header = mp4_atom::Header::new(child.kind, Some(stripped.len()));
+        header.encode(&mut out)?;
+        out.extend_from_slice(&stripped);
+    }
+
+    // Keep anything after the last child, like padding.
+    let end = children
+        .last()
+        .map(|child| child.end() as usize)
+        .unwrap_or(skip);
+    out.extend_from_slice(&body[end..]);
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mp4_atom::{Any, DecodeMaybe, Iloc};
+    use std::io::Cursor;
+
+    // The bytes at every chunk and item offset.
+    fn targets(data: &[u8]) -> Vec<&[u8]> {
+        let mut buf = data;
+        let mut targets = Vec::new();
+
+        while let Some(atom) = Any::decode_maybe(&mut buf).unwrap() {
+            match atom {
+                Any::Moov(moov) => {
+                    for trak in &moov.trak {
+                        for offset in trak.mdia.minf.stbl.chunk_offsets() {
+                            targets.push(&data[offset as usize..][..8]);
+                        }
+                    }
+                }
+                Any::Meta(meta) => {
+                    for location in &meta.get::<Iloc>().unwrap().item_locations {
+                        for extent in &location.extents {
+                            let offset = (location.base_offset + extent.offset) as usize;
+                            targets.push(&data[offset..][..extent.length as usize]);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        targets
+    }
+
+    #[test]
+    fn relocate() {
+        let data = include_bytes!("../../test/libavif_anim_q10.avif");
+
+        // Append a free atom to remove, without moving the media data.
+        let mut input = data.to_vec();
+        input.extend_from_slice(&[0, 0, 0, 16, b'f', b'r', b'e', b'e']);
+        input.extend_from_slice(&[0; 8]);
+
+        let args = Args {
+            remove: vec![b"free".into()],
+            name: Some("a title that makes the moov grow".into()),
+            description: None,
+            copyright: None,
+            year: None,
+            tool: None,
+        };
+
+        let mut output = Vec::new();
+        run(&mut Cursor::new(&input), &mut output, &args).unwrap();
+
+        // The moov grows, which moves the media data after it.
+        assert!(output.len() > data.len());
+        assert_eq!(targets(&output), targets(data));
+    }
+}
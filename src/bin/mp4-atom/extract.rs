@@ -0,0 +1,183 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use mp4_atom::{Any, LazyAtom, LazyReader, Meta, Moof, Moov, Trak, Trex};
+
+/// Write the samples of a track in decode order, without any container.
+pub fn track<R: Read + Seek, W: Write>(r: &mut R, w: &mut W, track_id: u32) -> anyhow::Result<()> {
+    let len = r.seek(SeekFrom::End(0))?;
+    let mut reader = LazyReader::new(&mut *r)?;
+    let atoms = reader.atoms()?;
+
+    let moov = atoms
+        .iter()
+        .find(|atom| atom.kind() == b"moov".into())
+        .ok_or_else(|| anyhow::anyhow!("missing moov"))?;
+    let moov: Moov = reader.decode(moov)?;
+
+    let trak = moov
+        .trak
+        .iter()
+        .find(|trak| trak.tkhd.track_id == track_id)
+        .ok_or_else(|| anyhow::anyhow!("unknown track: {track_id}"))?;
+
+    let mut samples = progressive(trak, len)?;
+
+    let trex = moov.mvex.map(|mvex| mvex.trex).unwrap_or_default();
+
+    for atom in atoms.iter().filter(|atom| atom.kind() == b"moof".into()) {
+        let moof: Moof = reader.decode(atom)?;
+        samples.extend(fragmented(&moof, atom.offset, track_id, &trex)?);
+    }
+
+    for (offset, size) in samples {
+        r.seek(SeekFrom::Start(offset))?;
+        let n = std::io::copy(&mut r.take(size), w)?;
+        anyhow::ensure!(n == size, "sample at offset {offset} is truncated");
+    }
+
+    Ok(())
+}
+
+/// Write the data of an item, found in the top-level or moov meta.
+pub fn item<R: Read + Seek, W: Write>(r: &mut R, w: &mut W, item_id: u32) -> anyhow::Result<()> {
+    let mut reader = LazyReader::new(&mut *r)?;
+
+    let mut metas = Vec::new();
+    for atom in reader.atoms()? {
+        match atom.kind().as_ref() {
+            b"meta" => metas.push(reader.decode::<Meta>(&atom)?),
+            b"moov" => metas.extend(reader.decode::<Moov>(&atom)?.meta),
+            _ => {}
+        }
+    }
+
+    for meta in &metas {
+        let location = meta.get::<mp4_atom::Iloc>().and_then(|iloc| {
+            iloc.item_locations
+                .iter()
+                .find(|location| location.item_id == item_id)
+        });
+
+        let Some(location) = location else {
+            continue;
+        };
+
+        for extent in &location.extents {
+            let offset = location.base_offset + extent.offset;
+
+            match location.construction_method {
+                0 => {
+                    let length = match extent.length {
+                        0 => r.seek(SeekFrom::End(0))? - offset,
+                        length => length,
+                    };
+
+                    r.seek(SeekFrom::Start(offset))?;
+                    let n = std::io::copy(&mut r.take(length), w)?;
+                    anyhow::ensure!(n == length, "item {item_id} is truncated");
+                }
+                1 => {
+                    let idat = meta
+                        .items
+                        .iter()
+                        .find_map(|any| match any {
+                            Any::Idat(idat) => Some(idat),
+                            _ => None,
+                        })
+                        .ok_or_else(|| anyhow::anyhow!("missing idat"))?;
+
+                    let start = offset as usize;
+                    let end = match extent.length {
+                        0 => idat.data.len(),
+                        length => start + length as usize,
+                    };
+
+                    let data = idat
+                        .data
+                        .get(start..end)
+                        .ok_or_else(|| anyhow::anyhow!("item {item_id} is outside the idat"))?;
+                    w.write_all(data)?;
+                }
+                method => anyhow::bail!("unsupported construction method: {method}"),
+            }
+        }
+
+        return Ok(());
+    }
+
+    anyhow::bail!("unknown item: {item_id}")
+}
+
+/// Read the header and body of an atom into memory.
+pub fn read_atom<R: Read + Seek>(r: &mut R, atom: &LazyAtom) -> anyhow::Result<Vec<u8>> {
+    r.seek(SeekFrom::Start(atom.offset))?;
+    let mut buf = vec![0; atom.size as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// The offset and size of each sample described by the sample table.
+pub fn progressive(trak: &Trak, len: u64) -> anyhow::Result<Vec<(u64, u64)>> {
+    let stbl = &trak.mdia.minf.stbl;
+
+    // The sample count isn't bounded by the tables, so check it fits in the file before expanding.
+    anyhow::ensure!(
+        stbl.sample_data_size() <= len,
+        "the samples are larger than the file"
+    );
+
+    Ok(stbl
+        .samples()?
+        .iter()
+        .map(|sample| (sample.offset, sample.size as u64))
+        .collect())
+}
+
+/// The offset and size of each sample for the track in a fragment.
+pub fn fragmented(
+    moof: &Moof,
+    moof_offset: u64,
+    track_id: u32,
+    trex: &[Trex],
+) -> anyhow::Result<Vec<(u64, u64)>> {
+    let mut samples = Vec::new();
+
+    // Without an explicit base, each traf continues where the previous one ended.
+    let mut next = moof_offset;
+
+    for traf in &moof.traf {
+        let tfhd = &traf.tfhd;
+        let trex = trex.iter().find(|trex| trex.track_id == tfhd.track_id);
+        let base = match tfhd.base_data_offset {
+            Some(base) => base,
+            None if tfhd.default_base_is_moof => moof_offset,
+            None => next,
+        };
+
+        let mut offset = base;
+        for trun in &traf.trun {
+            if let Some(data_offset) = trun.data_offset {
+                offset = base.checked_add_signed(data_offset as i64).ok_or_else(|| {
+                    anyhow::anyhow!("invalid data offset in track {}", tfhd.track_id)
+                })?;
+            }
+
+            for entry in &trun.entries {
+                let size = entry
+                    .size
+                    .or(tfhd.default_sample_size)
+                    .or(trex.map(|trex| trex.default_sample_size))
+                    .unwrap_or(0) as u64;
+
+                if tfhd.track_id == track_id {
+                    samples.push((offset, size));
+                }
+                offset += size;
+            }
+        }
+
+        next = offset;
+    }
+
+    Ok(samples)
+}
@@ -0,0 +1,131 @@
+//! A command-line tool to inspect and edit MP4 files.
+//!
+//! cargo run --features cli -- <command> <input_file>
+mod dump;
+mod edit;
+mod extract;
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use clap::{Parser, Subcommand};
+use mp4_atom::FourCC;
+
+#[derive(Parser)]
+#[command(name = "mp4-atom", version, about = "Inspect and edit MP4 files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the atom tree with offsets and sizes.
+    Dump { input: PathBuf },
+
+    /// Print every top-level atom as JSON.
+    Json {
+        input: PathBuf,
+
+        /// Reference mdat and unknown bodies larger than this many bytes instead of inlining them.
+        #[arg(long, default_value_t = mp4_atom::Dump::INLINE_LIMIT)]
+        inline_limit: u64,
+    },
+
+    /// Write the samples of a track, or the data of an item, to a file.
+    Extract {
+        input: PathBuf,
+        output: PathBuf,
+
+        /// The track ID to extract.
+        #[arg(long, required_unless_present = "item", conflicts_with = "item")]
+        track: Option<u32>,
+
+        /// The item ID to extract.
+        #[arg(long)]
+        item: Option<u32>,
+    },
+
+    /// Set metadata or remove atoms, writing a new file.
+    Edit {
+        input: PathBuf,
+        output: PathBuf,
+
+        #[command(flatten)]
+        args: edit::Args,
+    },
+
+    /// Move the moov ahead of the media data for progressive playback.
+    Faststart { input: PathBuf, output: PathBuf },
+
+    /// Check the file for structural inconsistencies.
+    Verify { input: PathBuf },
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Dump { input } => dump::run(&mut open(&input)?),
+        Command::Json {
+            input,
+            inline_limit,
+        } => {
+            let dump = mp4_atom::Dump::read(&mut open(&input)?, inline_limit)?;
+            serde_json::to_writer_pretty(std::io::stdout().lock(), &dump)?;
+            println!();
+            Ok(())
+        }
+        Command::Extract {
+            input,
+            output,
+            track,
+            item,
+        } => {
+            let mut input = open(&input)?;
+            let mut output = File::create(output)?;
+            match (track, item) {
+                (Some(track), _) => extract::track(&mut input, &mut output, track),
+                (_, Some(item)) => extract::item(&mut input, &mut output, item),
+                _ => unreachable!("clap requires one of them"),
+            }
+        }
+        Command::Edit {
+            input,
+            output,
+            args,
+        } => edit::run(&mut open(&input)?, &mut File::create(output)?, &args),
+        Command::Faststart { input, output } => {
+            let mut output = BufWriter::new(File::create(output)?);
+            mp4_atom::faststart(&mut open(&input)?, &mut output)?;
+            Ok(output.flush()?)
+        }
+        Command::Verify { input } => {
+            let diagnostics = mp4_atom::validate(&mut open(&input)?)?;
+            for diagnostic in &diagnostics {
+                println!("{diagnostic}");
+            }
+
+            let errors = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == mp4_atom::Severity::Error)
+                .count();
+
+            match errors {
+                0 => Ok(()),
+                n => anyhow::bail!("found {n} error(s)"),
+            }
+        }
+    }
+}
+
+fn open(path: &PathBuf) -> anyhow::Result<BufReader<File>> {
+    Ok(BufReader::new(File::open(path)?))
+}
+
+fn parse_fourcc(s: &str) -> anyhow::Result<FourCC> {
+    Ok(s.parse()?)
+}
@@ -0,0 +1,545 @@
+use std::collections::HashMap;
+
+use crate::*;
+
+/// Check an init segment and the following media segments against the CMAF and MSE byte stream rules.
+///
+/// The init segment contains the `ftyp` and `moov`, and each media segment contains an optional `styp` followed by `moof` and `mdat` pairs.
+/// The CMAF specific rules apply when the init segment has the `cmfc` or `cmf2` brand.
+/// Diagnostics for media segments are prefixed with the index of the segment.
+pub fn check_cmaf<S: AsRef<[u8]>>(init: &[u8], segments: &[S]) -> Vec<Diagnostic> {
+    let mut diagnostics = Diagnostics::default();
+
+    let Some((moov, cmaf)) = check_init(init, &mut diagnostics) else {
+        return diagnostics.0;
+    };
+
+    let mut state = State {
+        moov: &moov,
+        cmaf,
+        sequence: None,
+        tracks: HashMap::new(),
+    };
+
+    for (index, segment) in segments.iter().enumerate() {
+        let mut segment_diagnostics = Diagnostics::default();
+        state.segment(segment.as_ref(), &mut segment_diagnostics);
+
+        for mut diagnostic in segment_diagnostics.0 {
+            diagnostic.message = format!("segment {index}: {}", diagnostic.message);
+            diagnostics.0.push(diagnostic);
+        }
+    }
+
+    diagnostics.0
+}
+
+// Decode every top-level atom, stopping at the first one that fails.
+fn decode(mut buf: &[u8], diagnostics: &mut Diagnostics) -> Vec<Any> {
+    let mut atoms = Vec::new();
+
+    loop {
+        match Any::decode_maybe(&mut buf) {
+            Ok(Some(atom)) => atoms.push(atom),
+            Ok(None) => break,
+            Err(err) => {
+                diagnostics.decode_error(err, AtomPath::new());
+                break;
+            }
+        }
+    }
+
+    atoms
+}
+
+// Returns the moov and whether the CMAF rules apply.
+fn check_init(init: &[u8], diagnostics: &mut Diagnostics) -> Option<(Moov, bool)> {
+    let atoms = decode(init, diagnostics);
+
+    let mut ftyp = None;
+    let mut moov = None;
+
+    for (index, atom) in atoms.into_iter().enumerate() {
+        match atom {
+            Any::Ftyp(atom) if index == 0 => ftyp = Some(atom),
+            Any::Moov(atom) if moov.is_none() => moov = Some(atom),
+            Any::Moov(_) => {
+                diagnostics.error(AtomPath::new().join(Moov::KIND, None), "duplicate moov")
+            }
+            Any::Moof(_) | Any::Mdat(_) => diagnostics.error(
+                AtomPath::new().join(atom.kind(), None),
+                "the init segment contains media",
+            ),
+            _ => {}
+        }
+    }
+
+    let Some(ftyp) = ftyp else {
+        diagnostics.error(AtomPath::new(), "the init segment doesn't start with ftyp");
+        return None;
+    };
+
+    let Some(moov) = moov else {
+        diagnostics.error(AtomPath::new(), "the init segment has no moov");
+        return None;
+    };
+
+    let cmaf = [b"cmfc", b"cmf2"].iter().any(|&brand| {
+        ftyp.major_brand == brand.into() || ftyp.compatible_brands.contains(&brand.into())
+    });
+
+    if !cmaf {
+        diagnostics.warning(
+            AtomPath::new().join(Ftyp::KIND, None),
+            "the brands don't include cmfc or cmf2",
+        );
+    }
+
+    let root = AtomPath::new().join(Moov::KIND, None);
+
+    if cmaf && moov.trak.len() != 1 {
+        diagnostics.error(
+            root.clone(),
+            format!(
+                "a CMAF header has exactly one track, not {}",
+                moov.trak.len()
+            ),
+        );
+    }
+
+    for (index, trak) in moov.trak.iter().enumerate() {
+        let stbl = &trak.mdia.minf.stbl;
+
+        let empty = stbl.stts.entries.is_empty()
+            && stbl.stsc.entries.is_empty()
+            && stbl.chunk_offsets().is_empty()
+            && stbl
+                .ctts
+                .as_ref()
+                .is_none_or(|ctts| ctts.entries.is_empty())
+            && stbl
+                .stss
+                .as_ref()
+                .is_none_or(|stss| stss.entries.is_empty())
+            && stbl.sample_count() == 0;
+
+        if !empty {
+            diagnostics.error(
+                root.join(Trak::KIND, Some(index))
+                    .join(Mdia::KIND, None)
+                    .join(Minf::KIND, None)
+                    .join(Stbl::KIND, None),
+                "the sample table isn't empty",
+            );
+        }
+    }
+
+    match &moov.mvex {
+        None => diagnostics.error(root, "missing mvex"),
+        Some(mvex) => {
+            for trak in &moov.trak {
+                let track_id = trak.tkhd.track_id;
+                if !mvex.trex.iter().any(|trex| trex.track_id == track_id) {
+                    diagnostics.error(
+                        root.join(Mvex::KIND, None),
+                        format!("missing trex for track {track_id}"),
+                    );
+                }
+            }
+        }
+    }
+
+    Some((moov, cmaf))
+}
+
+// The timing of a track, carried between segments.
+struct Track {
+    // The tfdt of the previous fragment.
+    start: u64,
+
+    // The decode time after the last sample of the previous fragment.
+    end: u64,
+}
+
+struct State<'a> {
+    moov: &'a Moov,
+    cmaf: bool,
+    sequence: Option<u32>,
+    tracks: HashMap<u32, Track>,
+}
+
+impl State<'_> {
+    fn segment(&mut self, segment: &[u8], diagnostics: &mut Diagnostics) {
+        let atoms = decode(segment, diagnostics);
+
+        if atoms.is_empty() {
+            diagnostics.error(AtomPath::new(), "the segment is empty");
+            return;
+        }
+
+        let mut moofs = 0;
+        let mut started = Vec::new();
+
+        for (index, atom) in atoms.iter().enumerate() {
+            match atom {
+                Any::Styp(styp) if index == 0 => {
+                    let mut brands =
+                        std::iter::once(&styp.major_brand).chain(&styp.compatible_brands);
+                    if self.cmaf && !brands.any(|brand| brand == &b"cmfs".into()) {
+                        diagnostics.warning(
+                            AtomPath::new().join(Styp::KIND, None),
+                            "the brands don't include cmfs",
+                        );
+                    }
+                }
+                Any::Styp(_) | Any::Ftyp(_) | Any::Moov(_) => diagnostics.error(
+                    AtomPath::new().join(atom.kind(), None),
+                    "unexpected atom in a media segment",
+                ),
+                Any::Moof(moof) => {
+                    let path = AtomPath::new().join(Moof::KIND, Some(moofs));
+                    moofs += 1;
+
+                    if !matches!(atoms.get(index + 1), Some(Any::Mdat(_))) {
+                        diagnostics.error(path.clone(), "the moof isn't followed by an mdat");
+                    }
+
+                    self.fragment(moof, path, &mut started, diagnostics);
+                }
+                Any::Mdat(_)
+                    if !matches!(index.checked_sub(1).map(|i| &atoms[i]), Some(Any::Moof(_))) =>
+                {
+                    diagnostics.error(
+                        AtomPath::new().join(Mdat::KIND, None),
+                        "the mdat doesn't follow a moof",
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        if moofs == 0 {
+            diagnostics.error(AtomPath::new(), "the segment has no moof");
+        }
+    }
+
+    // `started` contains the tracks that already had a sample in this segment.
+    fn fragment(
+        &mut self,
+        moof: &Moof,
+        path: AtomPath,
+        started: &mut Vec<u32>,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let sequence = moof.mfhd.sequence_number;
+        if self.sequence.is_some_and(|previous| sequence <= previous) {
+            diagnostics.warning(
+                path.join(Mfhd::KIND, None),
+                format!("sequence number {sequence} is not increasing"),
+            );
+        }
+        self.sequence = Some(sequence);
+
+        if self.cmaf && moof.traf.len() != 1 {
+            diagnostics.error(
+                path.clone(),
+                format!(
+                    "a CMAF fragment has exactly one traf, not {}",
+                    moof.traf.len()
+                ),
+            );
+        }
+
+        let trex = self
+            .moov
+            .mvex
+            .as_ref()
+            .map(|mvex| mvex.trex.as_slice())
+            .unwrap_or_default();
+
+        let mut seen = Vec::new();
+
+        for (index, traf) in moof.traf.iter().enumerate() {
+            let path = path.join(Traf::KIND, Some(index));
+            let tfhd = &traf.tfhd;
+            let track_id = tfhd.track_id;
+
+            if seen.contains(&track_id) {
+                diagnostics.error(
+                    path.clone(),
+                    format!("more than one traf for track {track_id}"),
+                );
+            }
+            seen.push(track_id);
+
+            if !self
+                .moov
+                .trak
+                .iter()
+                .any(|trak| trak.tkhd.track_id == track_id)
+            {
+                diagnostics.error(
+                    path.join(Tfhd::KIND, None),
+                    format!("track {track_id} isn't in the init segment"),
+                );
+                continue;
+            }
+
+            if !tfhd.default_base_is_moof {
+                diagnostics.error(
+                    path.join(Tfhd::KIND, None),
+                    "default-base-is-moof is not set",
+                );
+            }
+
+            if tfhd.base_data_offset.is_some() {
+                diagnostics.error(path.join(Tfhd::KIND, None), "base-data-offset is present");
+            }
+
+            for (index, trun) in traf.trun.iter().enumerate() {
+                if trun.data_offset.is_none() {
+                    diagnostics.error(path.join(Trun::KIND, Some(index)), "missing data offset");
+                }
+            }
+
+            let trex = trex.iter().find(|trex| trex.track_id == track_id);
+            let mut entries = traf.trun.iter().flat_map(|trun| &trun.entries).peekable();
+
+            if let Some(first) = entries.peek() {
+                let flags = first
+                    .flags
+                    .or(tfhd.default_sample_flags)
+                    .or(trex.map(|trex| trex.default_sample_flags))
+                    .unwrap_or_default();
+
+                if !started.contains(&track_id) && !flags.is_sync() {
+                    diagnostics.error(
+                        path.join(Trun::KIND, Some(0)),
+                        format!(
+                            "the segment doesn't start with a sync sample for track {track_id}"
+                        ),
+                    );
+                }
+
+                started.push(track_id);
+            }
+
+            let duration: u64 = entries
+                .map(|entry| {
+                    entry
+                        .duration
+                        .or(tfhd.default_sample_duration)
+                        .or(trex.map(|trex| trex.default_sample_duration))
+                        .unwrap_or(0) as u64
+                })
+                .sum();
+
+            let Some(tfdt) = &traf.tfdt else {
+                diagnostics.error(path, "missing tfdt");
+                continue;
+            };
+
+            let start = tfdt.base_media_decode_time;
+            let path = path.join(Tfdt::KIND, None);
+
+            match self.tracks.get(&track_id) {
+                Some(previous) if start <= previous.start => {
+                    diagnostics.error(path, format!("decode time {start} is not increasing"))
+                }
+                Some(previous) if start != previous.end => diagnostics.warning(
+                    path,
+                    format!(
+                        "decode time {start} doesn't follow the previous fragment ({})",
+                        previous.end
+                    ),
+                ),
+                _ => {}
+            }
+
+            self.tracks.insert(
+                track_id,
+                Track {
+                    start,
+                    end: start + duration,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init(edit: impl FnOnce(&mut Ftyp, &mut Moov)) -> Vec<u8> {
+        let mp4a = Mp4a {
+            audio: Audio {
+                data_reference_index: 1,
+                channel_count: 2,
+                sample_size: 16,
+                sample_rate: 48000.into(),
+            },
+            esds: Esds::default(),
+            btrt: None,
+            taic: None,
+        };
+
+        let mut ftyp = Ftyp {
+            major_brand: b"cmfc".into(),
+            minor_version: 0,
+            compatible_brands: vec![b"iso6".into(), b"cmfc".into()],
+        };
+
+        let mut moov = Moov {
+            mvhd: Mvhd {
+                timescale: 1000,
+                next_track_id: 2,
+                ..Default::default()
+            },
+            trak: vec![Trak::audio(1, 48000, mp4a)],
+            mvex: Some(Mvex {
+                mehd: None,
+                trex: vec![Trex {
+                    track_id: 1,
+                    default_sample_description_index: 1,
+                    default_sample_duration: 1024,
+                    ..Default::default()
+                }],
+                leva: None,
+                custom: vec![],
+            }),
+            ..Default::default()
+        };
+
+        edit(&mut ftyp, &mut moov);
+
+        let mut buf = Vec::new();
+        ftyp.encode(&mut buf).unwrap();
+        moov.encode(&mut buf).unwrap();
+        buf
+    }
+
+    fn segment(sequence_number: u32, edit: impl FnOnce(&mut Traf)) -> Vec<u8> {
+        let mut traf = Traf {
+            tfhd: Tfhd {
+                track_id: 1,
+                default_base_is_moof: true,
+                ..Default::default()
+            },
+            tfdt: Some(Tfdt {
+                base_media_decode_time: (sequence_number as u64 - 1) * 2048,
+            }),
+            trun: vec![Trun {
+                data_offset: Some(0),
+                entries: vec![
+                    TrunEntry {
+                        size: Some(4),
+                        ..Default::default()
+                    };
+                    2
+                ],
+            }],
+            ..Default::default()
+        };
+
+        edit(&mut traf);
+
+        let styp = Styp {
+            major_brand: b"cmfs".into(),
+            minor_version: 0,
+            compatible_brands: vec![b"cmfs".into()],
+        };
+
+        let moof = Moof {
+            mfhd: Mfhd { sequence_number },
+            traf: vec![traf],
+            custom: vec![],
+        };
+
+        let mut buf = Vec::new();
+        styp.encode(&mut buf).unwrap();
+        moof.encode(&mut buf).unwrap();
+        Mdat { data: vec![0; 8] }.encode(&mut buf).unwrap();
+        buf
+    }
+
+    fn messages(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+        diagnostics.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn valid() {
+        let segments = [segment(1, |_| {}), segment(2, |_| {}), segment(3, |_| {})];
+        assert_eq!(check_cmaf(&init(|_, _| {}), &segments), vec![]);
+    }
+
+    #[test]
+    fn init_segment() {
+        let init = init(|ftyp, moov| {
+            ftyp.major_brand = b"isom".into();
+            ftyp.compatible_brands.clear();
+
+            moov.mvex.as_mut().unwrap().trex.clear();
+            moov.trak[0].mdia.minf.stbl.stts.entries.push(SttsEntry {
+                sample_count: 1,
+                sample_delta: 1024,
+            });
+        });
+
+        assert_eq!(
+            messages(check_cmaf::<&[u8]>(&init, &[])),
+            [
+                "warning: ftyp: the brands don't include cmfc or cmf2",
+                "error: moov/trak[0]/mdia/minf/stbl: the sample table isn't empty",
+                "error: moov/mvex: missing trex for track 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn media_segments() {
+        let segments = [
+            segment(1, |traf| {
+                traf.tfhd.default_base_is_moof = false;
+                traf.trun[0].entries[0].flags = Some(SampleFlags::NON_KEYFRAME);
+            }),
+            segment(2, |traf| traf.tfdt = None),
+            segment(3, |traf| {
+                traf.tfdt.as_mut().unwrap().base_media_decode_time = 0;
+                traf.trun[0].data_offset = None;
+            }),
+            segment(4, |traf| traf.tfhd.track_id = 2),
+        ];
+
+        assert_eq!(
+            messages(check_cmaf(&init(|_, _| {}), &segments)),
+            [
+                "error: moof[0]/traf[0]/tfhd: segment 0: default-base-is-moof is not set",
+                "error: moof[0]/traf[0]/trun[0]: segment 0: the segment doesn't start with a sync sample for track 1",
+                "error: moof[0]/traf[0]: segment 1: missing tfdt",
+                "error: moof[0]/traf[0]/trun[0]: segment 2: missing data offset",
+                "error: moof[0]/traf[0]/tfdt: segment 2: decode time 0 is not increasing",
+                "error: moof[0]/traf[0]/tfhd: segment 3: track 2 isn't in the init segment",
+            ]
+        );
+    }
+
+    #[test]
+    fn timeline() {
+        let segments = [
+            segment(1, |_| {}),
+            // A gap of one frame.
+            segment(2, |traf| {
+                traf.tfdt.as_mut().unwrap().base_media_decode_time += 1024;
+            }),
+        ];
+
+        let diagnostics = check_cmaf(&init(|_, _| {}), &segments);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(
+            diagnostics[0].message,
+            "segment 1: decode time 3072 doesn't follow the previous fragment (2048)"
+        );
+    }
+}
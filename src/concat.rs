@@ -0,0 +1,588 @@
+use std::io::{Read, Seek, Write};
+
+use crate::*;
+
+/// How [concat] lays out the media data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConcatLayout {
+    /// A single `mdat` with the samples of every input.
+    #[default]
+    Single,
+
+    /// An `mdat` for each input.
+    PerInput,
+}
+
+/// Join progressive files with compatible tracks into one continuous file.
+///
+/// The inputs must have the same tracks in the same order, with matching handlers and media timescales.
+/// A sample entry that differs from the previous inputs only by its H.264 or HEVC parameter sets is added to the `stsd`.
+/// Any other difference, like the codec, dimensions or audio format, is an error.
+/// Each track continues where it ended in the previous input, and the edit lists are joined when any input has one.
+///
+/// The output uses the `ftyp`, track IDs and movie timescale of the first input.
+/// Sample groups are kept only when every input describes them identically, while `subs`, `stsh`, `saiz` and `saio` are dropped.
+/// The `sdtp`, `stdp` and `padb` are kept only when every input has them.
+pub fn concat<R: Read + Seek, W: Write + ?Sized>(
+    inputs: &mut [R],
+    w: &mut W,
+    layout: ConcatLayout,
+) -> Result<()> {
+    let mut files = Vec::with_capacity(inputs.len());
+    for r in inputs.iter_mut() {
+        files.push(remux::read(r)?);
+    }
+
+    let Some((ftyp, first)) = files.first() else {
+        return Err(Error::MissingBox(Moov::KIND));
+    };
+
+    let mut moov = first.clone();
+    let movie_timescale = moov.mvhd.timescale;
+    let edited = files
+        .iter()
+        .any(|(_, moov)| moov.trak.iter().any(|trak| trak.edts.is_some()));
+
+    let mut tracks: Vec<Vec<StblSample>> = vec![Vec::new(); moov.trak.len()];
+    let mut edits = vec![Vec::new(); moov.trak.len()];
+    let mut durations = vec![0; moov.trak.len()];
+    let mut groups: Vec<Option<Vec<Sbgp>>> = first
+        .trak
+        .iter()
+        .map(|trak| Some(trak.mdia.minf.stbl.sbgp.clone()))
+        .collect();
+    let mut mdats: Vec<Vec<remux::Chunk>> = Vec::new();
+
+    for (input, (_, other)) in files.iter().enumerate() {
+        if other.trak.len() != moov.trak.len() {
+            return Err(Error::InvalidCombination(
+                "concat inputs have different tracks",
+            ));
+        }
+
+        let mut chunks = Vec::new();
+
+        for (track, (trak, src)) in moov.trak.iter_mut().zip(&other.trak).enumerate() {
+            if trak.mdia.hdlr.handler != src.mdia.hdlr.handler {
+                return Err(Error::InvalidCombination(
+                    "concat inputs have different tracks",
+                ));
+            }
+
+            let media_timescale = src.mdia.mdhd.timescale;
+            if media_timescale != trak.mdia.mdhd.timescale {
+                return Err(Error::InvalidCombination(
+                    "concat inputs have different media timescales",
+                ));
+            }
+
+            if media_timescale == 0 || movie_timescale == 0 || other.mvhd.timescale == 0 {
+                return Err(Error::Unsupported("concat timescale is zero"));
+            }
+
+            // Map each sample entry to an identical one, or add it when only the parameter sets differ.
+            let stsd = &mut trak.mdia.minf.stbl.stsd;
+            let mut indexes = Vec::new();
+            for codec in &src.mdia.minf.stbl.stsd.codecs {
+                let index = match stsd.codecs.iter().position(|existing| existing == codec) {
+                    Some(index) => index,
+                    None => {
+                        compatible(&stsd.codecs, codec)?;
+                        stsd.codecs.push(codec.clone());
+                        stsd.codecs.len() - 1
+                    }
+                };
+                indexes.push(index as u32 + 1);
+            }
+
+            let src_stbl = &src.mdia.minf.stbl;
+            let mut samples = src_stbl.samples()?;
+            for sample in &mut samples {
+                sample.description_index = sample
+                    .description_index
+                    .checked_sub(1)
+                    .and_then(|index| indexes.get(index as usize))
+                    .copied()
+                    .ok_or(Error::InvalidCombination(
+                        "sample description index out of range",
+                    ))?;
+            }
+
+            // The decode time where this input starts in the output.
+            let base: u64 = tracks[track]
+                .iter()
+                .map(|sample| sample.duration as u64)
+                .sum();
+            let media_duration: u64 = samples.iter().map(|sample| sample.duration as u64).sum();
+
+            let entries = match src.edts.as_ref().and_then(|edts| edts.elst.as_ref()) {
+                Some(elst) => {
+                    resolve_edits(elst, other.mvhd.timescale, media_timescale, media_duration)?
+                }
+                None => vec![ElstEntry {
+                    segment_duration: rescale(
+                        media_duration,
+                        media_timescale,
+                        other.mvhd.timescale,
+                    ),
+                    media_time: Some(0),
+                    media_rate: 1.into(),
+                }],
+            };
+
+            for entry in entries {
+                let segment_duration = rescale(
+                    entry.segment_duration,
+                    other.mvhd.timescale,
+                    movie_timescale,
+                );
+                durations[track] += segment_duration;
+
+                edits[track].push(ElstEntry {
+                    segment_duration,
+                    media_time: entry.media_time.map(|time| time + base),
+                    ..entry
+                });
+            }
+
+            // Join the sample groups, as long as every input uses the same descriptions.
+            let same = src_stbl.sgpd == first.trak[track].mdia.minf.stbl.sgpd
+                && groups[track].as_ref().is_some_and(|sbgps| {
+                    sbgps.len() == src_stbl.sbgp.len()
+                        && sbgps.iter().zip(&src_stbl.sbgp).all(|(a, b)| {
+                            a.grouping_type == b.grouping_type
+                                && a.grouping_type_parameter == b.grouping_type_parameter
+                        })
+                });
+
+            match (same, input) {
+                (false, _) => groups[track] = None,
+                (true, 0) => {}
+                (true, _) => {
+                    let sbgps = groups[track].as_mut().unwrap();
+                    for (sbgp, src) in sbgps.iter_mut().zip(&src_stbl.sbgp) {
+                        append_groups(&mut sbgp.entries, &src.entries, tracks[track].len());
+                    }
+                }
+            }
+
+            chunks.extend(remux::chunks(&samples, tracks[track].len(), input, track));
+            tracks[track].extend(samples);
+        }
+
+        chunks.sort_by_key(|chunk| chunk.offset);
+
+        match (layout, mdats.last_mut()) {
+            (ConcatLayout::Single, Some(mdat)) => mdat.extend(chunks),
+            _ => mdats.push(chunks),
+        }
+    }
+
+    for (track, trak) in moov.trak.iter_mut().enumerate() {
+        let samples = &tracks[track];
+        let stbl = &mut trak.mdia.minf.stbl;
+
+        match groups[track].take() {
+            Some(mut sbgps) => {
+                for sbgp in &mut sbgps {
+                    pad_groups(&mut sbgp.entries, samples.len());
+                }
+                stbl.sbgp = sbgps;
+            }
+            None => {
+                stbl.sbgp.clear();
+                stbl.sgpd.clear();
+            }
+        }
+        stbl.subs.clear();
+        stbl.saiz.clear();
+        stbl.saio.clear();
+
+        trak.edts = match edited {
+            true => Some(Edts {
+                elst: Some(Elst {
+                    entries: std::mem::take(&mut edits[track]),
+                }),
+                ..Default::default()
+            }),
+            false => None,
+        };
+
+        trak.tkhd.duration = durations[track];
+        trak.mdia.mdhd.duration = samples.iter().map(|sample| sample.duration as u64).sum();
+    }
+
+    moov.mvhd.duration = durations.iter().copied().max().unwrap_or(0);
+
+    let mut prefix = Vec::new();
+    if let Some(ftyp) = ftyp {
+        ftyp.encode(&mut prefix)?;
+    }
+
+    remux::write(inputs, w, &prefix, &mut moov, &tracks, &mdats)
+}
+
+// Check a new sample entry only differs from an existing one by its parameter sets (avcC or hvcC).
+fn compatible(codecs: &[Codec], codec: &Codec) -> Result<()> {
+    let existing = codecs
+        .iter()
+        .find(|existing| existing.kind() == codec.kind())
+        .ok_or(Error::InvalidCombination(
+            "concat inputs have different codecs",
+        ))?;
+
+    if let (Some(a), Some(b)) = (visual(existing), visual(codec)) {
+        if (a.width, a.height) != (b.width, b.height) {
+            return Err(Error::InvalidCombination(
+                "concat inputs have different dimensions",
+            ));
+        }
+    }
+
+    if let (Some(a), Some(b)) = (audio(existing), audio(codec)) {
+        if (a.channel_count, a.sample_rate) != (b.channel_count, b.sample_rate) {
+            return Err(Error::InvalidCombination(
+                "concat inputs have different audio formats",
+            ));
+        }
+    }
+
+    let parameter_sets = codecs.iter().any(|existing| match (existing, codec) {
+        (Codec::Avc1(a), Codec::Avc1(b)) => {
+            *a == Avc1 {
+                avcc: a.avcc.clone(),
+                ..b.clone()
+            }
+        }
+        (Codec::Hev1(a), Codec::Hev1(b)) => {
+            *a == Hev1 {
+                hvcc: a.hvcc.clone(),
+                ..b.clone()
+            }
+        }
+        (Codec::Hvc1(a), Codec::Hvc1(b)) => {
+            *a == Hvc1 {
+                hvcc: a.hvcc.clone(),
+                ..b.clone()
+            }
+        }
+        _ => false,
+    });
+
+    match parameter_sets {
+        true => Ok(()),
+        false => Err(Error::InvalidCombination(
+            "concat inputs have different sample entries",
+        )),
+    }
+}
+
+fn visual(codec: &Codec) -> Option<&Visual> {
+    match codec {
+        Codec::Avc1(codec) => Some(&codec.visual),
+        Codec::Hev1(codec) => Some(&codec.visual),
+        Codec::Hvc1(codec) => Some(&codec.visual),
+        Codec::Vp08(codec) => Some(&codec.visual),
+        Codec::Vp09(codec) => Some(&codec.visual),
+        Codec::Av01(codec) => Some(&codec.visual),
+        Codec::Uncv(codec) => Some(&codec.visual),
+        _ => None,
+    }
+}
+
+fn audio(codec: &Codec) -> Option<&Audio> {
+    match codec {
+        Codec::Mp4a(codec) => Some(&codec.audio),
+        Codec::Opus(codec) => Some(&codec.audio),
+        Codec::Flac(codec) => Some(&codec.audio),
+        Codec::Ac3(codec) => Some(&codec.audio),
+        Codec::Eac3(codec) => Some(&codec.audio),
+        _ => None,
+    }
+}
+
+// Spell out the duration of an open-ended last edit, as another input follows it in the output.
+fn resolve_edits(
+    elst: &Elst,
+    movie_timescale: u32,
+    media_timescale: u32,
+    media_duration: u64,
+) -> Result<Vec<ElstEntry>> {
+    let timeline = Timeline::new(elst, movie_timescale, media_timescale)?;
+    let mut entries = elst.entries.clone();
+
+    let (Some(entry), Some(segment)) = (entries.last_mut(), timeline.segments().last()) else {
+        return Ok(entries);
+    };
+
+    if segment.duration.is_some() {
+        return Ok(entries);
+    }
+
+    if segment.media_rate <= 0 {
+        return Err(Error::Unsupported(
+            "concat open-ended edit without a forward rate",
+        ));
+    }
+
+    // The rest of the media, converted to presentation time at the edit's rate.
+    let remaining = media_duration.saturating_sub(segment.media_time.unwrap_or(0)) as u128;
+    let remaining = (remaining << 16).div_ceil(segment.media_rate as u128) as u64;
+
+    // Convert the boundaries rather than the duration, matching how the timeline was built.
+    let start = rescale(segment.start, media_timescale, movie_timescale);
+    let end = rescale(segment.start + remaining, media_timescale, movie_timescale);
+    entry.segment_duration = end - start;
+
+    Ok(entries)
+}
+
+// Append the group assignments of the next input, after covering the samples so far.
+fn append_groups(entries: &mut Vec<SbgpEntry>, next: &[SbgpEntry], count: usize) {
+    pad_groups(entries, count);
+    entries.extend(next.iter().cloned());
+}
+
+// Explicitly mark any samples that aren't covered as not belonging to a group.
+fn pad_groups(entries: &mut Vec<SbgpEntry>, count: usize) {
+    let covered: usize = entries
+        .iter()
+        .map(|entry| entry.sample_count as usize)
+        .sum();
+
+    if covered < count {
+        entries.push(SbgpEntry {
+            sample_count: (count - covered) as u32,
+            group_description_index: 0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test::{progressive, samples};
+    use std::io::Cursor;
+
+    fn join(inputs: &[Vec<u8>], layout: ConcatLayout) -> Result<Vec<u8>> {
+        let mut inputs: Vec<_> = inputs.iter().map(Cursor::new).collect();
+        let mut output = Vec::new();
+        concat(&mut inputs, &mut output, layout)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn join_files() {
+        let output = join(
+            &[progressive(0, |_| {}), progressive(1, |_| {})],
+            ConcatLayout::Single,
+        )
+        .unwrap();
+
+        let (moov, samples) = samples(&output);
+        assert_eq!(moov.mvhd.duration, 2400);
+
+        for (track, trak) in moov.trak.iter().enumerate() {
+            let expected: Vec<_> = (0..2)
+                .flat_map(|tag| (0..60).map(move |i| [tag, track as u8, i, 0]))
+                .collect();
+            assert_eq!(samples[track], expected);
+
+            assert_eq!(trak.edts, None);
+            assert_eq!(trak.tkhd.duration, 2400);
+            assert_eq!(trak.mdia.mdhd.duration, 120);
+
+            let stbl = &trak.mdia.minf.stbl;
+            assert_eq!(stbl.stsd.codecs.len(), 1);
+            assert_eq!(stbl.stss.as_ref().unwrap().entries.len(), 12);
+        }
+
+        let mdats = decode_all(&output)
+            .iter()
+            .filter(|atom| atom.kind() == Mdat::KIND)
+            .count();
+        assert_eq!(mdats, 1);
+    }
+
+    #[test]
+    fn sample_entries() {
+        let avc1 = |sps: u8| {
+            move |moov: &mut Moov| {
+                let codec = &mut moov.trak[0].mdia.minf.stbl.stsd.codecs[0];
+                if let Codec::Uncv(uncv) = codec {
+                    *codec = Codec::Avc1(Avc1 {
+                        visual: uncv.visual.clone(),
+                        avcc: Avcc {
+                            configuration_version: 1,
+                            length_size: 4,
+                            sequence_parameter_sets: vec![vec![0x67, 0x64, 0x00, sps]],
+                            picture_parameter_sets: vec![vec![0x68]],
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    });
+                }
+            }
+        };
+
+        // New parameter sets get their own sample entry.
+        let output = join(
+            &[progressive(0, avc1(30)), progressive(1, avc1(31))],
+            ConcatLayout::PerInput,
+        )
+        .unwrap();
+
+        let (moov, _) = samples(&output);
+
+        let stbl = &moov.trak[0].mdia.minf.stbl;
+        assert_eq!(stbl.stsd.codecs.len(), 2);
+
+        let indexes: Vec<u32> = stbl
+            .samples()
+            .unwrap()
+            .iter()
+            .map(|sample| sample.description_index)
+            .collect();
+        assert_eq!(indexes[..60], [1; 60]);
+        assert_eq!(indexes[60..], [2; 60]);
+
+        assert_eq!(moov.trak[1].mdia.minf.stbl.stsd.codecs.len(), 1);
+
+        let mdats = decode_all(&output)
+            .iter()
+            .filter(|atom| atom.kind() == Mdat::KIND)
+            .count();
+        assert_eq!(mdats, 2);
+
+        // Any other difference can't be joined.
+        let wider = progressive(1, |moov| {
+            if let Codec::Uncv(uncv) = &mut moov.trak[0].mdia.minf.stbl.stsd.codecs[0] {
+                uncv.visual.width *= 2;
+            }
+        });
+
+        let err = join(&[progressive(0, |_| {}), wider], ConcatLayout::Single).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidCombination("concat inputs have different dimensions")
+        ));
+
+        let err = join(
+            &[progressive(0, |_| {}), progressive(1, avc1(30))],
+            ConcatLayout::Single,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidCombination("concat inputs have different codecs")
+        ));
+
+        let err = join(
+            &[
+                progressive(0, avc1(30)),
+                progressive(1, |moov| {
+                    avc1(31)(moov);
+                    if let Codec::Avc1(avc1) = &mut moov.trak[0].mdia.minf.stbl.stsd.codecs[0] {
+                        avc1.pasp = Some(Pasp {
+                            h_spacing: 4,
+                            v_spacing: 3,
+                        });
+                    }
+                }),
+            ],
+            ConcatLayout::Single,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidCombination("concat inputs have different sample entries")
+        ));
+    }
+
+    #[test]
+    fn edits() {
+        // The first input skips the first 5 frames, the second plays as-is.
+        let first = progressive(0, |moov| {
+            for trak in &mut moov.trak {
+                trak.edts = Some(Edts {
+                    elst: Some(Elst {
+                        entries: vec![ElstEntry {
+                            segment_duration: 1100,
+                            media_time: Some(5),
+                            media_rate: 1.into(),
+                        }],
+                    }),
+                    custom: vec![],
+                });
+            }
+        });
+
+        let expected = vec![
+            ElstEntry {
+                segment_duration: 1100,
+                media_time: Some(5),
+                media_rate: 1.into(),
+            },
+            ElstEntry {
+                segment_duration: 1200,
+                media_time: Some(60),
+                media_rate: 1.into(),
+            },
+        ];
+
+        let output = join(&[first, progressive(1, |_| {})], ConcatLayout::Single).unwrap();
+        let (moov, _) = samples(&output);
+
+        let trak = &moov.trak[0];
+        assert_eq!(trak.tkhd.duration, 2300);
+        assert_eq!(
+            trak.edts.as_ref().unwrap().elst.as_ref().unwrap().entries,
+            expected
+        );
+
+        // An open-ended edit plays the rest of the first input, rather than hiding it.
+        let open = progressive(0, |moov| {
+            for trak in &mut moov.trak {
+                trak.edts = Some(Edts {
+                    elst: Some(Elst {
+                        entries: vec![ElstEntry {
+                            segment_duration: 0,
+                            media_time: Some(5),
+                            media_rate: 1.into(),
+                        }],
+                    }),
+                    custom: vec![],
+                });
+            }
+        });
+
+        let output = join(&[open, progressive(1, |_| {})], ConcatLayout::Single).unwrap();
+        let (moov, _) = samples(&output);
+
+        let trak = &moov.trak[0];
+        assert_eq!(trak.tkhd.duration, 2300);
+        assert_eq!(
+            trak.edts.as_ref().unwrap().elst.as_ref().unwrap().entries,
+            expected
+        );
+    }
+
+    #[test]
+    fn incompatible() {
+        let second = progressive(1, |moov| {
+            moov.trak.pop();
+        });
+
+        let err = join(&[progressive(0, |_| {}), second], ConcatLayout::Single).unwrap_err();
+        assert!(matches!(err, Error::InvalidCombination(_)));
+    }
+
+    fn decode_all(mut buf: &[u8]) -> Vec<Any> {
+        let mut atoms = Vec::new();
+        while let Some(atom) = Any::decode_maybe(&mut buf).unwrap() {
+            atoms.push(atom);
+        }
+        atoms
+    }
+}
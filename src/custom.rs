@@ -0,0 +1,347 @@
+use std::fmt;
+
+use crate::*;
+
+/// A trait for user-defined atoms that can be decoded through [Any].
+///
+/// This is implemented for any [Atom] that is also [Clone], [PartialEq] and [fmt::Debug].
+/// Register the type with [Custom::register] and it will be returned as [Any::Custom] instead of [Any::Unknown].
+pub trait CustomAtom: Atom + Clone + PartialEq + fmt::Debug + Send + Sync + 'static {}
+
+impl<T: Atom + Clone + PartialEq + fmt::Debug + Send + Sync + 'static> CustomAtom for T {}
+
+// Stores a CustomAtom as an Erased, which encodes the body.
+#[derive(Clone, PartialEq)]
+struct Typed<T>(T);
+
+impl<T: CustomAtom> ErasedValue for Typed<T> {
+    fn kind(&self) -> FourCC {
+        T::KIND
+    }
+
+    fn encode(&self, mut buf: &mut dyn BufMut) -> Result<()> {
+        self.0.encode_body(&mut buf)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Typed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+type Decoder = fn(&mut dyn Buf) -> Result<Custom>;
+
+// The decoder for each registered kind, and whether it's a full box.
+static REGISTRY: Registry<(Decoder, bool)> = Registry::new();
+
+fn decode_custom<T: CustomAtom>(mut buf: &mut dyn Buf) -> Result<Custom> {
+    Ok(Custom::new(T::decode_body(&mut buf)?))
+}
+
+/// A user-defined atom, registered with [Custom::register].
+///
+/// Use [Custom::downcast_ref] to get the typed atom back.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Custom {
+    atom: Erased,
+    full_box: bool,
+}
+
+impl Custom {
+    /// Decode atoms of this kind as `T` from now on, for the whole process.
+    ///
+    /// Only kinds that this library doesn't support are looked up, so built-in atoms can't be replaced.
+    /// Registering the same kind again replaces the previous type.
+    pub fn register<T: CustomAtom>() {
+        REGISTRY.insert(T::KIND, (decode_custom::<T>, T::FULL_BOX));
+    }
+
+    /// Stop decoding atoms of this kind, returning true if it was registered.
+    pub fn unregister(kind: FourCC) -> bool {
+        REGISTRY.remove(kind)
+    }
+
+    /// Returns true if the kind has been registered.
+    pub fn is_registered(kind: FourCC) -> bool {
+        REGISTRY.get(kind).is_some()
+    }
+
+    pub fn new<T: CustomAtom>(atom: T) -> Self {
+        Self {
+            atom: Erased::new(Typed(atom)),
+            full_box: T::FULL_BOX,
+        }
+    }
+
+    pub fn kind(&self) -> FourCC {
+        self.atom.kind()
+    }
+
+    pub fn is_full_box(&self) -> bool {
+        self.full_box
+    }
+
+    pub fn is<T: CustomAtom>(&self) -> bool {
+        self.atom.is::<Typed<T>>()
+    }
+
+    pub fn downcast_ref<T: CustomAtom>(&self) -> Option<&T> {
+        self.atom.downcast_ref().map(|typed: &Typed<T>| &typed.0)
+    }
+
+    pub fn downcast_mut<T: CustomAtom>(&mut self) -> Option<&mut T> {
+        self.atom
+            .downcast_mut()
+            .map(|typed: &mut Typed<T>| &mut typed.0)
+    }
+
+    /// Returns the typed atom, or self if it's a different type.
+    pub fn downcast<T: CustomAtom>(self) -> std::result::Result<T, Self> {
+        let full_box = self.full_box;
+        match self.atom.downcast::<Typed<T>>() {
+            Ok(typed) => Ok(typed.0),
+            Err(atom) => Err(Self { atom, full_box }),
+        }
+    }
+
+    pub(crate) fn encode_body<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        self.atom.encode(buf)
+    }
+
+    // Returns the function used to decode the body, if the kind is registered.
+    pub(crate) fn decoder(kind: FourCC) -> Option<Decoder> {
+        REGISTRY.get(kind).map(|entry| entry.0)
+    }
+
+    // Returns true if the registered kind is a full box, or None if it's not registered.
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_full_box_kind(kind: FourCC) -> Option<bool> {
+        REGISTRY.get(kind).map(|entry| entry.1)
+    }
+}
+
+impl Encode for Custom {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        let start = buf.len();
+
+        0u32.encode(buf)?;
+        self.kind().encode(buf)?;
+        self.encode_body(buf)?;
+
+        let size: u32 = (buf.len() - start)
+            .try_into()
+            .map_err(|_| Error::TooLarge(self.kind()))?;
+        buf.set_slice(start, &size.to_be_bytes());
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Custom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.atom.fmt(f)
+    }
+}
+
+impl From<Custom> for Any {
+    fn from(custom: Custom) -> Self {
+        Any::Custom(custom)
+    }
+}
+
+/// Serialized as the kind and the encoded body, as the type isn't known when deserializing.
+///
+/// The body is decoded with the registered type, if any.
+/// Otherwise it's kept as bytes and encoded as-is, although it can't be downcast.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Custom {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.atom
+            .raw()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Custom {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let raw = Raw::deserialize(deserializer)?;
+
+        match Custom::decoder(raw.kind) {
+            Some(decode) => {
+                decode_atom_body(raw.kind, 8, &mut raw.body.as_slice(), |body| decode(body))
+                    .map_err(serde::de::Error::custom)
+            }
+            None => Ok(Self {
+                atom: Erased::new(raw),
+                full_box: false,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Xcam {
+        serial: u32,
+        model: String,
+    }
+
+    impl Atom for Xcam {
+        const KIND: FourCC = FourCC::new(b"xcam");
+
+        fn decode_body<B: Buf>(buf: &mut B) -> Result<Self> {
+            Ok(Self {
+                serial: u32::decode(buf)?,
+                model: String::decode(buf)?,
+            })
+        }
+
+        fn encode_body<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+            self.serial.encode(buf)?;
+            self.model.as_str().encode(buf)
+        }
+    }
+
+    fn xcam() -> Xcam {
+        Xcam {
+            serial: 1234,
+            model: "X1".into(),
+        }
+    }
+
+    // The registry is global, so tests that use it take turns and unregister when done, even on panic.
+    struct Registered {
+        _lock: std::sync::MutexGuard<'static, ()>,
+    }
+
+    impl Registered {
+        fn new() -> Self {
+            static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+            let lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+            Custom::register::<Xcam>();
+            Self { _lock: lock }
+        }
+    }
+
+    impl Drop for Registered {
+        fn drop(&mut self) {
+            Custom::unregister(Xcam::KIND);
+        }
+    }
+
+    #[test]
+    fn decode_registered() {
+        let _registered = Registered::new();
+
+        let mut buf = Vec::new();
+        xcam().encode(&mut buf).unwrap();
+
+        let any = Any::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(any.kind(), Xcam::KIND);
+
+        let Any::Custom(custom) = &any else {
+            panic!("expected a custom atom: {any:?}");
+        };
+        assert_eq!(custom.downcast_ref::<Xcam>(), Some(&xcam()));
+
+        let mut encoded = Vec::new();
+        any.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, buf);
+    }
+
+    #[test]
+    fn nested_registered() {
+        let _registered = Registered::new();
+
+        let udta = Udta {
+            custom: vec![Custom::new(xcam())],
+            ..Default::default()
+        };
+        udta.assert_encode_decode();
+
+        let traf = Traf {
+            custom: vec![Custom::new(xcam())],
+            ..Default::default()
+        };
+        traf.assert_encode_decode();
+
+        let moov = Moov {
+            custom: vec![Custom::new(xcam())],
+            ..Default::default()
+        };
+        moov.assert_encode_decode();
+
+        // Every container keeps them, not just the ones commonly used for metadata.
+        let moof = Moof {
+            custom: vec![Custom::new(xcam())],
+            ..Default::default()
+        };
+        moof.assert_encode_decode();
+
+        let stbl = Stbl {
+            custom: vec![Custom::new(xcam())],
+            ..Default::default()
+        };
+        stbl.assert_encode_decode();
+    }
+
+    #[test]
+    fn unregistered() {
+        let registered = Registered::new();
+        drop(registered);
+
+        let mut buf = Vec::new();
+        xcam().encode(&mut buf).unwrap();
+
+        let any = Any::decode(&mut buf.as_slice()).unwrap();
+        assert!(matches!(any, Any::Unknown(kind, _) if kind == Xcam::KIND));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let _registered = Registered::new();
+
+        let moov = Moov {
+            custom: vec![Custom::new(xcam())],
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&moov).unwrap();
+        let decoded: Moov = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.custom[0].downcast_ref::<Xcam>(), Some(&xcam()));
+
+        // Without the type, the body is kept as bytes and encoded as-is.
+        Custom::unregister(Xcam::KIND);
+        let raw: Moov = serde_json::from_str(&json).unwrap();
+        assert!(!raw.custom[0].is::<Xcam>());
+
+        let mut expected = Vec::new();
+        moov.encode(&mut expected).unwrap();
+
+        let mut encoded = Vec::new();
+        raw.encode(&mut encoded).unwrap();
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn downcast() {
+        let custom = Custom::new(xcam());
+        assert!(custom.is::<Xcam>());
+
+        let custom = custom.downcast::<Xcam>().unwrap();
+        assert_eq!(custom, xcam());
+    }
+}
@@ -0,0 +1,332 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// A serializable representation of a whole file, used to dump it to JSON (or any serde format) and back.
+///
+/// Each top-level atom is decoded into [Any], so it can be inspected and edited by hand.
+/// [Mdat] and unknown atoms larger than the inline limit are not decoded.
+/// Instead they reference a byte range in the source file, which is copied when writing.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Dump {
+    pub atoms: Vec<DumpAtom>,
+}
+
+/// A top-level atom in a [Dump].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpAtom {
+    pub kind: FourCC,
+
+    /// The position of the atom in the source file, including the header.
+    /// This is informational and ignored when writing.
+    pub offset: u64,
+
+    /// The size of the atom in the source file, including the header.
+    /// This is informational and ignored when writing.
+    pub size: u64,
+
+    /// The version, if the atom is a full box.
+    /// This is informational; the encoder picks the version based on the contents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u8>,
+
+    /// The flags, if the atom is a full box.
+    /// This is informational; the encoder picks the flags based on the contents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u32>,
+
+    /// The version and flags of every full box within the atom, in the order they appear.
+    /// This is informational; the encoder picks them based on the contents.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub full_boxes: Vec<DumpFullBox>,
+
+    pub body: DumpBody,
+}
+
+/// A full box nested within a [DumpAtom].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpFullBox {
+    /// The path from the top-level atom, ex. `moov/trak[1]/tkhd`.
+    pub path: String,
+    pub version: u8,
+    pub flags: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DumpBody {
+    /// The decoded atom.
+    Atom(Box<Any>),
+
+    /// The body was elided; it's copied from this range of the source file when writing.
+    Source { offset: u64, size: u64 },
+}
+
+impl Dump {
+    /// The default maximum size of a [Mdat] or unknown body that is included in the dump.
+    pub const INLINE_LIMIT: u64 = 1024;
+
+    /// Read every top-level atom until EOF.
+    ///
+    /// [Mdat] and unknown atoms with a body larger than `inline_limit` are referenced by offset instead.
+    pub fn read<R: Read + Seek>(r: &mut R, inline_limit: u64) -> Result<Self> {
+        let mut atoms = Vec::new();
+
+        loop {
+            let offset = r.stream_position()?;
+            let header = match <Option<Header> as ReadFrom>::read_from(r)? {
+                Some(header) => header,
+                None => break,
+            };
+
+            let body_offset = r.stream_position()?;
+            let body_size = match header.size {
+                Some(size) => size as u64,
+                None => r.seek(SeekFrom::End(0))? - body_offset,
+            };
+            r.seek(SeekFrom::Start(body_offset))?;
+
+            let size = body_offset - offset + body_size;
+            let source = DumpBody::Source {
+                offset: body_offset,
+                size: body_size,
+            };
+
+            // Check the size before reading, so a large body is never loaded into memory.
+            let unknown = Any::is_full_box_kind(header.kind).is_none();
+            if (header.kind == Mdat::KIND || unknown) && body_size > inline_limit {
+                r.seek(SeekFrom::Current(body_size as i64))?;
+                atoms.push(DumpAtom::new(header.kind, offset, size, source));
+                continue;
+            }
+
+            let body = header.read_body(r)?;
+            let header_size = (body_offset - offset) as usize;
+            let any = Any::decode_sized(&header, header_size, &mut body.get_ref().as_slice())
+                .map_err(|err| err.offset_by(offset))?;
+
+            let full_box = any.is_full_box();
+            let mut atom = DumpAtom::new(header.kind, offset, size, DumpBody::Atom(any.into()));
+
+            if full_box && body.get_ref().len() >= 4 {
+                let ext = u32::from_be_bytes(body.get_ref()[..4].try_into().unwrap());
+                atom.version = Some((ext >> 24) as u8);
+                atom.flags = Some(ext & 0x00ff_ffff);
+            }
+
+            let path = AtomPath::new().join(header.kind, None);
+            atom.full_boxes = full_boxes(header.kind, body.get_ref(), &path);
+
+            atoms.push(atom);
+        }
+
+        Ok(Self { atoms })
+    }
+
+    /// Encode every atom, copying any referenced bodies from the source file.
+    pub fn write<W: Write, R: Read + Seek>(&self, w: &mut W, source: &mut R) -> Result<()> {
+        for atom in &self.atoms {
+            match &atom.body {
+                DumpBody::Atom(any) => WriteTo::write_to(any.as_ref(), w)?,
+                DumpBody::Source { offset, size } => {
+                    let header = Header::new(atom.kind, Some(*size as usize));
+                    WriteTo::write_to(&header, w)?;
+
+                    source.seek(SeekFrom::Start(*offset))?;
+                    let n = std::io::copy(&mut source.take(*size), w)?;
+                    if n != *size {
+                        return Err(Error::OutOfBounds);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DumpAtom {
+    fn new(kind: FourCC, offset: u64, size: u64, body: DumpBody) -> Self {
+        Self {
+            kind,
+            offset,
+            size,
+            version: None,
+            flags: None,
+            full_boxes: Vec::new(),
+            body,
+        }
+    }
+}
+
+// Read the version and flags of every full box within the body, recursing into known containers.
+fn full_boxes(kind: FourCC, body: &[u8], path: &AtomPath) -> Vec<DumpFullBox> {
+    let skip = match children_offset(kind, body) {
+        Some(skip) if skip <= body.len() => skip,
+        _ => return Vec::new(),
+    };
+
+    // Anything that doesn't parse cleanly is skipped, as the decoder has already accepted it.
+    let spans = AtomSpan::scan(&body[skip..], skip as u64).unwrap_or_default();
+    let mut entries = Vec::new();
+
+    for (i, span) in spans.iter().enumerate() {
+        // Only index kinds that appear more than once; either resolves with [AtomSpan::get].
+        let index = match spans.iter().filter(|other| other.kind == span.kind).count() {
+            1 => None,
+            _ => Some(
+                spans[..i]
+                    .iter()
+                    .filter(|other| other.kind == span.kind)
+                    .count(),
+            ),
+        };
+
+        let path = path.join(span.kind, index);
+        let child = &body[span.body() as usize..span.end() as usize];
+
+        let full_box = match span.kind.as_ref() {
+            // QuickTime uses a regular box, ISO uses a full box.
+            b"meta" => children_offset(span.kind, child) == Some(4),
+            _ => Any::is_full_box_kind(span.kind).unwrap_or(false),
+        };
+
+        if full_box && child.len() >= 4 {
+            let ext = u32::from_be_bytes(child[..4].try_into().unwrap());
+            entries.push(DumpFullBox {
+                path: path.to_string(),
+                version: (ext >> 24) as u8,
+                flags: ext & 0x00ff_ffff,
+            });
+        }
+
+        entries.extend(full_boxes(span.kind, child, &path));
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    const ENCODED: &[u8] = include_bytes!("test/bbb.mp4");
+
+    #[test]
+    fn json_round_trip() {
+        // The test file is tiny, so reference every mdat.
+        let dump = Dump::read(&mut Cursor::new(ENCODED), 0).unwrap();
+
+        let kinds: Vec<_> = dump.atoms.iter().map(|atom| atom.kind).collect();
+        assert!(kinds.contains(&Moov::KIND));
+
+        let mdat = dump
+            .atoms
+            .iter()
+            .find(|atom| atom.kind == Mdat::KIND)
+            .unwrap();
+        assert!(matches!(mdat.body, DumpBody::Source { .. }));
+
+        let json = serde_json::to_string_pretty(&dump).unwrap();
+        assert!(json.contains(r#""kind": "moov""#));
+        assert!(json.contains(r#""major_brand": "iso6""#));
+
+        let loaded: Dump = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, dump);
+
+        let mut output = Vec::new();
+        loaded
+            .write(&mut output, &mut Cursor::new(ENCODED))
+            .unwrap();
+
+        // The encoder may pick different versions, so compare the decoded atoms instead of the bytes.
+        let reloaded = Dump::read(&mut Cursor::new(&output), 0).unwrap();
+        assert_eq!(reloaded.atoms.len(), dump.atoms.len());
+
+        for (a, b) in reloaded.atoms.iter().zip(dump.atoms.iter()) {
+            match (&a.body, &b.body) {
+                (DumpBody::Atom(a), DumpBody::Atom(b)) => assert_eq!(a, b),
+                (DumpBody::Source { size: a, .. }, DumpBody::Source { size: b, .. }) => {
+                    assert_eq!(a, b)
+                }
+                _ => panic!("mismatched bodies: {a:?} {b:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn version_flags() {
+        let sidx = Sidx {
+            reference_id: 1,
+            timescale: 1000,
+            earliest_presentation_time: u64::MAX,
+            first_offset: 0,
+            references: vec![],
+        };
+
+        let mut buf = Vec::new();
+        sidx.encode(&mut buf).unwrap();
+
+        let dump = Dump::read(&mut Cursor::new(&buf), Dump::INLINE_LIMIT).unwrap();
+        assert_eq!(dump.atoms[0].version, Some(1));
+        assert_eq!(dump.atoms[0].flags, Some(0));
+
+        // Nested full boxes are listed by path.
+        let dump = Dump::read(&mut Cursor::new(ENCODED), 0).unwrap();
+        let moov = dump
+            .atoms
+            .iter()
+            .find(|atom| atom.kind == Moov::KIND)
+            .unwrap();
+
+        let mvhd = &moov.full_boxes[0];
+        assert_eq!(mvhd.path, "moov/mvhd");
+        assert_eq!(mvhd.version, 0);
+
+        let tkhd = moov
+            .full_boxes
+            .iter()
+            .find(|entry| entry.path.ends_with("/tkhd"))
+            .unwrap();
+        assert_eq!(tkhd.flags, 3);
+        assert!(moov
+            .full_boxes
+            .iter()
+            .any(|entry| entry.path.ends_with("/stsd")));
+    }
+
+    #[test]
+    fn unknown() {
+        // The unknown atom claims more than the file has, so it must not be read.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(8u32 + (1 << 20)).to_be_bytes());
+        buf.extend_from_slice(b"abcd");
+        buf.extend_from_slice(&[0; 16]);
+
+        let dump = Dump::read(&mut Cursor::new(&buf), Dump::INLINE_LIMIT).unwrap();
+        assert_eq!(
+            dump.atoms[0].body,
+            DumpBody::Source {
+                offset: 8,
+                size: 1 << 20
+            }
+        );
+    }
+
+    #[test]
+    fn fourcc() {
+        let json = serde_json::to_string(&FourCC::new(b"ftyp")).unwrap();
+        assert_eq!(json, r#""ftyp""#);
+
+        let binary = FourCC::new(&[0xa9, b'n', b'a', b'm']);
+        let json = serde_json::to_string(&binary).unwrap();
+        assert_eq!(serde_json::from_str::<FourCC>(&json).unwrap(), binary);
+
+        assert!(serde_json::from_str::<FourCC>(r#""moo""#).is_err());
+        assert!(serde_json::from_str::<FourCC>(r#""moov2""#).is_err());
+    }
+}
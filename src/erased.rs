@@ -0,0 +1,167 @@
+use std::any::Any as StdAny;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{OnceLock, RwLock};
+
+use crate::*;
+
+// A value that can be stored in an [Erased], used for user-defined types identified by a FourCC.
+pub(crate) trait ErasedValue:
+    Clone + PartialEq + fmt::Debug + Send + Sync + 'static
+{
+    fn kind(&self) -> FourCC;
+    fn encode(&self, buf: &mut dyn BufMut) -> Result<()>;
+}
+
+// An object-safe version of ErasedValue, so we can store different types in the same Vec.
+trait DynValue: fmt::Debug + Send + Sync {
+    fn kind(&self) -> FourCC;
+    fn encode(&self, buf: &mut dyn BufMut) -> Result<()>;
+    fn clone_box(&self) -> Box<dyn DynValue>;
+    fn eq_dyn(&self, other: &dyn DynValue) -> bool;
+    fn as_any(&self) -> &dyn StdAny;
+    fn as_any_mut(&mut self) -> &mut dyn StdAny;
+    fn into_any(self: Box<Self>) -> Box<dyn StdAny>;
+}
+
+impl<T: ErasedValue> DynValue for T {
+    fn kind(&self) -> FourCC {
+        ErasedValue::kind(self)
+    }
+
+    fn encode(&self, buf: &mut dyn BufMut) -> Result<()> {
+        ErasedValue::encode(self, buf)
+    }
+
+    fn clone_box(&self) -> Box<dyn DynValue> {
+        Box::new(self.clone())
+    }
+
+    fn eq_dyn(&self, other: &dyn DynValue) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn StdAny {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn StdAny {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn StdAny> {
+        self
+    }
+}
+
+// A type-erased value that can still be encoded, cloned, compared and downcast.
+pub(crate) struct Erased(Box<dyn DynValue>);
+
+impl Erased {
+    pub fn new<T: ErasedValue>(value: T) -> Self {
+        Self(Box::new(value))
+    }
+
+    pub fn kind(&self) -> FourCC {
+        self.0.kind()
+    }
+
+    pub fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        self.0.encode(buf)
+    }
+
+    pub fn is<T: ErasedValue>(&self) -> bool {
+        self.0.as_any().is::<T>()
+    }
+
+    pub fn downcast_ref<T: ErasedValue>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+
+    pub fn downcast_mut<T: ErasedValue>(&mut self) -> Option<&mut T> {
+        self.0.as_any_mut().downcast_mut()
+    }
+
+    pub fn downcast<T: ErasedValue>(self) -> std::result::Result<T, Self> {
+        match self.is::<T>() {
+            true => Ok(*self.0.into_any().downcast().unwrap()),
+            false => Err(self),
+        }
+    }
+
+    // The kind and the encoded value, which is how it's serialized.
+    #[cfg(feature = "serde")]
+    pub fn raw(&self) -> Result<Raw> {
+        let mut body = Vec::new();
+        self.encode(&mut body)?;
+
+        Ok(Raw {
+            kind: self.kind(),
+            body,
+        })
+    }
+}
+
+impl Clone for Erased {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl PartialEq for Erased {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_dyn(other.0.as_ref())
+    }
+}
+
+impl Eq for Erased {}
+
+impl fmt::Debug for Erased {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// A value that was deserialized before its kind was registered, kept as the encoded bytes.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Raw {
+    pub kind: FourCC,
+    pub body: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl ErasedValue for Raw {
+    fn kind(&self) -> FourCC {
+        self.kind
+    }
+
+    fn encode(&self, mut buf: &mut dyn BufMut) -> Result<()> {
+        self.body.encode(&mut buf)
+    }
+}
+
+// The decoder registered for each kind, shared by the whole process.
+pub(crate) struct Registry<D>(OnceLock<RwLock<HashMap<FourCC, D>>>);
+
+impl<D: Copy> Registry<D> {
+    pub const fn new() -> Self {
+        Self(OnceLock::new())
+    }
+
+    fn map(&self) -> &RwLock<HashMap<FourCC, D>> {
+        self.0.get_or_init(Default::default)
+    }
+
+    pub fn insert(&self, kind: FourCC, decoder: D) {
+        self.map().write().unwrap().insert(kind, decoder);
+    }
+
+    pub fn remove(&self, kind: FourCC) -> bool {
+        self.map().write().unwrap().remove(&kind).is_some()
+    }
+
+    pub fn get(&self, kind: FourCC) -> Option<D> {
+        self.map().read().unwrap().get(&kind).copied()
+    }
+}
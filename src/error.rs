@@ -1,4 +1,6 @@
-use crate::{Any, FourCC};
+use std::fmt;
+
+use crate::{Any, AtomPath, FourCC};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -68,6 +70,113 @@ pub enum Error {
 
     #[error("invalid parameter combination: {0}")]
     InvalidCombination(&'static str),
+
+    /// An error raised while decoding an atom, annotated with its location.
+    #[error("{0}")]
+    Context(Box<ErrorContext>),
+}
+
+impl Error {
+    /// Returns the location of the failing atom, if the error was raised while decoding one.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Context(context) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error without any location.
+    pub fn root(&self) -> &Error {
+        match self {
+            Self::Context(context) => &context.error,
+            err => err,
+        }
+    }
+
+    /// Returns the underlying error without any location.
+    pub fn into_root(self) -> Error {
+        match self {
+            Self::Context(context) => context.error,
+            err => err,
+        }
+    }
+
+    /// Shift the reported offset, ex. by the file position of the outermost atom.
+    pub fn offset_by(mut self, base: u64) -> Self {
+        if let Self::Context(context) = &mut self {
+            context.offset += base;
+        }
+
+        self
+    }
+
+    // Attach the enclosing atom to an error raised while decoding its body.
+    // `position` is how far into the body the decoder got, which is just past the
+    // header of the failing child when the error came from a nested atom.
+    pub(crate) fn within(self, kind: FourCC, header_size: usize, position: usize) -> Self {
+        let mut context = match self {
+            Self::Context(mut context) => {
+                let start = header_size + position.saturating_sub(context.header_size);
+                context.offset += start as u64;
+                context
+                    .path
+                    .0
+                    .insert(0, crate::AtomPathSegment { kind, index: None });
+                context
+            }
+            error => Box::new(ErrorContext {
+                path: AtomPath::new().join(kind, None),
+                offset: 0,
+                error,
+                header_size,
+            }),
+        };
+
+        context.header_size = header_size;
+        Self::Context(context)
+    }
+
+    // Record the position of the failing child among its siblings of the same kind.
+    pub(crate) fn with_index(mut self, kind: FourCC, index: usize) -> Self {
+        if let Self::Context(context) = &mut self {
+            if let Some(segment) = context.path.0.first_mut() {
+                if segment.kind == kind && segment.index.is_none() {
+                    segment.index = Some(index);
+                }
+            }
+        }
+
+        self
+    }
+}
+
+/// Where a decode error occurred, see [Error::context].
+#[derive(Debug)]
+pub struct ErrorContext {
+    /// The chain of atoms from the outermost decoded atom to the one that failed.
+    pub path: AtomPath,
+
+    /// The offset of the failing atom's header, from where decoding started.
+    ///
+    /// This is the absolute offset when decoding with [LazyReader](crate::LazyReader), [AtomSpan](crate::AtomSpan) or the dump, or when reading from the start of a file.
+    /// Otherwise it's relative to the outermost decoded atom, and [Error::offset_by] makes it absolute when that atom's position is known.
+    pub offset: u64,
+
+    /// The underlying error.
+    pub error: Error,
+
+    // The header size of the outermost atom, used to relocate the offset when nested again.
+    header_size: usize,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (at {}, offset {})",
+            self.error, self.path, self.offset
+        )
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,533 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::*;
+
+/// Rewrite a progressive file so the `moov` comes before the media data, like `qt-faststart`.
+///
+/// The `moov` is moved ahead of the first `mdat` and every other atom is copied in order.
+/// The chunk offsets of each track are shifted to match, switching from `stco` to `co64` when they no longer fit in 32 bits.
+/// The `iloc` offsets of a file-level `meta` are updated too.
+///
+/// Only the `stco`, `co64` and `iloc` atoms are rewritten, along with the sizes of their parents when they grow.
+/// Every other byte of the `moov` and `meta` is copied as-is, including atoms that this crate doesn't support.
+///
+/// The media data is streamed from the reader and never buffered.
+/// A file that's already laid out this way is copied as-is.
+pub fn faststart<R: Read + Seek + ?Sized, W: Write + ?Sized>(r: &mut R, w: &mut W) -> Result<()> {
+    let spans = scan(r)?;
+
+    let moov = spans
+        .iter()
+        .position(|span| span.kind == Moov::KIND)
+        .ok_or(Error::MissingBox(Moov::KIND))?;
+
+    let order: Vec<usize> = match spans.iter().position(|span| span.kind == Mdat::KIND) {
+        Some(mdat) if mdat < moov => {
+            let mut order: Vec<usize> = (0..spans.len()).filter(|&i| i != moov).collect();
+            order.insert(mdat, moov);
+            order
+        }
+        _ => return copy(r, w, &spans, &(0..spans.len()).collect::<Vec<_>>(), &[]),
+    };
+
+    // Read the atoms that contain absolute offsets.
+    let mut atoms = Vec::with_capacity(spans.len());
+    for span in &spans {
+        let atom = match span.kind {
+            Moov::KIND | Meta::KIND => {
+                r.seek(SeekFrom::Start(span.offset))?;
+                let mut buf = vec![0; span.size as usize];
+                r.read_exact(&mut buf)?;
+                Some(buf)
+            }
+            _ => None,
+        };
+        atoms.push(atom);
+    }
+
+    // The offsets depend on the size of the moov, which depends on the offsets.
+    // Repeat until the layout is stable, which takes at most a few passes.
+    let mut sizes: Vec<u64> = spans.iter().map(|span| span.size).collect();
+
+    for _ in 0..4 {
+        let mut layout = Layout::new();
+        for &index in &order {
+            layout.push(spans[index].offset, spans[index].size, sizes[index]);
+        }
+
+        let mut encoded = vec![None; spans.len()];
+        for (index, atom) in atoms.iter().enumerate() {
+            if let Some(atom) = atom {
+                encoded[index] = Some(layout.relocate_bytes(atom)?);
+            }
+        }
+
+        let next: Vec<u64> = sizes
+            .iter()
+            .zip(&encoded)
+            .map(|(&size, buf)| buf.as_ref().map_or(size, |buf| buf.len() as u64))
+            .collect();
+
+        if next == sizes {
+            return copy(r, w, &spans, &order, &encoded);
+        }
+
+        sizes = next;
+    }
+
+    Err(Error::Unsupported("faststart layout didn't converge"))
+}
+
+/// The position of a top-level atom in the input.
+pub(crate) struct Span {
+    pub kind: FourCC,
+    pub offset: u64,
+    pub size: u64,
+
+    /// The offset of the body, after the header.
+    pub body: u64,
+}
+
+impl Span {
+    pub fn end(&self) -> u64 {
+        self.offset + self.size
+    }
+}
+
+pub(crate) fn scan<R: Read + Seek + ?Sized>(r: &mut R) -> Result<Vec<Span>> {
+    let end = r.seek(SeekFrom::End(0))?;
+    let mut offset = r.seek(SeekFrom::Start(0))?;
+    let mut spans = Vec::new();
+
+    while offset < end {
+        let header = <Header as ReadFrom>::read_from(r)?;
+        let body = r.stream_position()?;
+        let size = match header.size {
+            Some(size) => body - offset + size as u64,
+            None => end - offset,
+        };
+
+        if offset + size > end {
+            return Err(Error::OutOfBounds);
+        }
+
+        spans.push(Span {
+            kind: header.kind,
+            offset,
+            size,
+            body,
+        });
+
+        offset += size;
+        r.seek(SeekFrom::Start(offset))?;
+    }
+
+    Ok(spans)
+}
+
+// Write the atoms in the given order, using the encoded version when available.
+fn copy<R: Read + Seek + ?Sized, W: Write + ?Sized>(
+    r: &mut R,
+    w: &mut W,
+    spans: &[Span],
+    order: &[usize],
+    encoded: &[Option<Vec<u8>>],
+) -> Result<()> {
+    for &index in order {
+        if let Some(Some(buf)) = encoded.get(index) {
+            w.write_all(buf)?;
+            continue;
+        }
+
+        let span = &spans[index];
+        r.seek(SeekFrom::Start(span.offset))?;
+        let n = std::io::copy(&mut r.take(span.size), w)?;
+        if n != span.size {
+            return Err(Error::UnexpectedEof);
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps an offset in the input to the output, when the top-level atoms are moved or resized.
+///
+/// Used by [faststart], and by anything else that rearranges a file and needs to update its absolute offsets.
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    // The old offset, old end and new offset of every atom.
+    moves: Vec<(u64, u64, u64)>,
+
+    // The new offset of the next atom.
+    end: u64,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next atom in the output, found at `offset` in the input with the given `size`.
+    ///
+    /// The `new_size` is its size once written, which differs if it was modified.
+    /// Any atom that isn't pushed is removed, and an offset pointing into it fails to relocate.
+    pub fn push(&mut self, offset: u64, size: u64, new_size: u64) {
+        self.moves.push((offset, offset + size, self.end));
+        self.end += new_size;
+    }
+
+    /// Map an offset in the input to the output, or [Error::OutOfBounds] if it's not within a pushed atom.
+    pub fn relocate(&self, offset: u64) -> Result<u64> {
+        let (start, _, new) = self
+            .moves
+            .iter()
+            .find(|(start, end, _)| (*start..*end).contains(&offset))
+            .ok_or(Error::OutOfBounds)?;
+
+        Ok(new + (offset - start))
+    }
+
+    /// Update the chunk offsets of every track, along with any `iloc` in the `moov` or a `trak`.
+    pub fn relocate_moov(&self, moov: &mut Moov) -> Result<()> {
+        for trak in &mut moov.trak {
+            self.relocate_stbl(&mut trak.mdia.minf.stbl)?;
+
+            if let Some(meta) = &mut trak.meta {
+                self.relocate_meta(meta)?;
+            }
+        }
+
+        if let Some(meta) = &mut moov.meta {
+            self.relocate_meta(meta)?;
+        }
+
+        Ok(())
+    }
+
+    /// Update the offsets of the items stored in this file, if there's an `iloc`.
+    pub fn relocate_meta(&self, meta: &mut Meta) -> Result<()> {
+        match meta.get_mut::<Iloc>() {
+            Some(iloc) => self.relocate_iloc(iloc),
+            None => Ok(()),
+        }
+    }
+
+    /// Copy the encoded atom, updating the offsets in any `stco`, `co64` or `iloc` within it.
+    ///
+    /// Everything else is copied byte-for-byte; only the sizes of the parents change if a table grows.
+    pub fn relocate_bytes(&self, atom: &[u8]) -> Result<Vec<u8>> {
+        let spans = AtomSpan::scan(atom, 0)?;
+
+        let mut out = Vec::with_capacity(atom.len());
+        for span in &spans {
+            self.patch(atom, span, &mut out)?;
+        }
+
+        Ok(out)
+    }
+
+    fn patch(&self, buf: &[u8], span: &AtomSpan, out: &mut Vec<u8>) -> Result<()> {
+        let mut atom = &buf[span.offset as usize..span.end() as usize];
+
+        match span.kind {
+            Stco::KIND | Co64::KIND => {
+                let mut stbl = Stbl::default();
+                match Any::decode(&mut atom)? {
+                    Any::Stco(stco) => stbl.stco = Some(stco),
+                    Any::Co64(co64) => stbl.co64 = Some(co64),
+                    _ => unreachable!(),
+                }
+
+                self.relocate_stbl(&mut stbl)?;
+                stbl.stco.encode(out)?;
+                stbl.co64.encode(out)?;
+
+                return Ok(());
+            }
+            Iloc::KIND => {
+                let mut iloc = Iloc::decode(&mut atom)?;
+                self.relocate_iloc(&mut iloc)?;
+                iloc.encode(out)?;
+
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let (Some(first), Some(last)) = (span.children.first(), span.children.last()) else {
+            out.extend_from_slice(atom);
+            return Ok(());
+        };
+
+        // Copy the header and any fields before the children, then each child, then any trailing bytes.
+        let start = out.len();
+        out.extend_from_slice(&buf[span.offset as usize..first.offset as usize]);
+
+        for child in &span.children {
+            self.patch(buf, child, out)?;
+        }
+
+        out.extend_from_slice(&buf[last.end() as usize..span.end() as usize]);
+
+        let size = out.len() - start;
+        if size != atom.len() {
+            resize(&mut out[start..], size)?;
+        }
+
+        Ok(())
+    }
+
+    fn relocate_stbl(&self, stbl: &mut Stbl) -> Result<()> {
+        let offsets = stbl
+            .chunk_offsets()
+            .into_iter()
+            .map(|offset| self.relocate(offset))
+            .collect::<Result<Vec<_>>>()?;
+        stbl.set_chunk_offsets(offsets);
+
+        Ok(())
+    }
+
+    fn relocate_iloc(&self, iloc: &mut Iloc) -> Result<()> {
+        // Only items stored in this file have absolute offsets.
+        let locations = iloc.item_locations.iter_mut().filter(|location| {
+            location.construction_method == 0 && location.data_reference_index == 0
+        });
+
+        for location in locations {
+            if location.base_offset == 0 {
+                for extent in &mut location.extents {
+                    extent.offset = self.relocate(extent.offset)?;
+                }
+                continue;
+            }
+
+            // Move the base by the same amount as the first extent, keeping the extents relative.
+            let old =
+                location.base_offset + location.extents.first().map_or(0, |extent| extent.offset);
+            let new = self.relocate(old)?;
+            location.base_offset = (location.base_offset + new)
+                .checked_sub(old)
+                .ok_or(Error::OutOfBounds)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Update the size in the header of an encoded atom, which is either 32-bit or a 64-bit largesize.
+fn resize(atom: &mut [u8], size: usize) -> Result<()> {
+    let kind = FourCC::new(&[atom[4], atom[5], atom[6], atom[7]]);
+
+    match atom[..4] {
+        [0, 0, 0, 1] => atom[8..16].copy_from_slice(&(size as u64).to_be_bytes()),
+        // The atom extends to the end of the file.
+        [0, 0, 0, 0] => {}
+        _ => {
+            let size: u32 = size.try_into().map_err(|_| Error::TooLarge(kind))?;
+            atom[..4].copy_from_slice(&size.to_be_bytes());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    fn decode(mut buf: &[u8]) -> Vec<Any> {
+        let mut atoms = Vec::new();
+        while let Some(atom) = Any::decode_maybe(&mut buf).unwrap() {
+            atoms.push(atom);
+        }
+        atoms
+    }
+
+    fn mdat_offset(atoms: &[Any]) -> u64 {
+        let mut offset = 0;
+        for atom in atoms {
+            if let Any::Mdat(_) = atom {
+                // Skip the header too.
+                return offset + 8;
+            }
+
+            let mut buf = Vec::new();
+            atom.encode(&mut buf).unwrap();
+            offset += buf.len() as u64;
+        }
+        panic!("missing mdat")
+    }
+
+    // Every chunk and item offset, relative to the start of the mdat payload.
+    fn offsets(data: &[u8]) -> Vec<i64> {
+        let mut offsets = Vec::new();
+
+        // The encoded size of the atoms can differ from the input, so scan the input.
+        let spans = scan(&mut Cursor::new(data)).unwrap();
+        let mdat = spans.iter().find(|span| span.kind == Mdat::KIND).unwrap();
+        let base = (mdat.offset + 8) as i64;
+
+        for atom in decode(data) {
+            match atom {
+                Any::Moov(moov) => {
+                    for trak in &moov.trak {
+                        let stbl = &trak.mdia.minf.stbl;
+                        offsets.extend(stbl.chunk_offsets().iter().map(|&o| o as i64 - base));
+                    }
+                }
+                Any::Meta(meta) => {
+                    for location in &meta.get::<Iloc>().unwrap().item_locations {
+                        for extent in &location.extents {
+                            let offset = location.base_offset + extent.offset;
+                            offsets.push(offset as i64 - base);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        offsets
+    }
+
+    // Undo faststart: move the moov to the end, shifting every offset to match.
+    fn slowstart(data: &[u8]) -> Vec<u8> {
+        let spans = scan(&mut Cursor::new(data)).unwrap();
+        let mdat = spans.iter().find(|span| span.kind == Mdat::KIND).unwrap();
+
+        let mut atoms = decode(data);
+        let index = atoms
+            .iter()
+            .position(|atom| matches!(atom, Any::Moov(_)))
+            .unwrap();
+        let mut moov = match atoms.remove(index) {
+            Any::Moov(moov) => moov,
+            _ => unreachable!(),
+        };
+
+        let shift = mdat.offset + 8 - mdat_offset(&atoms);
+
+        for trak in &mut moov.trak {
+            let stbl = &mut trak.mdia.minf.stbl;
+            let offsets = stbl.chunk_offsets().iter().map(|o| o - shift).collect();
+            stbl.set_chunk_offsets(offsets);
+        }
+
+        for atom in &mut atoms {
+            let Any::Meta(meta) = atom else { continue };
+            let Some(iloc) = meta.get_mut::<Iloc>() else {
+                continue;
+            };
+
+            for location in &mut iloc.item_locations {
+                match location.base_offset {
+                    0 => location.extents.iter_mut().for_each(|e| e.offset -= shift),
+                    _ => location.base_offset -= shift,
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        for atom in atoms.iter().chain(std::iter::once(&moov.into())) {
+            atom.encode(&mut out).unwrap();
+        }
+        out
+    }
+
+    fn kinds(data: &[u8]) -> Vec<FourCC> {
+        decode(data).iter().map(Any::kind).collect()
+    }
+
+    fn round_trip(data: &[u8]) {
+        let slow = slowstart(data);
+        assert_eq!(offsets(&slow), offsets(data));
+        assert_eq!(kinds(&slow).last(), Some(&Moov::KIND));
+
+        let mut out = Vec::new();
+        faststart(&mut Cursor::new(&slow), &mut out).unwrap();
+        assert_eq!(kinds(&out), kinds(data));
+        assert_eq!(offsets(&out), offsets(data));
+
+        // A second pass copies the file as-is.
+        let mut again = Vec::new();
+        faststart(&mut Cursor::new(&out), &mut again).unwrap();
+        assert_eq!(again, out);
+    }
+
+    #[test]
+    fn progressive() {
+        round_trip(include_bytes!("test/uncompressed.mp4"));
+    }
+
+    #[test]
+    fn items() {
+        round_trip(include_bytes!("test/libavif_anim_q10.avif"));
+    }
+
+    #[test]
+    fn preserves_unknown() {
+        let mut slow = slowstart(include_bytes!("test/uncompressed.mp4"));
+
+        // Append an atom that isn't supported to the moov, which is at the end of the file.
+        let unknown = [0, 0, 0, 12, b'a', b'b', b'c', b'd', 1, 2, 3, 4];
+        let spans = scan(&mut Cursor::new(&slow)).unwrap();
+        let moov = spans.iter().find(|span| span.kind == Moov::KIND).unwrap();
+        let size = moov.size as u32 + unknown.len() as u32;
+        slow[moov.offset as usize..][..4].copy_from_slice(&size.to_be_bytes());
+        slow.extend_from_slice(&unknown);
+
+        let mut out = Vec::new();
+        faststart(&mut Cursor::new(&slow), &mut out).unwrap();
+
+        let spans = scan(&mut Cursor::new(&out)).unwrap();
+        let moov = spans.iter().find(|span| span.kind == Moov::KIND).unwrap();
+        let end = (moov.offset + moov.size) as usize;
+        assert_eq!(&out[end - unknown.len()..end], unknown);
+
+        // Remove it again to decode, which leaves the offsets pointing 12 bytes later.
+        let size = moov.size as u32 - unknown.len() as u32;
+        out[moov.offset as usize..][..4].copy_from_slice(&size.to_be_bytes());
+        out.drain(end - unknown.len()..end);
+
+        let expected: Vec<_> = offsets(include_bytes!("test/uncompressed.mp4"))
+            .into_iter()
+            .map(|offset| offset + unknown.len() as i64)
+            .collect();
+        assert_eq!(offsets(&out), expected);
+    }
+
+    #[test]
+    fn promote() {
+        let mut stbl = Stbl {
+            stco: Some(Stco {
+                entries: vec![1, 2],
+            }),
+            ..Default::default()
+        };
+
+        stbl.set_chunk_offsets(vec![1, u32::MAX as u64 + 1]);
+        assert_eq!(stbl.stco, None);
+        assert_eq!(stbl.chunk_offsets(), vec![1, u32::MAX as u64 + 1]);
+
+        // Once promoted, the offsets stay 64-bit.
+        stbl.set_chunk_offsets(vec![1, 2]);
+        assert_eq!(stbl.stco, None);
+        assert_eq!(stbl.co64.as_ref().unwrap().entries, vec![1, 2]);
+    }
+
+    #[test]
+    fn missing_moov() {
+        let mut data = Vec::new();
+        Mdat {
+            data: vec![1, 2, 3],
+        }
+        .encode(&mut data)
+        .unwrap();
+
+        let err = faststart(&mut Cursor::new(&data), &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::MissingBox(kind) if kind == Moov::KIND));
+    }
+}
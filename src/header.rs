@@ -3,7 +3,7 @@ use std::io::{Cursor, Read};
 use crate::*;
 
 /// A atom header, which contains the atom's kind and size.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Header {
     /// The name of the atom, always 4 bytes.
     pub kind: FourCC,
@@ -13,6 +13,13 @@ pub struct Header {
     pub size: Option<usize>,
 }
 
+impl Header {
+    /// A header for an atom of this kind, with the size of the body if known.
+    pub fn new(kind: FourCC, size: Option<usize>) -> Self {
+        Self { kind, size }
+    }
+}
+
 impl Encode for Header {
     fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
         match self.size.map(|size| size + 8) {
@@ -77,6 +84,15 @@ impl ReadFrom for Header {
 
 impl ReadFrom for Option<Header> {
     fn read_from<R: Read + ?Sized>(r: &mut R) -> Result<Self> {
+        Ok(Header::read_sized(r)?.map(|(header, _)| header))
+    }
+}
+
+// Utility methods
+impl Header {
+    // Read a header, along with the number of bytes it occupied.
+    // A largesize header is 16 bytes even when the size would fit in 32 bits.
+    pub(crate) fn read_sized<R: Read + ?Sized>(r: &mut R) -> Result<Option<(Self, usize)>> {
         let mut buf = [0u8; 8];
         let n = r.read(&mut buf)?;
         if n == 0 {
@@ -88,25 +104,33 @@ impl ReadFrom for Option<Header> {
         let size = u32::from_be_bytes(buf[0..4].try_into().unwrap());
         let kind = u32::from_be_bytes(buf[4..8].try_into().unwrap()).into();
 
-        let size = match size {
-            0 => None,
+        let (size, header_size) = match size {
+            0 => (None, 8),
             1 => {
                 // Read another 8 bytes
                 r.read_exact(&mut buf)?;
                 let size = u64::from_be_bytes(buf);
                 let size = size.checked_sub(16).ok_or(Error::InvalidSize)?;
 
-                Some(size as usize)
+                (Some(size as usize), 16)
             }
-            _ => Some(size.checked_sub(8).ok_or(Error::InvalidSize)? as usize),
+            _ => (
+                Some(size.checked_sub(8).ok_or(Error::InvalidSize)? as usize),
+                8,
+            ),
         };
 
-        Ok(Some(Header { kind, size }))
+        Ok(Some((Header { kind, size }, header_size)))
+    }
+
+    // The number of bytes this header occupies when encoded.
+    pub(crate) fn encoded_size(&self) -> usize {
+        match self.size {
+            Some(size) if size + 8 > u32::MAX as usize => 16,
+            _ => 8,
+        }
     }
-}
 
-// Utility methods
-impl Header {
     pub(crate) fn read_body<R: Read + ?Sized>(&self, r: &mut R) -> Result<Cursor<Vec<u8>>> {
         // TODO This allocates on the heap.
         // Ideally, we should use ReadFrom instead of Decode to avoid this.
@@ -118,7 +142,7 @@ impl Header {
 
         match self.size {
             Some(size) => {
-                let n = std::io::copy(&mut r.take(size as _), &mut buf)? as _;
+                let n = std::io::copy(&mut r.take(size as _), &mut buf)? as usize;
                 if size != n {
                     return Err(Error::OutOfBounds);
                 }
@@ -131,6 +155,22 @@ impl Header {
         Ok(Cursor::new(buf))
     }
 
+    // Discard the body, returning its size.
+    pub(crate) fn skip_body<R: Read + ?Sized>(&self, r: &mut R) -> Result<usize> {
+        let n = match self.size {
+            Some(size) => {
+                let n = std::io::copy(&mut r.take(size as _), &mut std::io::sink())? as usize;
+                if size != n {
+                    return Err(Error::OutOfBounds);
+                }
+                n
+            }
+            None => std::io::copy(r, &mut std::io::sink())? as usize,
+        };
+
+        Ok(n)
+    }
+
     #[cfg(feature = "tokio")]
     pub(crate) async fn read_body_tokio<R: ::tokio::io::AsyncRead + Unpin + ?Sized>(
         &self,
@@ -148,7 +188,7 @@ impl Header {
 
         match self.size {
             Some(size) => {
-                let n = ::tokio::io::copy(&mut r.take(size as _), &mut buf).await? as _;
+                let n = ::tokio::io::copy(&mut r.take(size as _), &mut buf).await? as usize;
                 if size != n {
                     return Err(Error::OutOfBounds);
                 }
@@ -160,4 +200,26 @@ impl Header {
 
         Ok(Cursor::new(buf))
     }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn skip_body_tokio<R: ::tokio::io::AsyncRead + Unpin + ?Sized>(
+        &self,
+        r: &mut R,
+    ) -> Result<usize> {
+        use ::tokio::io::AsyncReadExt;
+
+        let n = match self.size {
+            Some(size) => {
+                let n = ::tokio::io::copy(&mut r.take(size as _), &mut ::tokio::io::sink()).await?
+                    as usize;
+                if size != n {
+                    return Err(Error::OutOfBounds);
+                }
+                n
+            }
+            None => ::tokio::io::copy(r, &mut ::tokio::io::sink()).await? as usize,
+        };
+
+        Ok(n)
+    }
 }
@@ -0,0 +1,344 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::*;
+
+/// The position of an atom found by a [LazyReader], without its body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyAtom {
+    pub header: Header,
+
+    /// The offset of the header.
+    pub offset: u64,
+
+    /// The offset of the body, after the header.
+    pub body: u64,
+
+    /// The size of the atom, **including** the header.
+    pub size: u64,
+}
+
+impl LazyAtom {
+    pub fn kind(&self) -> FourCC {
+        self.header.kind
+    }
+
+    /// The offset just past the atom.
+    pub fn end(&self) -> u64 {
+        self.offset + self.size
+    }
+}
+
+/// A reader that seeks over atom bodies instead of reading them, decoding only what's asked for.
+///
+/// This is useful to probe a file, where only the `ftyp` and `moov` are needed but the `mdat` could be gigabytes.
+/// Unlike [ReadUntil], the `moov` is found without reading the media data even when it's at the end of the file.
+///
+/// ```
+/// # use mp4_atom::*;
+/// # fn main() -> Result<()> {
+/// # let file = std::io::Cursor::new(include_bytes!("test/uncompressed.mp4"));
+/// let mut reader = LazyReader::new(file)?;
+///
+/// // Decode the sample descriptions without decoding the sample tables.
+/// let path = [Moov::KIND, Trak::KIND, Mdia::KIND, Minf::KIND, Stbl::KIND];
+/// for stsd in reader.decode_all::<Stsd>(&path)? {
+///     println!("{:?}", stsd.codecs);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct LazyReader<R> {
+    inner: R,
+    end: u64,
+}
+
+impl<R: Read + Seek> LazyReader<R> {
+    pub fn new(mut inner: R) -> Result<Self> {
+        let end = inner.seek(SeekFrom::End(0))?;
+        Ok(Self { inner, end })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// The headers of the top-level atoms, in order.
+    pub fn atoms(&mut self) -> Result<Vec<LazyAtom>> {
+        self.scan(0, self.end)
+    }
+
+    /// The first top-level atom of the given kind.
+    pub fn find(&mut self, kind: FourCC) -> Result<Option<LazyAtom>> {
+        let mut offset = 0;
+        while offset < self.end {
+            let atom = self.header(offset, self.end)?;
+            if atom.kind() == kind {
+                return Ok(Some(atom));
+            }
+
+            offset = atom.end();
+        }
+
+        Ok(None)
+    }
+
+    /// The headers of the atoms within a container, in order.
+    ///
+    /// Any fields before the first child, such as the version and entry count of a `stsd`, are skipped.
+    /// Returns an empty list if the atom isn't a known container, so the body of a `mdat` is never scanned.
+    pub fn children(&mut self, parent: &LazyAtom) -> Result<Vec<LazyAtom>> {
+        let Some(prefix) = self.prefix(parent)? else {
+            return Ok(Vec::new());
+        };
+
+        let start = parent.body + prefix;
+        if start > parent.end() {
+            return Err(Error::OutOfBounds);
+        }
+
+        self.scan(start, parent.end())
+    }
+
+    /// Every atom at the end of the path of kinds, starting from the top level.
+    pub fn find_all(&mut self, path: &[FourCC]) -> Result<Vec<LazyAtom>> {
+        let Some((first, rest)) = path.split_first() else {
+            return Ok(Vec::new());
+        };
+
+        let mut found: Vec<LazyAtom> = self
+            .atoms()?
+            .into_iter()
+            .filter(|atom| atom.kind() == *first)
+            .collect();
+
+        for kind in rest {
+            let mut next = Vec::new();
+            for parent in &found {
+                let children = self.children(parent)?;
+                next.extend(children.into_iter().filter(|atom| atom.kind() == *kind));
+            }
+
+            found = next;
+        }
+
+        Ok(found)
+    }
+
+    /// Read and decode a single atom.
+    pub fn decode<T: Atom>(&mut self, atom: &LazyAtom) -> Result<T> {
+        if atom.kind() != T::KIND {
+            return Err(Error::UnexpectedBox(atom.kind()));
+        }
+
+        self.inner.seek(SeekFrom::Start(atom.body))?;
+
+        // An atom without a size extends to the end of the file, which is resolved here.
+        let header = Header::new(atom.kind(), Some((atom.end() - atom.body) as usize));
+        let body = &mut header.read_body(&mut self.inner)?;
+
+        let header_size = (atom.body - atom.offset) as usize;
+        decode_atom_body(T::KIND, header_size, body, T::decode_body)
+            .map_err(|err| err.offset_by(atom.offset))
+    }
+
+    /// Decode every atom of type `T` found within the path of containers.
+    pub fn decode_all<T: Atom>(&mut self, path: &[FourCC]) -> Result<Vec<T>> {
+        let mut path = path.to_vec();
+        path.push(T::KIND);
+
+        let atoms = self.find_all(&path)?;
+        atoms.iter().map(|atom| self.decode(atom)).collect()
+    }
+
+    // Read the header of an atom at the offset, which must end before the limit.
+    fn header(&mut self, offset: u64, limit: u64) -> Result<LazyAtom> {
+        self.inner.seek(SeekFrom::Start(offset))?;
+
+        let header = <Header as ReadFrom>::read_from(&mut self.inner)?;
+        let body = self.inner.stream_position()?;
+        let size = match header.size {
+            Some(size) => body - offset + size as u64,
+            None => limit - offset,
+        };
+
+        if offset + size > limit {
+            return Err(Error::OutOfBounds);
+        }
+
+        Ok(LazyAtom {
+            header,
+            offset,
+            body,
+            size,
+        })
+    }
+
+    fn scan(&mut self, mut offset: u64, limit: u64) -> Result<Vec<LazyAtom>> {
+        let mut atoms = Vec::new();
+
+        // Anything smaller than a header is padding, such as the zero terminator of a udta.
+        while offset + 8 <= limit {
+            let atom = self.header(offset, limit)?;
+            offset = atom.end();
+            atoms.push(atom);
+        }
+
+        Ok(atoms)
+    }
+
+    // The number of bytes between the header of a container and its first child, or None if it isn't a known container.
+    fn prefix(&mut self, parent: &LazyAtom) -> Result<Option<u64>> {
+        let mut buf = [0u8; 16];
+        let size = buf.len().min((parent.end() - parent.body) as usize);
+
+        self.inner.seek(SeekFrom::Start(parent.body))?;
+        self.inner.read_exact(&mut buf[..size])?;
+
+        Ok(children_offset(parent.kind(), &buf[..size]).map(|offset| offset as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    const FILE: &[u8] = include_bytes!("test/uncompressed.mp4");
+
+    // Counts the bytes read, to check the media data is skipped.
+    struct Counting<R> {
+        inner: R,
+        read: usize,
+    }
+
+    impl<R: Read> Read for Counting<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    impl<R: Seek> Seek for Counting<R> {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    fn moov() -> Moov {
+        <Moov as ReadUntil>::read_until(&mut &FILE[..]).unwrap()
+    }
+
+    #[test]
+    fn atoms() {
+        let mut reader = LazyReader::new(Cursor::new(FILE)).unwrap();
+        let atoms = reader.atoms().unwrap();
+
+        let kinds: Vec<FourCC> = atoms.iter().map(LazyAtom::kind).collect();
+        assert_eq!(
+            kinds,
+            [Ftyp::KIND, Moov::KIND, Mdat::KIND, Free::KIND].map(FourCC::from)
+        );
+        assert_eq!(atoms[1].offset, 20);
+        assert_eq!(atoms[1].body, 28);
+        assert_eq!(atoms[2].offset, 848);
+        assert_eq!(atoms.last().unwrap().end(), FILE.len() as u64);
+    }
+
+    #[test]
+    fn moov_at_end() {
+        // Move the moov after the mdat.
+        let spans = AtomSpan::scan(FILE, 0).unwrap();
+        let (moov_span, others): (Vec<_>, Vec<_>) =
+            spans.iter().partition(|span| span.kind == Moov::KIND);
+
+        let mut file = Vec::new();
+        for span in others.iter().chain(&moov_span) {
+            file.extend_from_slice(&FILE[span.offset as usize..span.end() as usize]);
+        }
+
+        let mut reader = LazyReader::new(Counting {
+            inner: Cursor::new(file),
+            read: 0,
+        })
+        .unwrap();
+
+        let atom = reader.find(Moov::KIND).unwrap().unwrap();
+        let moov: Moov = reader.decode(&atom).unwrap();
+        assert_eq!(moov, self::moov());
+
+        // Only the headers and the moov itself were read.
+        let reader = reader.into_inner();
+        assert!(reader.read < moov_span[0].size() as usize + 4 * 16);
+    }
+
+    #[test]
+    fn opaque() {
+        let mut reader = LazyReader::new(Counting {
+            inner: Cursor::new(FILE),
+            read: 0,
+        })
+        .unwrap();
+
+        let mdat = reader.find(Mdat::KIND).unwrap().unwrap();
+        let before = reader.inner.read;
+
+        // The media data isn't scanned for atoms.
+        assert!(reader.children(&mdat).unwrap().is_empty());
+        assert!(reader.inner.read - before <= 16);
+    }
+
+    #[test]
+    fn descend() {
+        let mut reader = LazyReader::new(Cursor::new(FILE)).unwrap();
+
+        let path = [Moov::KIND, Trak::KIND, Mdia::KIND, Minf::KIND, Stbl::KIND];
+        let stsd = reader.decode_all::<Stsd>(&path).unwrap();
+        let expected: Vec<Stsd> = moov()
+            .trak
+            .into_iter()
+            .map(|trak| trak.mdia.minf.stbl.stsd)
+            .collect();
+        assert_eq!(stsd, expected);
+
+        // The sample entries are children of the stsd.
+        let stsd = reader
+            .find_all(&[
+                Moov::KIND,
+                Trak::KIND,
+                Mdia::KIND,
+                Minf::KIND,
+                Stbl::KIND,
+                Stsd::KIND,
+            ])
+            .unwrap();
+        let entries = reader.children(&stsd[0]).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind(), Uncv::KIND);
+    }
+
+    #[test]
+    fn meta() {
+        let file = include_bytes!("test/image.avif");
+        let mut reader = LazyReader::new(Cursor::new(file)).unwrap();
+
+        let iloc = reader.decode_all::<Iloc>(&[Meta::KIND]).unwrap();
+        assert_eq!(iloc.len(), 1);
+
+        let meta: Meta = {
+            let atom = reader.find(Meta::KIND).unwrap().unwrap();
+            reader.decode(&atom).unwrap()
+        };
+        assert_eq!(meta.get::<Iloc>(), Some(&iloc[0]));
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let mut reader = LazyReader::new(Cursor::new(&FILE[..100])).unwrap();
+        assert!(reader.atoms().is_err());
+
+        // The ftyp is still found before the truncated moov.
+        assert!(reader.find(Ftyp::KIND).unwrap().is_some());
+    }
+}
@@ -132,6 +132,11 @@
 //! # Ok(()) }
 //! ```
 //!
+//! ### JSON
+//! Enable using the `serde` feature.
+//! Every atom implements `Serialize` and `Deserialize`, and [FourCC] is serialized as a string.
+//! Use [Dump] to convert a whole file, referencing large [Mdat] bodies by offset instead of inlining them.
+//!
 //! ### Asynchronous IO
 //! Enable using the `tokio` feature.
 //! It's the same as the above two but using [AsyncReadFrom], [AsyncWriteTo], and [AsyncReadAtom] instead.
@@ -141,45 +146,80 @@ mod any;
 mod atom;
 mod atom_ext;
 mod buf;
+mod cmaf;
 mod coding;
+mod concat;
+mod custom;
 mod emsg;
+mod erased;
 mod error;
+mod faststart;
 mod free;
 mod ftyp;
 mod header;
 mod io;
 mod iso639;
+mod lazy;
 mod mdat;
 mod meta;
 mod mfra;
 mod moof;
 mod moov;
+mod path;
 mod prft;
+mod push;
+mod remux;
+mod sample_flags;
 mod sidx;
+mod span;
+mod ssix;
 mod styp;
+mod trim;
 mod types;
+mod validate;
+mod visit;
 
 pub use any::*;
 pub use atom::*;
 pub(crate) use atom_ext::*;
 pub use buf::*;
+pub use cmaf::*;
 pub use coding::*;
+pub use concat::*;
+pub use custom::*;
 pub use emsg::*;
+pub(crate) use erased::*;
 pub use error::*;
+pub use faststart::*;
 pub use free::*;
 pub use ftyp::*;
 pub use header::*;
 pub use io::*;
 pub(crate) use iso639::*;
+pub use lazy::*;
 pub use mdat::*;
 pub use meta::*;
 pub use mfra::*;
 pub use moof::*;
 pub use moov::*;
+pub use path::*;
 pub use prft::*;
+pub use push::*;
+pub use sample_flags::*;
 pub use sidx::*;
+pub use span::*;
+pub use ssix::*;
 pub use styp::*;
+pub use trim::*;
 pub use types::*;
+pub use validate::*;
+pub use visit::*;
+
+#[cfg(feature = "serde")]
+mod dump;
+
+#[cfg(feature = "serde")]
+pub use dump::*;
 
 #[cfg(feature = "tokio")]
 mod tokio;
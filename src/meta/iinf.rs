@@ -194,6 +194,18 @@ impl AtomExt for Iinf {
 
         Ok(IinfExt { version })
     }
+
+    fn visit_children_ext<'a>(&'a self, path: &mut AtomPath, visitor: &mut dyn Visitor<'a>) {
+        for (index, item_info) in self.item_infos.iter().enumerate() {
+            visit_atom(item_info, Some(index), path, visitor);
+        }
+    }
+
+    fn visit_children_mut_ext(&mut self, path: &mut AtomPath, visitor: &mut dyn VisitorMut) {
+        for (index, item_info) in self.item_infos.iter_mut().enumerate() {
+            visit_atom_mut(item_info, Some(index), path, visitor);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -336,7 +348,7 @@ mod tests {
         let result = Iinf::decode_body(&mut std::io::Cursor::new(body));
 
         assert!(matches!(
-            result,
+            result.map_err(Error::into_root),
             Err(Error::Unsupported("infe version 1 extensions"))
         ));
 
@@ -347,7 +359,7 @@ mod tests {
         let fuzz_result = Iinf::decode_body(&mut std::io::Cursor::new(fuzz_body));
 
         assert!(matches!(
-            fuzz_result,
+            fuzz_result.map_err(Error::into_root),
             Err(Error::Unsupported("infe version 1 extensions"))
         ));
     }
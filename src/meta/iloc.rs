@@ -121,7 +121,6 @@ impl AtomExt for Iloc {
 
     fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<IlocExt> {
         let mut base_offset_size = 0u8;
-        // TODO: work out which version and sizes we really need for this instance.
         for item_location in &self.item_locations {
             if item_location.base_offset > 0 {
                 if item_location.base_offset > u32::MAX as u64 {
@@ -131,9 +130,39 @@ impl AtomExt for Iloc {
                 }
             }
         }
-        let version = IlocVersion::V0;
-        let offset_size = 4u8;
-        let length_size = 4u8;
+
+        // Use the smallest version that can represent every item.
+        let version = if self.item_locations.len() > u16::MAX as usize
+            || self
+                .item_locations
+                .iter()
+                .any(|item_location| item_location.item_id > u16::MAX as u32)
+        {
+            IlocVersion::V2
+        } else if self
+            .item_locations
+            .iter()
+            .any(|item_location| item_location.construction_method != 0)
+        {
+            IlocVersion::V1
+        } else {
+            IlocVersion::V0
+        };
+
+        // Switch to 64-bit extents when a value overflows 32 bits.
+        let extents = || {
+            self.item_locations
+                .iter()
+                .flat_map(|item_location| &item_location.extents)
+        };
+        let offset_size = match extents().any(|extent| extent.offset > u32::MAX as u64) {
+            true => 8u8,
+            false => 4u8,
+        };
+        let length_size = match extents().any(|extent| extent.length > u32::MAX as u64) {
+            true => 8u8,
+            false => 4u8,
+        };
 
         let index_size = 0u8;
         let size0 = (offset_size << 4) | length_size;
@@ -183,9 +212,7 @@ impl AtomExt for Iloc {
                 }
             }
         }
-        Ok(IlocExt {
-            version: IlocVersion::V0,
-        })
+        Ok(IlocExt { version })
     }
 }
 
@@ -219,6 +246,44 @@ mod tests {
         assert_eq!(decoded, iloc);
     }
 
+    #[test]
+    fn test_iloc_large() {
+        let iloc = Iloc {
+            item_locations: vec![
+                ItemLocation {
+                    item_id: 1,
+                    construction_method: 1,
+                    data_reference_index: 0,
+                    base_offset: 0,
+                    extents: vec![ItemLocationExtent {
+                        item_reference_index: 0,
+                        offset: 16,
+                        length: 8,
+                    }],
+                },
+                ItemLocation {
+                    item_id: 2,
+                    construction_method: 0,
+                    data_reference_index: 0,
+                    base_offset: 0,
+                    extents: vec![ItemLocationExtent {
+                        item_reference_index: 0,
+                        offset: 5_000_000_000,
+                        length: 6_000_000_000,
+                    }],
+                },
+            ],
+        };
+
+        let mut buf = Vec::new();
+        iloc.encode(&mut buf).unwrap();
+        assert_eq!(buf[8], 1, "construction methods require version 1");
+        assert_eq!(buf[12], 0x88, "large extents use 64-bit fields");
+
+        let decoded = Iloc::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded, iloc);
+    }
+
     // Regression for issue #158: out-of-range *_size nibbles (anything
     // outside {0, 4, 8} per ISO/IEC 14496-12 §8.11.3.3) must return
     // Err, not panic.
@@ -83,6 +83,12 @@ impl Atom for Ilst {
         self.cprt.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [],
+        optional: [ name, year, covr, desc, ctoo, cprt ],
+        multiple: [],
+    }
 }
 
 #[cfg(test)]
@@ -169,7 +175,7 @@ mod tests {
         let encoded = atom_box(b"ilst", &art_item);
 
         let result = Ilst::decode(&mut encoded.as_slice());
-        match result {
+        match result.map_err(Error::into_root) {
             Err(Error::UnexpectedBox(kind)) => assert_eq!(kind, FourCC::new(b"\xa9ART")),
             other => panic!("expected UnexpectedBox, got {other:?}"),
         }
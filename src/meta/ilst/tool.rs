@@ -90,7 +90,7 @@ mod tests {
 
         let parse_result = Tool::decode(&mut buf.as_slice());
         assert!(parse_result.is_err());
-        match parse_result.err().unwrap() {
+        match parse_result.err().unwrap().into_root() {
             Error::UnexpectedBox(four_cc) => {
                 assert_eq!(four_cc, FourCC::new(b"datx"));
             }
@@ -109,7 +109,7 @@ mod tests {
 
         let parse_result = Tool::decode(&mut buf.as_slice());
         assert!(parse_result.is_err());
-        match parse_result.err().unwrap() {
+        match parse_result.err().unwrap().into_root() {
             Error::Unsupported(s) => {
                 assert_eq!(s, "Only UTF-8 text is supported in ilst data atoms")
             }
@@ -8,6 +8,9 @@ use crate::*;
 pub struct Iprp {
     pub ipco: Ipco,
     pub ipma: Vec<Ipma>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Iprp {
@@ -17,6 +20,7 @@ impl Atom for Iprp {
         required: [ Ipco ],
         optional: [ ],
         multiple: [ Ipma ],
+        custom: custom,
     }
 }
 
@@ -44,6 +48,13 @@ impl Atom for Ipco {
         }
         Ok(())
     }
+
+    visit! {
+        required: [],
+        optional: [],
+        multiple: [],
+        any: properties,
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -118,6 +118,13 @@ impl Atom for Meta {
         }
         Ok(())
     }
+
+    visit! {
+        required: [ hdlr ],
+        optional: [],
+        multiple: [],
+        any: items,
+    }
 }
 
 #[cfg(test)]
@@ -158,6 +165,7 @@ mod tests {
                     location: "".into(),
                 }],
             },
+            custom: vec![],
         });
         expected.push(Iloc {
             item_locations: vec![ItemLocation {
@@ -229,6 +237,7 @@ mod tests {
                     },
                 ],
             }],
+            custom: vec![],
         });
         expected.push(Iref {
             references: vec![Reference {
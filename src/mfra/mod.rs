@@ -50,6 +50,12 @@ impl Atom for Mfra {
         self.tfra.iter().try_for_each(|x| x.encode(buf))?;
         self.mfro.encode(buf)
     }
+
+    visit! {
+        required: [ mfro ],
+        optional: [],
+        multiple: [ tfra ],
+    }
 }
 
 #[cfg(test)]
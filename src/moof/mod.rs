@@ -11,6 +11,9 @@ use crate::*;
 pub struct Moof {
     pub mfhd: Mfhd,
     pub traf: Vec<Traf>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Moof {
@@ -20,5 +23,6 @@ impl Atom for Moof {
         required: [ Mfhd ],
         optional: [],
         multiple: [ Traf ],
+        custom: custom,
     }
 }
@@ -21,7 +21,11 @@ pub struct Traf {
     pub saio: Vec<Saio>,
     pub meta: Option<Meta>,
     pub senc: Option<Senc>,
+    pub sdtp: Option<Sdtp>,
     pub udta: Option<Udta>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Traf {
@@ -29,7 +33,8 @@ impl Atom for Traf {
 
     nested! {
         required: [ Tfhd ],
-        optional: [ Tfdt, Meta, Senc, Udta ],
+        optional: [ Tfdt, Meta, Senc, Sdtp, Udta ],
         multiple: [ Trun, Sbgp, Sgpd, Subs, Saiz, Saio ],
+        custom: custom,
     }
 }
@@ -22,7 +22,7 @@ pub struct Tfhd {
     pub sample_description_index: Option<u32>,
     pub default_sample_duration: Option<u32>,
     pub default_sample_size: Option<u32>,
-    pub default_sample_flags: Option<u32>,
+    pub default_sample_flags: Option<SampleFlags>,
     pub duration_is_empty: bool,
     pub default_base_is_moof: bool,
 }
@@ -56,7 +56,7 @@ impl AtomExt for Tfhd {
         };
 
         let default_sample_flags = match ext.default_sample_flags {
-            true => u32::decode(buf)?.into(),
+            true => SampleFlags::decode(buf)?.into(),
             false => None,
         };
 
@@ -127,7 +127,7 @@ mod tests {
             sample_description_index: Some(1),
             default_sample_duration: Some(512),
             default_sample_size: None,
-            default_sample_flags: Some(0x1010000),
+            default_sample_flags: Some(SampleFlags::NON_KEYFRAME),
             duration_is_empty: false,
             default_base_is_moof: false,
         };
@@ -161,7 +161,7 @@ mod tests {
             default_base_is_moof: true,
             default_sample_duration: Some(512),
             default_sample_size: Some(0),
-            default_sample_flags: Some(0x1010000),
+            default_sample_flags: Some(SampleFlags::NON_KEYFRAME),
             ..Default::default()
         };
         let mut buf = Vec::new();
@@ -32,7 +32,7 @@ pub struct Trun {
 pub struct TrunEntry {
     pub duration: Option<u32>,
     pub size: Option<u32>,
-    pub flags: Option<u32>,
+    pub flags: Option<SampleFlags>,
     pub cts: Option<i32>,
 }
 
@@ -49,7 +49,7 @@ impl AtomExt for Trun {
         };
 
         let mut first_sample_flags = match ext.first_sample_flags {
-            true => u32::decode(buf)?.into(),
+            true => SampleFlags::decode(buf)?.into(),
             false => None,
         };
 
@@ -79,7 +79,7 @@ impl AtomExt for Trun {
             let sample_flags = match first_sample_flags.take() {
                 Some(flags) => Some(flags),
                 None => match ext.sample_flags {
-                    true => u32::decode(buf)?.into(),
+                    true => SampleFlags::decode(buf)?.into(),
                     false => None,
                 },
             };
@@ -142,7 +142,7 @@ impl AtomExt for Trun {
                 Some(Some(entry.size.unwrap_or(0))).encode(buf)?;
             }
             if ext.sample_flags {
-                Some(Some(entry.flags.unwrap_or(0))).encode(buf)?;
+                Some(Some(entry.flags.unwrap_or_default())).encode(buf)?;
             }
             if ext.sample_cts {
                 Some(Some(entry.cts.unwrap_or(0))).encode(buf)?;
@@ -171,7 +171,7 @@ mod test {
                 TrunEntry {
                     duration: Some(512),
                     size: Some(1000),
-                    flags: Some(0x02000000), // keyframe (sample_depends_on=2)
+                    flags: Some(SampleFlags::KEYFRAME), // keyframe (sample_depends_on=2)
                     cts: None,
                 },
                 TrunEntry {
@@ -195,7 +195,7 @@ mod test {
         let decoded = Trun::decode(&mut &buf[..]).expect("decode");
 
         // entry[0] must have the keyframe flags from first_sample_flags
-        assert_eq!(decoded.entries[0].flags, Some(0x02000000));
+        assert_eq!(decoded.entries[0].flags, Some(SampleFlags::KEYFRAME));
         // entries[1..N] must have None (they use default_sample_flags from tfhd)
         assert_eq!(decoded.entries[1].flags, None);
         assert_eq!(decoded.entries[2].flags, None);
@@ -213,13 +213,13 @@ mod test {
                 TrunEntry {
                     duration: Some(512),
                     size: Some(1000),
-                    flags: Some(0x02000000), // keyframe
+                    flags: Some(SampleFlags::KEYFRAME), // keyframe
                     cts: None,
                 },
                 TrunEntry {
                     duration: Some(512),
                     size: Some(200),
-                    flags: Some(0x01010000), // non-keyframe (explicit)
+                    flags: Some(SampleFlags::NON_KEYFRAME), // non-keyframe (explicit)
                     cts: None,
                 },
                 TrunEntry {
@@ -237,9 +237,9 @@ mod test {
         let decoded = Trun::decode(&mut &buf[..]).expect("decode");
 
         // Mixed Some/None: encoder backfills None with 0 and emits per-sample flags.
-        assert_eq!(decoded.entries[0].flags, Some(0x02000000));
-        assert_eq!(decoded.entries[1].flags, Some(0x01010000));
-        assert_eq!(decoded.entries[2].flags, Some(0)); // was None, backfilled to 0
+        assert_eq!(decoded.entries[0].flags, Some(SampleFlags::KEYFRAME));
+        assert_eq!(decoded.entries[1].flags, Some(SampleFlags::NON_KEYFRAME));
+        assert_eq!(decoded.entries[2].flags, Some(SampleFlags::default())); // was None, backfilled to 0
     }
 
     /// When all entries have explicit flags, per-sample flags are used.
@@ -251,13 +251,13 @@ mod test {
                 TrunEntry {
                     duration: Some(512),
                     size: Some(1000),
-                    flags: Some(0x02000000),
+                    flags: Some(SampleFlags::KEYFRAME),
                     cts: None,
                 },
                 TrunEntry {
                     duration: Some(512),
                     size: Some(200),
-                    flags: Some(0x01010000),
+                    flags: Some(SampleFlags::NON_KEYFRAME),
                     cts: None,
                 },
             ],
@@ -268,8 +268,8 @@ mod test {
 
         let decoded = Trun::decode(&mut &buf[..]).expect("decode");
 
-        assert_eq!(decoded.entries[0].flags, Some(0x02000000));
-        assert_eq!(decoded.entries[1].flags, Some(0x01010000));
+        assert_eq!(decoded.entries[0].flags, Some(SampleFlags::KEYFRAME));
+        assert_eq!(decoded.entries[1].flags, Some(SampleFlags::NON_KEYFRAME));
     }
 
     /// Entries with None duration (inherited from tfhd default_sample_duration)
@@ -282,7 +282,7 @@ mod test {
                 TrunEntry {
                     duration: Some(512),
                     size: Some(1000),
-                    flags: Some(0x02000000),
+                    flags: Some(SampleFlags::KEYFRAME),
                     cts: None,
                 },
                 TrunEntry {
@@ -0,0 +1,246 @@
+use crate::*;
+
+impl Moov {
+    /// Recompute the header fields that are derived from the rest of the tree.
+    ///
+    /// - `mdhd` duration from the sample durations, in the media timescale.
+    /// - `tkhd` duration from the edit list, or the media duration, in the movie timescale.
+    /// - `mvhd` duration from the longest track.
+    /// - `mvhd` next_track_id when it's not larger than every track ID.
+    /// - `mehd` fragment duration when any fragments are provided, including the samples in the moov.
+    ///
+    /// Durations that don't fit in 32 bits are fine, as the encoders pick the header version from the values.
+    /// Returns an error if a track already uses the largest track ID, as there's no next one.
+    pub fn finalize(&mut self, fragments: &[Moof]) -> Result<()> {
+        let movie_timescale = self.mvhd.timescale;
+        if movie_timescale == 0 {
+            return Err(Error::Unsupported("mvhd timescale is zero"));
+        }
+
+        let trex = self
+            .mvex
+            .as_ref()
+            .map(|mvex| mvex.trex.as_slice())
+            .unwrap_or_default();
+
+        let mut fragment_duration = 0;
+
+        for trak in &mut self.trak {
+            let media_timescale = trak.mdia.mdhd.timescale;
+            if media_timescale == 0 {
+                return Err(Error::Unsupported("mdhd timescale is zero"));
+            }
+
+            let media_duration: u64 = trak
+                .mdia
+                .minf
+                .stbl
+                .stts
+                .entries
+                .iter()
+                .map(|entry| entry.sample_count as u64 * entry.sample_delta as u64)
+                .sum();
+
+            trak.mdia.mdhd.duration = media_duration;
+            trak.tkhd.duration = presentation(trak, media_duration, movie_timescale);
+
+            if !fragments.is_empty() {
+                let total = media_duration + fragmented(trak.tkhd.track_id, fragments, trex);
+                let duration = presentation(trak, total, movie_timescale);
+                fragment_duration = fragment_duration.max(duration);
+            }
+        }
+
+        self.mvhd.duration = self
+            .trak
+            .iter()
+            .map(|trak| trak.tkhd.duration)
+            .max()
+            .unwrap_or(0);
+
+        let max_track_id = self
+            .trak
+            .iter()
+            .map(|trak| trak.tkhd.track_id)
+            .max()
+            .unwrap_or(0);
+        if self.mvhd.next_track_id <= max_track_id {
+            self.mvhd.next_track_id = max_track_id
+                .checked_add(1)
+                .ok_or(Error::TooLarge(Mvhd::KIND))?;
+        }
+
+        if let Some(mvex) = &mut self.mvex {
+            if !fragments.is_empty() {
+                mvex.mehd = Some(Mehd { fragment_duration });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The duration of the track in the movie timescale, given the duration of its media.
+fn presentation(trak: &Trak, media_duration: u64, movie_timescale: u32) -> u64 {
+    let media_timescale = trak.mdia.mdhd.timescale;
+
+    let Some(elst) = trak.edts.as_ref().and_then(|edts| edts.elst.as_ref()) else {
+        return rescale(media_duration, media_timescale, movie_timescale);
+    };
+
+    let mut duration = 0;
+    for (index, entry) in elst.entries.iter().enumerate() {
+        duration += match (entry.segment_duration, entry.media_time) {
+            // The last edit extends to the end of the media when its duration is zero.
+            (0, Some(media_time)) if index + 1 == elst.entries.len() => rescale(
+                media_duration.saturating_sub(media_time),
+                media_timescale,
+                movie_timescale,
+            ),
+            (segment_duration, _) => segment_duration,
+        };
+    }
+
+    duration
+}
+
+// The total duration of the samples in the fragments for a track, in the media timescale.
+fn fragmented(track_id: u32, fragments: &[Moof], trex: &[Trex]) -> u64 {
+    let trex = trex.iter().find(|trex| trex.track_id == track_id);
+    let mut duration = 0;
+
+    for traf in fragments.iter().flat_map(|moof| &moof.traf) {
+        if traf.tfhd.track_id != track_id {
+            continue;
+        }
+
+        let default = traf
+            .tfhd
+            .default_sample_duration
+            .or(trex.map(|trex| trex.default_sample_duration))
+            .unwrap_or(0);
+
+        for entry in traf.trun.iter().flat_map(|trun| &trun.entries) {
+            duration += entry.duration.unwrap_or(default) as u64;
+        }
+    }
+
+    duration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moov() -> Moov {
+        let mut moov = <Moov as ReadUntil>::read_until(
+            &mut include_bytes!("../test/uncompressed.mp4").as_slice(),
+        )
+        .unwrap();
+
+        moov.mvhd.timescale = 1000;
+        moov.mvhd.duration = 0;
+        moov.mvhd.next_track_id = 0;
+
+        let trak = &mut moov.trak[0];
+        trak.tkhd.duration = 0;
+        trak.mdia.mdhd.timescale = 48000;
+        trak.mdia.mdhd.duration = 0;
+        trak.mdia.minf.stbl.stts = Stts {
+            entries: vec![SttsEntry {
+                sample_count: 100,
+                sample_delta: 1024,
+            }],
+        };
+
+        moov
+    }
+
+    #[test]
+    fn durations() {
+        let mut moov = moov();
+        moov.finalize(&[]).unwrap();
+
+        assert_eq!(moov.trak[0].mdia.mdhd.duration, 102_400);
+        assert_eq!(moov.trak[0].tkhd.duration, 2133);
+        assert_eq!(moov.mvhd.duration, 2133);
+        assert_eq!(moov.mvhd.next_track_id, moov.trak[0].tkhd.track_id + 1);
+
+        moov.trak[0].tkhd.track_id = u32::MAX;
+        assert!(matches!(moov.finalize(&[]), Err(Error::TooLarge(_))));
+    }
+
+    #[test]
+    fn edits() {
+        let mut moov = moov();
+
+        // Skip the priming samples and play until the end of the media.
+        moov.trak[0].edts = Some(Edts {
+            elst: Some(Elst {
+                entries: vec![
+                    ElstEntry {
+                        segment_duration: 500,
+                        media_time: None,
+                        media_rate: 1.into(),
+                    },
+                    ElstEntry {
+                        segment_duration: 0,
+                        media_time: Some(2400),
+                        media_rate: 1.into(),
+                    },
+                ],
+            }),
+            custom: vec![],
+        });
+
+        moov.finalize(&[]).unwrap();
+        assert_eq!(moov.trak[0].tkhd.duration, 500 + 2083);
+        assert_eq!(moov.mvhd.duration, 500 + 2083);
+    }
+
+    #[test]
+    fn fragments() {
+        let mut moov = moov();
+        let track_id = moov.trak[0].tkhd.track_id;
+
+        moov.mvex = Some(Mvex {
+            mehd: None,
+            trex: vec![Trex {
+                track_id,
+                default_sample_duration: 1024,
+                ..Default::default()
+            }],
+            leva: None,
+            custom: vec![],
+        });
+
+        let moof = Moof {
+            mfhd: Mfhd { sequence_number: 1 },
+            traf: vec![Traf {
+                tfhd: Tfhd {
+                    track_id,
+                    ..Default::default()
+                },
+                trun: vec![Trun {
+                    data_offset: None,
+                    entries: vec![
+                        TrunEntry {
+                            duration: Some(2048),
+                            ..Default::default()
+                        },
+                        TrunEntry::default(),
+                    ],
+                }],
+                ..Default::default()
+            }],
+            custom: vec![],
+        };
+
+        moov.finalize(&[moof]).unwrap();
+
+        // 100 samples in the moov plus 3 frames worth of fragments.
+        let mehd = moov.mvex.as_ref().unwrap().mehd.as_ref().unwrap();
+        assert_eq!(mehd.fragment_duration, 103 * 1024 * 1000 / 48000);
+        assert_eq!(moov.mvhd.duration, 2133);
+    }
+}
@@ -1,4 +1,5 @@
 mod ainf;
+mod finalize;
 mod mvex;
 mod mvhd;
 mod trak;
@@ -21,6 +22,9 @@ pub struct Moov {
     pub trak: Vec<Trak>,
     pub udta: Option<Udta>,
     pub ainf: Option<Ainf>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Moov {
@@ -30,6 +34,7 @@ impl Atom for Moov {
         required: [ Mvhd ],
         optional: [ Ainf, Meta, Mvex, Udta ],
         multiple: [ Trak ],
+        custom: custom,
     }
 }
 
@@ -47,11 +52,116 @@ mod test {
         encoded[..4].copy_from_slice(&size.to_be_bytes());
 
         assert!(matches!(
-            Moov::decode(&mut encoded.as_slice()),
+            Moov::decode(&mut encoded.as_slice()).map_err(Error::into_root),
             Err(Error::UnderDecode(kind)) if kind == Moov::KIND
         ));
     }
 
+    #[test]
+    fn error_context() {
+        let trak = Trak {
+            mdia: Mdia {
+                minf: Minf {
+                    stbl: Stbl {
+                        stss: Some(Stss { entries: vec![1] }),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let moov = Moov {
+            trak: vec![trak.clone(), trak],
+            ..Default::default()
+        };
+
+        let mut encoded = Vec::new();
+        moov.encode(&mut encoded).unwrap();
+
+        // Claim the second stss has more entries than it does.
+        let stss = encoded
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| *w == b"stss")
+            .map(|(i, _)| i - 4)
+            .nth(1)
+            .unwrap();
+        encoded[stss + 12..stss + 16].copy_from_slice(&2u32.to_be_bytes());
+
+        let check = |err: Error| {
+            let context = err.context().expect("missing context");
+            assert_eq!(context.path.to_string(), "moov/trak[1]/mdia/minf/stbl/stss");
+            assert_eq!(context.offset, stss as u64);
+            assert!(matches!(err.root(), Error::OverDecode(kind) if *kind == Stss::KIND));
+        };
+
+        check(Moov::decode(&mut encoded.as_slice()).unwrap_err());
+        check(<Moov as ReadFrom>::read_from(&mut encoded.as_slice()).unwrap_err());
+        check(Any::decode(&mut encoded.as_slice()).unwrap_err());
+
+        let err = Moov::decode(&mut encoded.as_slice())
+            .unwrap_err()
+            .offset_by(100);
+        assert_eq!(err.context().unwrap().offset, stss as u64 + 100);
+
+        // Any skipped atoms are included in the offset.
+        let mut file = vec![0, 0, 0, 12, b'f', b'r', b'e', b'e', 0, 0, 0, 0];
+        file.extend_from_slice(&encoded);
+
+        let err = <Moov as ReadUntil>::read_until(&mut file.as_slice()).unwrap_err();
+        assert_eq!(err.context().unwrap().offset, 12 + stss as u64);
+
+        // A largesize header moves the children by another 8 bytes, even though the size fits in 32 bits.
+        let mut large = vec![0, 0, 0, 1];
+        large.extend_from_slice(b"moov");
+        large.extend_from_slice(&(encoded.len() as u64 + 8).to_be_bytes());
+        large.extend_from_slice(&encoded[8..]);
+
+        let err = Moov::decode(&mut large.as_slice()).unwrap_err();
+        assert_eq!(err.context().unwrap().offset, stss as u64 + 8);
+        let err = <Moov as ReadFrom>::read_from(&mut large.as_slice()).unwrap_err();
+        assert_eq!(err.context().unwrap().offset, stss as u64 + 8);
+        let err = <Any as ReadFrom>::read_from(&mut large.as_slice()).unwrap_err();
+        assert_eq!(err.context().unwrap().offset, stss as u64 + 8);
+
+        let mut reader = LazyReader::new(std::io::Cursor::new(&large)).unwrap();
+        let atom = reader.find(Moov::KIND).unwrap().unwrap();
+        let err = reader.decode::<Moov>(&atom).unwrap_err();
+        assert_eq!(err.context().unwrap().offset, stss as u64 + 8);
+
+        // The header only describes the atom, not how it was encoded.
+        let header = Header::decode(&mut large.as_slice()).unwrap();
+        assert_eq!(header, Header::new(Moov::KIND, Some(encoded.len() - 8)));
+
+        #[cfg(feature = "tokio")]
+        {
+            use crate::{AsyncReadFrom, AsyncReadUntil};
+            use std::future::Future;
+            use std::task::{Context, Poll, Waker};
+
+            // Reading from a slice never waits, so the future completes on the first poll.
+            fn ready<T>(future: impl Future<Output = T>) -> T {
+                let mut context = Context::from_waker(Waker::noop());
+                match std::pin::pin!(future).poll(&mut context) {
+                    Poll::Ready(output) => output,
+                    Poll::Pending => panic!("future is pending"),
+                }
+            }
+
+            check(ready(<Moov as AsyncReadFrom>::read_from(&mut encoded.as_slice())).unwrap_err());
+
+            let err = ready(<Moov as AsyncReadFrom>::read_from(&mut large.as_slice())).unwrap_err();
+            assert_eq!(err.context().unwrap().offset, stss as u64 + 8);
+
+            let err =
+                ready(<Moov as AsyncReadUntil>::read_until(&mut file.as_slice())).unwrap_err();
+            assert_eq!(err.context().unwrap().offset, 12 + stss as u64);
+        }
+    }
+
     #[test]
     fn test_meta() {
         const ENCODED: &[u8] = &[
@@ -155,7 +265,9 @@ mod test {
                         default_sample_description_index: 1,
                         default_sample_duration: 3000,
                         ..Default::default()
-                    }]
+                    }],
+                    leva: None,
+                    custom: vec![],
                 }),
                 trak: vec![Trak {
                     tkhd: Tkhd {
@@ -175,7 +287,8 @@ mod test {
                                 media_rate: 1.into(),
                                 ..Default::default()
                             }]
-                        })
+                        }),
+                        custom: vec![],
                     }),
                     mdia: Mdia {
                         mdhd: Mdhd {
@@ -201,7 +314,8 @@ mod test {
                             dinf: Dinf {
                                 dref: Dref {
                                     urls: vec![Url::default()]
-                                }
+                                },
+                                custom: vec![],
                             },
                             stbl: Stbl {
                                 stsd: Stsd {
@@ -240,11 +354,13 @@ mod test {
                                     }
                                     .into()],
                                 },
+                                stsz: Some(Stsz::default()),
                                 stco: Some(Stco::default()),
                                 ..Default::default()
                             },
                             ..Default::default()
-                        }
+                        },
+                        custom: vec![],
                     },
                     ..Default::default()
                 }],
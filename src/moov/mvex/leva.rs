@@ -0,0 +1,177 @@
+use crate::*;
+
+/// How the samples of a track are assigned to a level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LevelAssignment {
+    /// The samples mapped to the level's index in the `sbgp` of this grouping type (assignment_type 0).
+    SampleGroup { grouping_type: FourCC },
+
+    /// Like [Self::SampleGroup], but with a grouping type parameter (assignment_type 1).
+    SampleGroupParameter {
+        grouping_type: FourCC,
+        grouping_type_parameter: u32,
+    },
+
+    /// Every sample of the track (assignment_type 2).
+    Track,
+
+    /// Every sample of the track (assignment_type 3).
+    ///
+    /// This differs from [Self::Track] in how the `ssix` ranges of the level are processed, see Section 8.16.4.3.
+    TrackType3,
+
+    /// The samples of a sub-track (assignment_type 4).
+    SubTrack { sub_track_id: u32 },
+}
+
+impl LevelAssignment {
+    pub fn assignment_type(&self) -> u8 {
+        match self {
+            Self::SampleGroup { .. } => 0,
+            Self::SampleGroupParameter { .. } => 1,
+            Self::Track => 2,
+            Self::TrackType3 => 3,
+            Self::SubTrack { .. } => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Level {
+    pub track_id: u32,
+
+    /// Whether a subsegment of this level is padded to the full subsegment, ex. with zeros.
+    pub padding_flag: bool,
+    pub assignment: LevelAssignment,
+}
+
+/// LevelAssignmentBox, ISO/IEC 14496-12 Section 8.8.13
+///
+/// The levels used by the `ssix`, numbered from 1 in order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Leva {
+    pub levels: Vec<Level>,
+}
+
+impl AtomExt for Leva {
+    type Ext = ();
+
+    const KIND_EXT: FourCC = FourCC::new(b"leva");
+
+    fn decode_body_ext<B: Buf>(buf: &mut B, _ext: ()) -> Result<Self> {
+        let level_count = u8::decode(buf)?;
+
+        let mut levels = Vec::with_capacity(level_count as usize);
+        for _ in 0..level_count {
+            let track_id = u32::decode(buf)?;
+            let flag_and_type = u8::decode(buf)?;
+
+            let assignment = match flag_and_type & 0x7f {
+                0 => LevelAssignment::SampleGroup {
+                    grouping_type: FourCC::decode(buf)?,
+                },
+                1 => LevelAssignment::SampleGroupParameter {
+                    grouping_type: FourCC::decode(buf)?,
+                    grouping_type_parameter: u32::decode(buf)?,
+                },
+                2 => LevelAssignment::Track,
+                3 => LevelAssignment::TrackType3,
+                4 => LevelAssignment::SubTrack {
+                    sub_track_id: u32::decode(buf)?,
+                },
+                _ => return Err(Error::Unsupported("leva assignment type")),
+            };
+
+            levels.push(Level {
+                track_id,
+                padding_flag: flag_and_type & 0x80 != 0,
+                assignment,
+            });
+        }
+
+        Ok(Leva { levels })
+    }
+
+    fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        let level_count: u8 = self
+            .levels
+            .len()
+            .try_into()
+            .map_err(|_| Error::TooLarge(Self::KIND))?;
+        level_count.encode(buf)?;
+
+        for level in &self.levels {
+            level.track_id.encode(buf)?;
+
+            ((level.padding_flag as u8) << 7 | level.assignment.assignment_type()).encode(buf)?;
+
+            match &level.assignment {
+                LevelAssignment::SampleGroup { grouping_type } => grouping_type.encode(buf)?,
+                LevelAssignment::SampleGroupParameter {
+                    grouping_type,
+                    grouping_type_parameter,
+                } => {
+                    grouping_type.encode(buf)?;
+                    grouping_type_parameter.encode(buf)?;
+                }
+                LevelAssignment::Track | LevelAssignment::TrackType3 => {}
+                LevelAssignment::SubTrack { sub_track_id } => sub_track_id.encode(buf)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leva() {
+        let expected = Leva {
+            levels: vec![
+                Level {
+                    track_id: 1,
+                    padding_flag: false,
+                    assignment: LevelAssignment::SampleGroup {
+                        grouping_type: FourCC::new(b"tele"),
+                    },
+                },
+                Level {
+                    track_id: 1,
+                    padding_flag: true,
+                    assignment: LevelAssignment::SampleGroupParameter {
+                        grouping_type: FourCC::new(b"sap "),
+                        grouping_type_parameter: 7,
+                    },
+                },
+                Level {
+                    track_id: 2,
+                    padding_flag: false,
+                    assignment: LevelAssignment::Track,
+                },
+                Level {
+                    track_id: 2,
+                    padding_flag: false,
+                    assignment: LevelAssignment::TrackType3,
+                },
+                Level {
+                    track_id: 2,
+                    padding_flag: false,
+                    assignment: LevelAssignment::SubTrack { sub_track_id: 5 },
+                },
+            ],
+        };
+
+        let mut buf = Vec::new();
+        expected.encode(&mut buf).unwrap();
+
+        let mut buf = buf.as_ref();
+        let decoded = Leva::decode(&mut buf).unwrap();
+        assert_eq!(decoded, expected);
+    }
+}
@@ -1,6 +1,8 @@
+mod leva;
 mod mehd;
 mod trex;
 
+pub use leva::*;
 pub use mehd::*;
 pub use trex::*;
 
@@ -11,6 +13,10 @@ use crate::*;
 pub struct Mvex {
     pub mehd: Option<Mehd>,
     pub trex: Vec<Trex>,
+    pub leva: Option<Leva>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Mvex {
@@ -18,7 +24,8 @@ impl Atom for Mvex {
 
     nested! {
         required: [],
-        optional: [ Mehd ],
+        optional: [ Mehd, Leva ],
         multiple: [ Trex ],
+        custom: custom,
     }
 }
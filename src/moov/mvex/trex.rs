@@ -7,7 +7,7 @@ pub struct Trex {
     pub default_sample_description_index: u32,
     pub default_sample_duration: u32,
     pub default_sample_size: u32,
-    pub default_sample_flags: u32,
+    pub default_sample_flags: SampleFlags,
 }
 
 impl AtomExt for Trex {
@@ -21,7 +21,7 @@ impl AtomExt for Trex {
             default_sample_description_index: u32::decode(buf)?,
             default_sample_duration: u32::decode(buf)?,
             default_sample_size: u32::decode(buf)?,
-            default_sample_flags: u32::decode(buf)?,
+            default_sample_flags: SampleFlags::decode(buf)?,
         })
     }
 
@@ -47,7 +47,7 @@ mod tests {
             default_sample_description_index: 1,
             default_sample_duration: 1000,
             default_sample_size: 0,
-            default_sample_flags: 65536,
+            default_sample_flags: 65536.into(),
         };
         let mut buf = Vec::new();
         expected.encode(&mut buf).unwrap();
@@ -1,5 +1,8 @@
 mod elst;
+mod timeline;
+
 pub use elst::*;
+pub use timeline::*;
 
 use crate::*;
 
@@ -7,6 +10,9 @@ use crate::*;
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edts {
     pub elst: Option<Elst>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Edts {
@@ -16,5 +22,6 @@ impl Atom for Edts {
         required: [],
         optional: [ Elst ],
         multiple: [],
+        custom: custom,
     }
 }
@@ -0,0 +1,294 @@
+use crate::*;
+
+/// Maps media time to presentation time (and back) using an edit list.
+///
+/// Every time is expressed in the media timescale (`mdhd`).
+/// The edit list durations use the movie timescale (`mvhd`) and are converted without accumulating rounding error.
+///
+/// A track without an edit list is presented as-is, starting at zero.
+/// A leading empty edit delays the start of the track, while a non-zero `media_time` skips media,
+/// for example the AAC priming samples.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    segments: Vec<TimelineSegment>,
+}
+
+/// A single edit, converted to the media timescale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimelineSegment {
+    /// The presentation time at which the segment starts.
+    pub start: u64,
+
+    /// The duration of the segment in presentation time, or None if it extends to the end of the media.
+    ///
+    /// Only the last edit can be open-ended, signaled with a zero `segment_duration` in fragmented files.
+    pub duration: Option<u64>,
+
+    /// The media time shown at the start of the segment, or None for an empty edit.
+    pub media_time: Option<u64>,
+
+    /// The playback rate as 16.16 fixed point; 0 is a dwell that freezes on `media_time`.
+    pub media_rate: i32,
+}
+
+impl TimelineSegment {
+    /// The presentation time at which the segment ends, or None if it's open-ended.
+    pub fn end(&self) -> Option<u64> {
+        self.duration.map(|duration| self.start + duration)
+    }
+
+    /// Returns true if the segment doesn't contain any media.
+    pub fn is_empty(&self) -> bool {
+        self.media_time.is_none()
+    }
+
+    /// Returns true if the segment repeats a single media time.
+    pub fn is_dwell(&self) -> bool {
+        self.media_time.is_some() && self.media_rate == 0
+    }
+
+    fn contains(&self, time: u64) -> bool {
+        time >= self.start && self.end().is_none_or(|end| time < end)
+    }
+
+    // The amount of media consumed over the given presentation duration.
+    pub(crate) fn media_duration(&self, duration: u64) -> u64 {
+        ((duration as u128 * self.media_rate.max(0) as u128) >> 16) as u64
+    }
+}
+
+impl Timeline {
+    /// Build a timeline from an edit list, given the movie (`mvhd`) and media (`mdhd`) timescales.
+    pub fn new(elst: &Elst, movie_timescale: u32, media_timescale: u32) -> Result<Self> {
+        if movie_timescale == 0 || media_timescale == 0 {
+            return Err(Error::Unsupported("timeline timescale is zero"));
+        }
+
+        let mut segments = Vec::with_capacity(elst.entries.len());
+
+        // Sum the durations in the movie timescale and convert each boundary, avoiding drift.
+        let mut movie_time = 0u64;
+
+        for (index, entry) in elst.entries.iter().enumerate() {
+            let start = rescale(movie_time, movie_timescale, media_timescale);
+            movie_time = movie_time
+                .checked_add(entry.segment_duration)
+                .ok_or(Error::Unsupported("elst duration overflow"))?;
+            let end = rescale(movie_time, movie_timescale, media_timescale);
+
+            let last = index + 1 == elst.entries.len();
+            let duration = match entry.segment_duration {
+                0 if last && entry.media_time.is_some() => None,
+                _ => Some(end - start),
+            };
+
+            let rate = entry.media_rate;
+            let media_rate = ((rate.integer() as i32) << 16) | rate.decimal() as u16 as i32;
+
+            segments.push(TimelineSegment {
+                start,
+                duration,
+                media_time: entry.media_time,
+                media_rate,
+            });
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// A timeline that presents the media as-is, for a track without an edit list.
+    pub fn identity(media_duration: u64) -> Self {
+        Self {
+            segments: vec![TimelineSegment {
+                start: 0,
+                duration: Some(media_duration),
+                media_time: Some(0),
+                media_rate: 1 << 16,
+            }],
+        }
+    }
+
+    /// Build the timeline of a track, falling back to the identity when there's no edit list.
+    pub fn from_trak(trak: &Trak, movie_timescale: u32) -> Result<Self> {
+        let mdhd = &trak.mdia.mdhd;
+
+        match trak.edts.as_ref().and_then(|edts| edts.elst.as_ref()) {
+            Some(elst) => Self::new(elst, movie_timescale, mdhd.timescale),
+            None => Ok(Self::identity(mdhd.duration)),
+        }
+    }
+
+    /// The segments in presentation order.
+    pub fn segments(&self) -> &[TimelineSegment] {
+        &self.segments
+    }
+
+    /// The presentation time at which media first appears, after any initial empty edits.
+    pub fn start(&self) -> Option<u64> {
+        self.segments
+            .iter()
+            .find(|segment| !segment.is_empty())
+            .map(|segment| segment.start)
+    }
+
+    /// The presentation time at which the track ends, or None if the last edit is open-ended.
+    pub fn end(&self) -> Option<u64> {
+        match self.segments.last() {
+            Some(segment) => segment.end(),
+            None => Some(0),
+        }
+    }
+
+    /// Map a media time (composition time) to the first presentation time at which it's shown.
+    ///
+    /// Returns None if the media time is edited out.
+    pub fn to_presentation(&self, media_time: u64) -> Option<u64> {
+        self.segments.iter().find_map(|segment| {
+            let first = segment.media_time?;
+            if media_time < first || segment.media_rate < 0 {
+                return None;
+            }
+
+            if segment.media_rate == 0 {
+                return (media_time == first).then_some(segment.start);
+            }
+
+            let offset = media_time - first;
+            if segment
+                .duration
+                .is_some_and(|duration| offset >= segment.media_duration(duration))
+            {
+                return None;
+            }
+
+            let offset = ((offset as u128) << 16) / segment.media_rate as u128;
+            Some(segment.start + offset as u64)
+        })
+    }
+
+    /// Map a presentation time to the media time shown at that instant.
+    ///
+    /// Returns None during an empty edit or past the end of the timeline.
+    pub fn to_media(&self, presentation_time: u64) -> Option<u64> {
+        let segment = self
+            .segments
+            .iter()
+            .find(|segment| segment.contains(presentation_time))?;
+
+        let first = segment.media_time?;
+        if segment.media_rate < 0 {
+            return None;
+        }
+
+        Some(first + segment.media_duration(presentation_time - segment.start))
+    }
+}
+
+pub(crate) fn rescale(time: u64, from: u32, to: u32) -> u64 {
+    (time as u128 * to as u128 / from as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(segment_duration: u64, media_time: Option<u64>, rate: i16) -> ElstEntry {
+        ElstEntry {
+            segment_duration,
+            media_time,
+            media_rate: rate.into(),
+        }
+    }
+
+    #[test]
+    fn priming() {
+        // 1024 samples of AAC priming are skipped, at a movie timescale of 1000.
+        let elst = Elst {
+            entries: vec![entry(10_000, Some(1024), 1)],
+        };
+
+        let timeline = Timeline::new(&elst, 1000, 48_000).unwrap();
+        assert_eq!(timeline.start(), Some(0));
+        assert_eq!(timeline.end(), Some(480_000));
+
+        assert_eq!(timeline.to_presentation(0), None);
+        assert_eq!(timeline.to_presentation(1024), Some(0));
+        assert_eq!(timeline.to_presentation(2048), Some(1024));
+        assert_eq!(timeline.to_presentation(1024 + 480_000), None);
+
+        assert_eq!(timeline.to_media(0), Some(1024));
+        assert_eq!(timeline.to_media(479_999), Some(481_023));
+        assert_eq!(timeline.to_media(480_000), None);
+    }
+
+    #[test]
+    fn empty_edit() {
+        // The track starts half a second into the presentation.
+        let elst = Elst {
+            entries: vec![entry(500, None, 1), entry(2000, Some(0), 1)],
+        };
+
+        let timeline = Timeline::new(&elst, 1000, 90_000).unwrap();
+        assert_eq!(timeline.start(), Some(45_000));
+        assert_eq!(timeline.end(), Some(225_000));
+
+        assert_eq!(timeline.to_media(0), None);
+        assert_eq!(timeline.to_media(45_000), Some(0));
+        assert_eq!(timeline.to_presentation(0), Some(45_000));
+        assert_eq!(timeline.to_presentation(90_000), Some(135_000));
+    }
+
+    #[test]
+    fn dwell() {
+        // Show the first frame for a second, then play from the start.
+        let elst = Elst {
+            entries: vec![entry(1000, Some(0), 0), entry(1000, Some(0), 1)],
+        };
+
+        let timeline = Timeline::new(&elst, 1000, 1000).unwrap();
+        assert!(timeline.segments()[0].is_dwell());
+
+        assert_eq!(timeline.to_media(500), Some(0));
+        assert_eq!(timeline.to_media(1500), Some(500));
+        assert_eq!(timeline.to_presentation(0), Some(0));
+        assert_eq!(timeline.to_presentation(500), Some(1500));
+    }
+
+    #[test]
+    fn rate() {
+        let elst = Elst {
+            entries: vec![ElstEntry {
+                segment_duration: 1000,
+                media_time: Some(0),
+                media_rate: FixedPoint::new(0, i16::MIN), // 0.5
+            }],
+        };
+
+        let timeline = Timeline::new(&elst, 1000, 1000).unwrap();
+        assert_eq!(timeline.to_media(1000), None);
+        assert_eq!(timeline.to_media(600), Some(300));
+        assert_eq!(timeline.to_presentation(300), Some(600));
+        assert_eq!(timeline.to_presentation(500), None);
+    }
+
+    #[test]
+    fn open_ended() {
+        let elst = Elst {
+            entries: vec![entry(0, Some(512), 1)],
+        };
+
+        let timeline = Timeline::new(&elst, 1000, 44_100).unwrap();
+        assert_eq!(timeline.end(), None);
+        assert_eq!(timeline.to_presentation(1_000_512), Some(1_000_000));
+        assert_eq!(timeline.to_media(1_000_000), Some(1_000_512));
+    }
+
+    #[test]
+    fn identity() {
+        let timeline = Timeline::identity(1000);
+        assert_eq!(timeline.start(), Some(0));
+        assert_eq!(timeline.end(), Some(1000));
+        assert_eq!(timeline.to_presentation(10), Some(10));
+        assert_eq!(timeline.to_media(10), Some(10));
+    }
+}
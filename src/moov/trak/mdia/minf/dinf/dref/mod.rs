@@ -35,4 +35,16 @@ impl AtomExt for Dref {
 
         Ok(())
     }
+
+    fn visit_children_ext<'a>(&'a self, path: &mut AtomPath, visitor: &mut dyn Visitor<'a>) {
+        for (index, url) in self.urls.iter().enumerate() {
+            visit_atom(url, Some(index), path, visitor);
+        }
+    }
+
+    fn visit_children_mut_ext(&mut self, path: &mut AtomPath, visitor: &mut dyn VisitorMut) {
+        for (index, url) in self.urls.iter_mut().enumerate() {
+            visit_atom_mut(url, Some(index), path, visitor);
+        }
+    }
 }
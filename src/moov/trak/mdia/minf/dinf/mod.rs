@@ -7,6 +7,9 @@ use crate::*;
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dinf {
     pub dref: Dref,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Dinf {
@@ -16,5 +19,6 @@ impl Atom for Dinf {
         required: [ Dref ],
         optional: [],
         multiple: [],
+        custom: custom,
     }
 }
@@ -26,6 +26,9 @@ pub struct Minf {
     pub hmhd: Option<Hmhd>,
     pub dinf: Dinf,
     pub stbl: Stbl,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Minf {
@@ -35,5 +38,6 @@ impl Atom for Minf {
         required: [ Dinf, Stbl ],
         optional: [ Vmhd, Smhd, Nmhd, Sthd, Hmhd ],
         multiple: [],
+        custom: custom,
     }
 }
@@ -0,0 +1,278 @@
+use crate::*;
+
+impl Stbl {
+    /// The auxiliary information of each sample in decode order, sliced from the file.
+    ///
+    /// The `saiz` and `saio` are matched by their [AuxInfo]; when `aux_info` is provided, a pair without an explicit type is used as a fallback, as the type is then implied by the sample entry (ex. the `cenc` scheme).
+    /// The `saio` offsets are absolute, either a single offset for all samples or one per chunk.
+    /// Returns an empty list if there's no matching `saiz`.
+    pub fn sample_aux_info<'a>(
+        &self,
+        aux_info: Option<&AuxInfo>,
+        file: &'a [u8],
+    ) -> Result<Vec<&'a [u8]>> {
+        let Some((saiz, saio)) = find(&self.saiz, &self.saio, aux_info)? else {
+            return Ok(Vec::new());
+        };
+
+        let runs = match saio.offsets.len() {
+            1 => vec![saiz.sample_count],
+            _ => self.chunks()?.iter().map(|chunk| chunk.samples).collect(),
+        };
+
+        resolve(saiz, saio, &runs, 0, 0, file)
+    }
+}
+
+impl Traf {
+    /// The auxiliary information of each sample in this fragment, sliced from the segment.
+    ///
+    /// The `saiz` and `saio` are matched by their [AuxInfo], like [Stbl::sample_aux_info].
+    /// The `saio` offsets are relative to the `tfhd` base data offset if present, otherwise the start of the `moof` when `default_base_is_moof` is set.
+    /// There's either a single offset for all samples or one per `trun`.
+    ///
+    /// Both `moof_offset` and `segment_offset` are file offsets, the latter being where `segment` starts.
+    /// Returns an error if neither base is signaled, as the offsets then continue from the data of the previous `traf`.
+    pub fn sample_aux_info<'a>(
+        &self,
+        aux_info: Option<&AuxInfo>,
+        moof_offset: u64,
+        segment_offset: u64,
+        segment: &'a [u8],
+    ) -> Result<Vec<&'a [u8]>> {
+        let Some((saiz, saio)) = find(&self.saiz, &self.saio, aux_info)? else {
+            return Ok(Vec::new());
+        };
+
+        let runs = match saio.offsets.len() {
+            1 => vec![saiz.sample_count],
+            _ => self
+                .trun
+                .iter()
+                .map(|trun| trun.entries.len() as u32)
+                .collect(),
+        };
+
+        let base = match self.tfhd.base_data_offset {
+            Some(base) => base,
+            None if self.tfhd.default_base_is_moof => moof_offset,
+            None => {
+                return Err(Error::Unsupported(
+                    "saio offsets relative to the previous traf",
+                ))
+            }
+        };
+
+        resolve(saiz, saio, &runs, base, segment_offset, segment)
+    }
+}
+
+fn find<'a>(
+    saiz: &'a [Saiz],
+    saio: &'a [Saio],
+    aux_info: Option<&AuxInfo>,
+) -> Result<Option<(&'a Saiz, &'a Saio)>> {
+    let matches = |info: Option<&AuxInfo>| info == aux_info;
+    let implied = |info: Option<&AuxInfo>| aux_info.is_some() && info.is_none();
+
+    let Some(saiz) = saiz
+        .iter()
+        .find(|saiz| matches(saiz.aux_info.as_ref()))
+        .or_else(|| saiz.iter().find(|saiz| implied(saiz.aux_info.as_ref())))
+    else {
+        return Ok(None);
+    };
+
+    let saio = saio
+        .iter()
+        .find(|saio| saio.aux_info == saiz.aux_info)
+        .ok_or(Error::MissingBox(Saio::KIND))?;
+
+    Ok(Some((saiz, saio)))
+}
+
+// Slice the aux info of each sample, where each saio offset covers the next run of samples.
+// The offsets are added to the base, and the data starts at the given file offset.
+fn resolve<'a>(
+    saiz: &Saiz,
+    saio: &Saio,
+    runs: &[u32],
+    base: u64,
+    data_offset: u64,
+    data: &'a [u8],
+) -> Result<Vec<&'a [u8]>> {
+    if saio.offsets.len() != runs.len() {
+        return Err(Error::InvalidCombination(
+            "saio offsets don't match the chunks or runs",
+        ));
+    }
+
+    if saiz.default_sample_info_size == 0
+        && saiz.sample_info_size.len() != saiz.sample_count as usize
+    {
+        return Err(Error::InvalidCombination(
+            "saiz sample count doesn't match the sizes",
+        ));
+    }
+
+    let size = |sample: usize| match saiz.default_sample_info_size {
+        0 => saiz.sample_info_size[sample],
+        size => size,
+    };
+
+    // Don't use `with_capacity` on an untrusted count.
+    let mut samples = Vec::with_capacity((saiz.sample_count as usize).min(4096));
+
+    for (&offset, &count) in saio.offsets.iter().zip(runs) {
+        let mut start = base
+            .checked_add(offset)
+            .and_then(|offset| offset.checked_sub(data_offset))
+            .ok_or(Error::OutOfBounds)? as usize;
+
+        for _ in 0..count {
+            // Any remaining samples don't have auxiliary information.
+            if samples.len() == saiz.sample_count as usize {
+                break;
+            }
+
+            let end = start + size(samples.len()) as usize;
+            samples.push(data.get(start..end).ok_or(Error::OutOfBounds)?);
+            start = end;
+        }
+    }
+
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cenc() -> AuxInfo {
+        AuxInfo {
+            aux_info_type: FourCC::new(b"cenc"),
+            aux_info_type_parameter: 0,
+        }
+    }
+
+    #[test]
+    fn per_chunk() {
+        let stbl = Stbl {
+            stsc: Stsc {
+                entries: vec![StscEntry {
+                    first_chunk: 1,
+                    samples_per_chunk: 2,
+                    sample_description_index: 1,
+                }],
+            },
+            stco: Some(Stco {
+                entries: vec![100, 200],
+            }),
+            saiz: vec![Saiz {
+                aux_info: None,
+                default_sample_info_size: 0,
+                sample_count: 4,
+                sample_info_size: vec![1, 2, 1, 2],
+            }],
+            saio: vec![Saio {
+                aux_info: None,
+                offsets: vec![2, 6],
+            }],
+            ..Default::default()
+        };
+
+        let file: Vec<u8> = (0..10).collect();
+
+        // The saiz has no explicit type, so it's implied.
+        let samples = stbl.sample_aux_info(Some(&cenc()), &file).unwrap();
+        assert_eq!(samples, [&[2][..], &[3, 4], &[6], &[7, 8]]);
+
+        let samples = stbl.sample_aux_info(None, &file).unwrap();
+        assert_eq!(samples.len(), 4);
+
+        let file = &file[..8];
+        assert!(matches!(
+            stbl.sample_aux_info(None, file),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn too_many() {
+        // The count isn't bounded by the saiz when there's a default size, but the data is.
+        let stbl = Stbl {
+            saiz: vec![Saiz {
+                aux_info: None,
+                default_sample_info_size: 16,
+                sample_count: u32::MAX,
+                sample_info_size: Vec::new(),
+            }],
+            saio: vec![Saio {
+                aux_info: None,
+                offsets: vec![0],
+            }],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            stbl.sample_aux_info(None, &[0; 64]),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn relative_to_moof() {
+        let mut traf = Traf {
+            trun: vec![Trun {
+                data_offset: None,
+                entries: vec![TrunEntry::default(); 3],
+            }],
+            saiz: vec![Saiz {
+                aux_info: Some(cenc()),
+                default_sample_info_size: 2,
+                sample_count: 3,
+                sample_info_size: Vec::new(),
+            }],
+            saio: vec![Saio {
+                aux_info: Some(cenc()),
+                offsets: vec![4],
+            }],
+            ..Default::default()
+        };
+
+        // The segment starts 100 bytes into the file, with the moof 8 bytes after that.
+        let segment: Vec<u8> = (0..20).collect();
+
+        // Without a signaled base, the offsets depend on the previous traf.
+        assert!(matches!(
+            traf.sample_aux_info(Some(&cenc()), 108, 100, &segment),
+            Err(Error::Unsupported(_))
+        ));
+
+        traf.tfhd.default_base_is_moof = true;
+        let samples = traf
+            .sample_aux_info(Some(&cenc()), 108, 100, &segment)
+            .unwrap();
+        assert_eq!(samples, [&[12, 13][..], &[14, 15], &[16, 17]]);
+
+        // No saiz with that type.
+        assert!(traf
+            .sample_aux_info(None, 108, 100, &segment)
+            .unwrap()
+            .is_empty());
+
+        // An explicit base data offset takes precedence over the moof, and is a file offset too.
+        traf.tfhd.base_data_offset = Some(100);
+        let samples = traf
+            .sample_aux_info(Some(&cenc()), 108, 100, &segment)
+            .unwrap();
+        assert_eq!(samples[0], &[4, 5]);
+
+        // Offsets before the segment are out of bounds.
+        traf.tfhd.base_data_offset = Some(0);
+        assert!(matches!(
+            traf.sample_aux_info(Some(&cenc()), 108, 100, &segment),
+            Err(Error::OutOfBounds)
+        ));
+    }
+}
@@ -0,0 +1,835 @@
+use crate::*;
+
+/// The profile, tier and level of a layer, with the same layout as in `hvcC`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileTierLevel {
+    pub profile_space: u8,
+    pub tier_flag: bool,
+    pub profile_idc: u8,
+    pub profile_compatibility_flags: [u8; 4],
+    pub constraint_indicator_flags: [u8; 6],
+    pub level_idc: u8,
+}
+
+impl Decode for ProfileTierLevel {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        let temp = u8::decode(buf)?;
+        Ok(Self {
+            profile_space: (temp & 0b1100_0000) >> 6,
+            tier_flag: (temp & 0b0010_0000) != 0,
+            profile_idc: temp & 0b0001_1111,
+            profile_compatibility_flags: <[u8; 4]>::decode(buf)?,
+            constraint_indicator_flags: <[u8; 6]>::decode(buf)?,
+            level_idc: u8::decode(buf)?,
+        })
+    }
+}
+
+impl Encode for ProfileTierLevel {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        ((self.profile_space & 0b11) << 6
+            | (self.tier_flag as u8) << 5
+            | (self.profile_idc & 0b1_1111))
+            .encode(buf)?;
+        self.profile_compatibility_flags.encode(buf)?;
+        self.constraint_indicator_flags.encode(buf)?;
+        self.level_idc.encode(buf)
+    }
+}
+
+/// A temporal layer of HEVC (`tscl`), ISO/IEC 14496-15 Sect 8.4.5
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TemporalLayerEntry {
+    pub temporal_layer_id: u8,
+    pub profile_tier_level: ProfileTierLevel,
+    pub max_bit_rate: u16,
+    pub avg_bit_rate: u16,
+    pub constant_frame_rate: u8,
+    pub avg_frame_rate: u16,
+}
+
+impl Decode for TemporalLayerEntry {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        Ok(Self {
+            temporal_layer_id: u8::decode(buf)?,
+            profile_tier_level: ProfileTierLevel::decode(buf)?,
+            max_bit_rate: u16::decode(buf)?,
+            avg_bit_rate: u16::decode(buf)?,
+            constant_frame_rate: u8::decode(buf)?,
+            avg_frame_rate: u16::decode(buf)?,
+        })
+    }
+}
+
+impl Encode for TemporalLayerEntry {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        self.temporal_layer_id.encode(buf)?;
+        self.profile_tier_level.encode(buf)?;
+        self.max_bit_rate.encode(buf)?;
+        self.avg_bit_rate.encode(buf)?;
+        self.constant_frame_rate.encode(buf)?;
+        self.avg_frame_rate.encode(buf)
+    }
+}
+
+/// The operating points of a layered HEVC stream (`oinf`), ISO/IEC 14496-15 Sect 9.6.2
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperatingPointsEntry {
+    pub scalability_mask: u16,
+    pub profile_tier_levels: Vec<ProfileTierLevel>,
+    pub operating_points: Vec<OperatingPoint>,
+    pub layers: Vec<OperatingPointsLayer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperatingPoint {
+    pub output_layer_set_idx: u16,
+    pub max_temporal_id: u8,
+    pub layers: Vec<OperatingPointLayer>,
+    pub min_pic_width: u16,
+    pub min_pic_height: u16,
+    pub max_pic_width: u16,
+    pub max_pic_height: u16,
+    pub max_chroma_format: u8,
+    pub max_bit_depth_minus8: u8,
+
+    /// The average frame rate and the constant frame rate flag, if present.
+    pub frame_rate: Option<(u16, u8)>,
+
+    /// The maximum and average bitrate, if present.
+    pub bit_rate: Option<(u32, u32)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperatingPointLayer {
+    /// The index into [OperatingPointsEntry::profile_tier_levels].
+    pub ptl_idx: u8,
+    pub layer_id: u8,
+    pub is_output_layer: bool,
+    pub is_alternate_output_layer: bool,
+}
+
+/// The dependencies of a layer, and its value for each dimension set in the scalability mask.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperatingPointsLayer {
+    pub layer_id: u8,
+    pub direct_ref_layer_ids: Vec<u8>,
+    pub dimension_identifiers: Vec<u8>,
+}
+
+impl Decode for OperatingPointsEntry {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        let scalability_mask = u16::decode(buf)?;
+
+        let count = u8::decode(buf)? & 0b11_1111;
+        let mut profile_tier_levels = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            profile_tier_levels.push(ProfileTierLevel::decode(buf)?);
+        }
+
+        let count = u16::decode(buf)?;
+        let mut operating_points = Vec::with_capacity(count.min(64) as usize);
+        for _ in 0..count {
+            operating_points.push(OperatingPoint::decode(buf)?);
+        }
+
+        let count = u8::decode(buf)?;
+        let mut layers = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let layer_id = u8::decode(buf)?;
+
+            let refs = u8::decode(buf)?;
+            let mut direct_ref_layer_ids = Vec::with_capacity(refs as usize);
+            for _ in 0..refs {
+                direct_ref_layer_ids.push(u8::decode(buf)?);
+            }
+
+            let mut dimension_identifiers = Vec::new();
+            for _ in 0..scalability_mask.count_ones() {
+                dimension_identifiers.push(u8::decode(buf)?);
+            }
+
+            layers.push(OperatingPointsLayer {
+                layer_id,
+                direct_ref_layer_ids,
+                dimension_identifiers,
+            });
+        }
+
+        Ok(Self {
+            scalability_mask,
+            profile_tier_levels,
+            operating_points,
+            layers,
+        })
+    }
+}
+
+impl Encode for OperatingPointsEntry {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        let too_large = || Error::TooLarge(FourCC::new(b"oinf"));
+
+        self.scalability_mask.encode(buf)?;
+
+        let count: u8 = self
+            .profile_tier_levels
+            .len()
+            .try_into()
+            .map_err(|_| too_large())?;
+        if count > 0b11_1111 {
+            return Err(too_large());
+        }
+        count.encode(buf)?;
+        for ptl in &self.profile_tier_levels {
+            ptl.encode(buf)?;
+        }
+
+        let count: u16 = self
+            .operating_points
+            .len()
+            .try_into()
+            .map_err(|_| too_large())?;
+        count.encode(buf)?;
+        for operating_point in &self.operating_points {
+            operating_point.encode(buf)?;
+        }
+
+        let count: u8 = self.layers.len().try_into().map_err(|_| too_large())?;
+        count.encode(buf)?;
+        for layer in &self.layers {
+            if layer.dimension_identifiers.len() != self.scalability_mask.count_ones() as usize {
+                return Err(Error::InvalidSize);
+            }
+
+            layer.layer_id.encode(buf)?;
+            let refs: u8 = layer
+                .direct_ref_layer_ids
+                .len()
+                .try_into()
+                .map_err(|_| too_large())?;
+            refs.encode(buf)?;
+            for id in &layer.direct_ref_layer_ids {
+                id.encode(buf)?;
+            }
+            for id in &layer.dimension_identifiers {
+                id.encode(buf)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for OperatingPoint {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        let output_layer_set_idx = u16::decode(buf)?;
+        let max_temporal_id = u8::decode(buf)?;
+
+        let count = u8::decode(buf)?;
+        let mut layers = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let ptl_idx = u8::decode(buf)?;
+            let temp = u8::decode(buf)?;
+            layers.push(OperatingPointLayer {
+                ptl_idx,
+                layer_id: temp >> 2,
+                is_output_layer: temp & 0b10 != 0,
+                is_alternate_output_layer: temp & 0b01 != 0,
+            });
+        }
+
+        let min_pic_width = u16::decode(buf)?;
+        let min_pic_height = u16::decode(buf)?;
+        let max_pic_width = u16::decode(buf)?;
+        let max_pic_height = u16::decode(buf)?;
+
+        let temp = u8::decode(buf)?;
+        let max_chroma_format = temp >> 6;
+        let max_bit_depth_minus8 = (temp >> 3) & 0b111;
+
+        let frame_rate = match temp & 0b10 != 0 {
+            true => Some((u16::decode(buf)?, u8::decode(buf)? & 0b11)),
+            false => None,
+        };
+        let bit_rate = match temp & 0b01 != 0 {
+            true => Some((u32::decode(buf)?, u32::decode(buf)?)),
+            false => None,
+        };
+
+        Ok(Self {
+            output_layer_set_idx,
+            max_temporal_id,
+            layers,
+            min_pic_width,
+            min_pic_height,
+            max_pic_width,
+            max_pic_height,
+            max_chroma_format,
+            max_bit_depth_minus8,
+            frame_rate,
+            bit_rate,
+        })
+    }
+}
+
+impl Encode for OperatingPoint {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        self.output_layer_set_idx.encode(buf)?;
+        self.max_temporal_id.encode(buf)?;
+
+        let count: u8 = self
+            .layers
+            .len()
+            .try_into()
+            .map_err(|_| Error::TooLarge(FourCC::new(b"oinf")))?;
+        count.encode(buf)?;
+        for layer in &self.layers {
+            layer.ptl_idx.encode(buf)?;
+            ((layer.layer_id & 0b11_1111) << 2
+                | (layer.is_output_layer as u8) << 1
+                | layer.is_alternate_output_layer as u8)
+                .encode(buf)?;
+        }
+
+        self.min_pic_width.encode(buf)?;
+        self.min_pic_height.encode(buf)?;
+        self.max_pic_width.encode(buf)?;
+        self.max_pic_height.encode(buf)?;
+
+        ((self.max_chroma_format & 0b11) << 6
+            | (self.max_bit_depth_minus8 & 0b111) << 3
+            | (self.frame_rate.is_some() as u8) << 1
+            | self.bit_rate.is_some() as u8)
+            .encode(buf)?;
+
+        if let Some((avg_frame_rate, constant_frame_rate)) = self.frame_rate {
+            avg_frame_rate.encode(buf)?;
+            (constant_frame_rate & 0b11).encode(buf)?;
+        }
+        if let Some((max_bit_rate, avg_bit_rate)) = self.bit_rate {
+            max_bit_rate.encode(buf)?;
+            avg_bit_rate.encode(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The layers carried by a layered HEVC track (`linf`), ISO/IEC 14496-15 Sect 9.8.2
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerInfoEntry {
+    pub layers: Vec<LayerInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerInfo {
+    pub layer_id: u8,
+    pub min_temporal_id: u8,
+    pub max_temporal_id: u8,
+    pub sub_layer_presence_flags: u8,
+}
+
+impl Decode for LayerInfoEntry {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        let count = u8::decode(buf)? & 0b11_1111;
+
+        let mut layers = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let temp = u16::decode(buf)?;
+            layers.push(LayerInfo {
+                layer_id: ((temp >> 6) & 0b11_1111) as u8,
+                min_temporal_id: ((temp >> 3) & 0b111) as u8,
+                max_temporal_id: (temp & 0b111) as u8,
+                sub_layer_presence_flags: u8::decode(buf)? & 0b111_1111,
+            });
+        }
+
+        Ok(Self { layers })
+    }
+}
+
+impl Encode for LayerInfoEntry {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        if self.layers.len() > 0b11_1111 {
+            return Err(Error::TooLarge(FourCC::new(b"linf")));
+        }
+
+        (self.layers.len() as u8).encode(buf)?;
+        for layer in &self.layers {
+            ((layer.layer_id as u16 & 0b11_1111) << 6
+                | (layer.min_temporal_id as u16 & 0b111) << 3
+                | (layer.max_temporal_id as u16 & 0b111))
+                .encode(buf)?;
+            (layer.sub_layer_presence_flags & 0b111_1111).encode(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A rectangular region of HEVC tiles (`trif`), ISO/IEC 14496-15 Sect 10.1
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileRegionEntry {
+    pub group_id: u16,
+
+    /// The region, or `None` if the group isn't a tile region.
+    pub region: Option<TileRegion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileRegion {
+    pub independent_idc: u8,
+    pub filtering_disabled: bool,
+
+    /// The horizontal and vertical offset, or `None` if the region covers the full picture.
+    pub offset: Option<(u16, u16)>,
+    pub width: u16,
+    pub height: u16,
+
+    /// The group IDs of the tile regions this one depends on, if listed.
+    pub dependency_tile_group_ids: Option<Vec<u16>>,
+}
+
+impl Decode for TileRegionEntry {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        let group_id = u16::decode(buf)?;
+
+        let temp = u8::decode(buf)?;
+        if temp & 0x80 == 0 {
+            return Ok(Self {
+                group_id,
+                region: None,
+            });
+        }
+
+        let offset = match temp & 0b0001_0000 != 0 {
+            true => None,
+            false => Some((u16::decode(buf)?, u16::decode(buf)?)),
+        };
+        let width = u16::decode(buf)?;
+        let height = u16::decode(buf)?;
+
+        let dependency_tile_group_ids = match temp & 0b0000_0100 != 0 {
+            true => Some(decode_ids(buf)?),
+            false => None,
+        };
+
+        Ok(Self {
+            group_id,
+            region: Some(TileRegion {
+                independent_idc: (temp >> 5) & 0b11,
+                filtering_disabled: temp & 0b0000_1000 != 0,
+                offset,
+                width,
+                height,
+                dependency_tile_group_ids,
+            }),
+        })
+    }
+}
+
+impl Encode for TileRegionEntry {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        self.group_id.encode(buf)?;
+
+        let Some(region) = &self.region else {
+            return 0u8.encode(buf);
+        };
+
+        (0x80
+            | (region.independent_idc & 0b11) << 5
+            | (region.offset.is_none() as u8) << 4
+            | (region.filtering_disabled as u8) << 3
+            | (region.dependency_tile_group_ids.is_some() as u8) << 2)
+            .encode(buf)?;
+
+        if let Some((horizontal, vertical)) = region.offset {
+            horizontal.encode(buf)?;
+            vertical.encode(buf)?;
+        }
+        region.width.encode(buf)?;
+        region.height.encode(buf)?;
+
+        if let Some(ids) = &region.dependency_tile_group_ids {
+            encode_ids(ids, FourCC::new(b"trif"), buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A set of HEVC tile regions (`tsif`), ISO/IEC 14496-15 Sect 10.2
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileSetEntry {
+    pub group_id: u16,
+    pub independent_idc: u8,
+    pub filtering_disabled: bool,
+
+    /// The horizontal and vertical offset, or `None` if the set covers the full picture.
+    pub offset: Option<(u16, u16)>,
+    pub width: u16,
+    pub height: u16,
+
+    /// The group IDs of the tile regions in the set.
+    pub tile_group_ids: Vec<u16>,
+
+    /// The group IDs of the tile regions the set depends on, if listed.
+    pub dependency_tile_group_ids: Option<Vec<u16>>,
+}
+
+impl Decode for TileSetEntry {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        let group_id = u16::decode(buf)?;
+        let temp = u8::decode(buf)?;
+
+        let offset = match temp & 0b0010_0000 != 0 {
+            true => None,
+            false => Some((u16::decode(buf)?, u16::decode(buf)?)),
+        };
+        let width = u16::decode(buf)?;
+        let height = u16::decode(buf)?;
+        let tile_group_ids = decode_ids(buf)?;
+
+        let dependency_tile_group_ids = match temp & 0b0000_1000 != 0 {
+            true => Some(decode_ids(buf)?),
+            false => None,
+        };
+
+        Ok(Self {
+            group_id,
+            independent_idc: temp >> 6,
+            filtering_disabled: temp & 0b0001_0000 != 0,
+            offset,
+            width,
+            height,
+            tile_group_ids,
+            dependency_tile_group_ids,
+        })
+    }
+}
+
+impl Encode for TileSetEntry {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        self.group_id.encode(buf)?;
+        ((self.independent_idc & 0b11) << 6
+            | (self.offset.is_none() as u8) << 5
+            | (self.filtering_disabled as u8) << 4
+            | (self.dependency_tile_group_ids.is_some() as u8) << 3)
+            .encode(buf)?;
+
+        if let Some((horizontal, vertical)) = self.offset {
+            horizontal.encode(buf)?;
+            vertical.encode(buf)?;
+        }
+        self.width.encode(buf)?;
+        self.height.encode(buf)?;
+        encode_ids(&self.tile_group_ids, FourCC::new(b"tsif"), buf)?;
+
+        if let Some(ids) = &self.dependency_tile_group_ids {
+            encode_ids(ids, FourCC::new(b"tsif"), buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+// A list of 16-bit group IDs, preceded by a 16-bit count.
+fn decode_ids<B: Buf>(buf: &mut B) -> Result<Vec<u16>> {
+    let count = u16::decode(buf)?;
+    let mut ids = Vec::with_capacity(count.min(1024) as usize);
+    for _ in 0..count {
+        ids.push(u16::decode(buf)?);
+    }
+
+    Ok(ids)
+}
+
+// The grouping type is reported if there are too many IDs.
+fn encode_ids<B: BufMut>(ids: &[u16], grouping_type: FourCC, buf: &mut B) -> Result<()> {
+    let count: u16 = ids
+        .len()
+        .try_into()
+        .map_err(|_| Error::TooLarge(grouping_type))?;
+    count.encode(buf)?;
+    for id in ids {
+        id.encode(buf)?;
+    }
+
+    Ok(())
+}
+
+/// The temporal and spatial layers of a sample, from the `tscl` and `linf` sample groups.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SampleLayers {
+    /// The temporal layer, if the sample is in a `tscl` group.
+    pub temporal_layer_id: Option<u8>,
+
+    /// The layers in the sample, if the sample is in a `linf` group.
+    pub layers: Vec<LayerInfo>,
+}
+
+impl SampleLayers {
+    fn from_groups(groups: &[SampleGroup<'_>]) -> Self {
+        let mut layers = Self::default();
+
+        for group in groups {
+            match group.entry {
+                AnySampleGroupEntry::TemporalLayer(entry) => {
+                    layers.temporal_layer_id = Some(entry.temporal_layer_id)
+                }
+                AnySampleGroupEntry::LayerInfo(entry) => layers.layers = entry.layers.clone(),
+                _ => {}
+            }
+        }
+
+        layers
+    }
+}
+
+impl Stbl {
+    /// The temporal and spatial layers of each sample, in decode order.
+    pub fn sample_layers(&self) -> Result<Vec<SampleLayers>> {
+        self.iter_sample_groups()
+            .map(|groups| Ok(SampleLayers::from_groups(&groups?)))
+            .collect()
+    }
+}
+
+impl Traf {
+    /// The temporal and spatial layers of each sample in this fragment, in decode order.
+    pub fn sample_layers(&self, stbl: &Stbl) -> Result<Vec<SampleLayers>> {
+        self.iter_sample_groups(stbl)
+            .map(|groups| Ok(SampleLayers::from_groups(&groups?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ptl(level_idc: u8) -> ProfileTierLevel {
+        ProfileTierLevel {
+            profile_space: 0,
+            tier_flag: true,
+            profile_idc: 1,
+            profile_compatibility_flags: [0x60, 0, 0, 0],
+            constraint_indicator_flags: [0x90, 0, 0, 0, 0, 0],
+            level_idc,
+        }
+    }
+
+    fn roundtrip<T: Decode + Encode + PartialEq + std::fmt::Debug>(expected: T, size: usize) {
+        let mut buf = Vec::new();
+        expected.encode(&mut buf).unwrap();
+        assert_eq!(buf.len(), size);
+
+        let decoded = T::decode_exact(&mut buf.as_slice(), size).unwrap();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn entries() {
+        roundtrip(
+            TemporalLayerEntry {
+                temporal_layer_id: 1,
+                profile_tier_level: ptl(120),
+                max_bit_rate: 1000,
+                avg_bit_rate: 800,
+                constant_frame_rate: 1,
+                avg_frame_rate: 30 * 256,
+            },
+            20,
+        );
+
+        roundtrip(
+            LayerInfoEntry {
+                layers: vec![
+                    LayerInfo {
+                        layer_id: 0,
+                        min_temporal_id: 0,
+                        max_temporal_id: 2,
+                        sub_layer_presence_flags: 0b111,
+                    },
+                    LayerInfo {
+                        layer_id: 1,
+                        min_temporal_id: 1,
+                        max_temporal_id: 2,
+                        sub_layer_presence_flags: 0b110,
+                    },
+                ],
+            },
+            7,
+        );
+
+        roundtrip(
+            OperatingPointsEntry {
+                scalability_mask: 0b10,
+                profile_tier_levels: vec![ptl(120), ptl(123)],
+                operating_points: vec![OperatingPoint {
+                    output_layer_set_idx: 1,
+                    max_temporal_id: 2,
+                    layers: vec![OperatingPointLayer {
+                        ptl_idx: 1,
+                        layer_id: 1,
+                        is_output_layer: true,
+                        is_alternate_output_layer: false,
+                    }],
+                    min_pic_width: 1920,
+                    min_pic_height: 1080,
+                    max_pic_width: 1920,
+                    max_pic_height: 1080,
+                    max_chroma_format: 1,
+                    max_bit_depth_minus8: 2,
+                    frame_rate: Some((30 * 256, 1)),
+                    bit_rate: None,
+                }],
+                layers: vec![OperatingPointsLayer {
+                    layer_id: 1,
+                    direct_ref_layer_ids: vec![0],
+                    dimension_identifiers: vec![1],
+                }],
+            },
+            2 + 1 + 2 * 12 + 2 + (4 + 2 + 9 + 3) + 1 + 4,
+        );
+
+        roundtrip(
+            TileRegionEntry {
+                group_id: 7,
+                region: Some(TileRegion {
+                    independent_idc: 1,
+                    filtering_disabled: true,
+                    offset: Some((640, 0)),
+                    width: 640,
+                    height: 360,
+                    dependency_tile_group_ids: Some(vec![5, 6]),
+                }),
+            },
+            3 + 4 + 4 + 6,
+        );
+        roundtrip(
+            TileRegionEntry {
+                group_id: 8,
+                region: None,
+            },
+            3,
+        );
+
+        roundtrip(
+            TileSetEntry {
+                group_id: 9,
+                independent_idc: 2,
+                filtering_disabled: false,
+                offset: None,
+                width: 1280,
+                height: 720,
+                tile_group_ids: vec![7, 8],
+                dependency_tile_group_ids: None,
+            },
+            3 + 4 + 6,
+        );
+
+        // Too many IDs are reported with the grouping type of the entry.
+        let tsif = TileSetEntry {
+            group_id: 9,
+            independent_idc: 0,
+            filtering_disabled: false,
+            offset: None,
+            width: 1280,
+            height: 720,
+            tile_group_ids: vec![0; u16::MAX as usize + 1],
+            dependency_tile_group_ids: None,
+        };
+        assert!(matches!(
+            tsif.encode(&mut Vec::new()),
+            Err(Error::TooLarge(kind)) if kind == FourCC::new(b"tsif")
+        ));
+    }
+
+    #[test]
+    fn layers() {
+        let tscl = |id| SgpdEntry {
+            description_length: Some(20),
+            entry: AnySampleGroupEntry::TemporalLayer(TemporalLayerEntry {
+                temporal_layer_id: id,
+                ..Default::default()
+            }),
+        };
+
+        let layer = LayerInfo {
+            layer_id: 1,
+            min_temporal_id: 0,
+            max_temporal_id: 1,
+            sub_layer_presence_flags: 0b11,
+        };
+
+        let stbl = Stbl {
+            stsz: Some(Stsz {
+                samples: StszSamples::Identical { count: 3, size: 10 },
+            }),
+            sbgp: vec![Sbgp {
+                grouping_type: FourCC::new(b"tscl"),
+                grouping_type_parameter: None,
+                entries: vec![
+                    SbgpEntry {
+                        sample_count: 1,
+                        group_description_index: 1,
+                    },
+                    SbgpEntry {
+                        sample_count: 2,
+                        group_description_index: 2,
+                    },
+                ],
+            }],
+            sgpd: vec![
+                Sgpd {
+                    grouping_type: FourCC::new(b"tscl"),
+                    default_length: Some(20),
+                    default_group_description_index: None,
+                    static_group_description: false,
+                    static_mapping: false,
+                    essential: false,
+                    entries: vec![tscl(0), tscl(1)],
+                },
+                Sgpd {
+                    grouping_type: FourCC::new(b"linf"),
+                    default_length: Some(4),
+                    default_group_description_index: Some(1),
+                    static_group_description: false,
+                    static_mapping: false,
+                    essential: false,
+                    entries: vec![SgpdEntry {
+                        description_length: Some(4),
+                        entry: AnySampleGroupEntry::LayerInfo(LayerInfoEntry {
+                            layers: vec![layer],
+                        }),
+                    }],
+                },
+            ],
+            ..Default::default()
+        };
+
+        // The sgpd atoms survive a round trip.
+        for sgpd in &stbl.sgpd {
+            let mut buf = Vec::new();
+            sgpd.encode(&mut buf).unwrap();
+            assert_eq!(&Sgpd::decode(&mut buf.as_slice()).unwrap(), sgpd);
+        }
+
+        let layers = stbl.sample_layers().unwrap();
+        let temporal: Vec<Option<u8>> = layers.iter().map(|l| l.temporal_layer_id).collect();
+        assert_eq!(temporal, [Some(0), Some(1), Some(1)]);
+        assert!(layers.iter().all(|l| l.layers == [layer]));
+    }
+}
@@ -1,34 +1,51 @@
+mod aux_info;
 mod co64;
 mod cslg;
 mod ctts;
+mod hevc_group;
+mod padb;
 mod saiz;
+mod sample_group;
+mod samples;
 mod sbgp;
+mod sdtp;
 mod sgpd;
 mod stco;
+mod stdp;
 mod stsc;
 mod stsd;
+mod stsh;
 mod stss;
 mod stsz;
 mod stts;
+mod stz2;
 mod subs;
 
 pub use co64::*;
 pub use cslg::*;
 pub use ctts::*;
+pub use hevc_group::*;
+pub use padb::*;
 pub use saiz::*;
+pub use sample_group::*;
+pub use samples::*;
 pub use sbgp::*;
+pub use sdtp::*;
 pub use sgpd::*;
 pub use stco::*;
+pub use stdp::*;
 pub use stsc::*;
 pub use stsd::*;
+pub use stsh::*;
 pub use stss::*;
 pub use stsz::*;
 pub use stts::*;
+pub use stz2::*;
 pub use subs::*;
 
 use crate::*;
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stbl {
     pub stsd: Stsd,
@@ -36,7 +53,8 @@ pub struct Stbl {
     pub ctts: Option<Ctts>,
     pub stss: Option<Stss>,
     pub stsc: Stsc,
-    pub stsz: Stsz,
+    pub stsz: Option<Stsz>,
+    pub stz2: Option<Stz2>,
     pub stco: Option<Stco>,
     pub co64: Option<Co64>,
     pub sbgp: Vec<Sbgp>,
@@ -45,14 +63,126 @@ pub struct Stbl {
     pub saiz: Vec<Saiz>,
     pub saio: Vec<Saio>,
     pub cslg: Option<Cslg>,
+    pub sdtp: Option<Sdtp>,
+    pub stsh: Option<Stsh>,
+    pub stdp: Option<Stdp>,
+    pub padb: Option<Padb>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
+}
+
+// An empty stbl still has a sample size table, as one of stsz or stz2 is required.
+impl Default for Stbl {
+    fn default() -> Self {
+        Self {
+            stsd: Stsd::default(),
+            stts: Stts::default(),
+            ctts: None,
+            stss: None,
+            stsc: Stsc::default(),
+            stsz: Some(Stsz::default()),
+            stz2: None,
+            stco: None,
+            co64: None,
+            sbgp: Vec::new(),
+            sgpd: Vec::new(),
+            subs: Vec::new(),
+            saiz: Vec::new(),
+            saio: Vec::new(),
+            cslg: None,
+            sdtp: None,
+            stsh: None,
+            stdp: None,
+            padb: None,
+            custom: Vec::new(),
+        }
+    }
 }
 
 impl Atom for Stbl {
     const KIND: FourCC = FourCC::new(b"stbl");
 
     nested! {
-        required: [ Stsd, Stts, Stsc, Stsz ],
-        optional: [ Ctts, Stss, Stco, Co64, Cslg ],
+        required: [ Stsd, Stts, Stsc ],
+        optional: [ Stsz, Stz2, Ctts, Stss, Stco, Co64, Cslg, Sdtp, Stsh, Stdp, Padb ],
         multiple: [ Sbgp, Sgpd, Subs, Saiz, Saio ],
+        custom: custom,
+        check: check_sample_sizes,
+    }
+}
+
+impl Stbl {
+    // Exactly one of the stsz or stz2 is required.
+    fn check_sample_sizes(&self) -> Result<()> {
+        match (&self.stsz, &self.stz2) {
+            (None, None) => Err(Error::MissingBox(Stsz::KIND)),
+            (Some(_), Some(_)) => Err(Error::InvalidCombination(
+                "stbl contains both stsz and stz2",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The size of each sample, from either the `stsz` or `stz2` atom.
+    ///
+    /// The sizes are produced lazily, as an `stsz` with identical sizes may claim billions of samples.
+    pub fn sample_sizes(&self) -> impl ExactSizeIterator<Item = u32> + '_ {
+        (0..self.sample_count()).map(move |index| match (&self.stsz, &self.stz2) {
+            (Some(stsz), _) => match &stsz.samples {
+                StszSamples::Identical { size, .. } => *size,
+                StszSamples::Different { sizes } => sizes[index as usize],
+            },
+            (_, Some(stz2)) => stz2.sizes[index as usize] as u32,
+            _ => 0,
+        })
+    }
+
+    /// The total size of the samples in bytes, without expanding the sizes.
+    pub fn sample_data_size(&self) -> u64 {
+        match &self.stsz {
+            Some(Stsz {
+                samples: StszSamples::Identical { count, size },
+            }) => *count as u64 * *size as u64,
+            _ => self.sample_sizes().map(|size| size as u64).sum(),
+        }
+    }
+
+    /// The number of samples, from either the `stsz` or `stz2` atom.
+    pub fn sample_count(&self) -> u32 {
+        match (&self.stsz, &self.stz2) {
+            (Some(stsz), _) => match &stsz.samples {
+                StszSamples::Identical { count, .. } => *count,
+                StszSamples::Different { sizes } => sizes.len() as u32,
+            },
+            (_, Some(stz2)) => stz2.sizes.len() as u32,
+            _ => 0,
+        }
+    }
+
+    /// The offset of each chunk, from either the `stco` or `co64` atom.
+    pub fn chunk_offsets(&self) -> Vec<u64> {
+        match (&self.stco, &self.co64) {
+            (Some(stco), _) => stco.entries.iter().map(|&offset| offset as u64).collect(),
+            (_, Some(co64)) => co64.entries.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Replace the chunk offsets, switching from `stco` to `co64` when an offset doesn't fit in 32 bits.
+    pub fn set_chunk_offsets(&mut self, offsets: Vec<u64>) {
+        let large = offsets.iter().any(|&offset| offset > u32::MAX as u64);
+
+        match large || self.co64.is_some() {
+            true => {
+                self.stco = None;
+                self.co64 = Some(Co64 { entries: offsets });
+            }
+            false => {
+                self.stco = Some(Stco {
+                    entries: offsets.into_iter().map(|offset| offset as u32).collect(),
+                });
+            }
+        }
     }
 }
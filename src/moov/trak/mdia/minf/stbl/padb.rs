@@ -0,0 +1,60 @@
+use crate::*;
+
+/// Padding Bits Box (padb)
+///
+/// The number of padding bits at the end of each sample, up to 7.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Padb {
+    pub pads: Vec<u8>,
+}
+
+impl AtomExt for Padb {
+    type Ext = ();
+
+    const KIND_EXT: FourCC = FourCC::new(b"padb");
+
+    fn decode_body_ext<B: Buf>(buf: &mut B, _ext: ()) -> Result<Self> {
+        let count = u32::decode(buf)? as usize;
+
+        // Two samples per byte, each with a reserved bit and 3 bits of padding.
+        let mut pads = Vec::with_capacity(count.min(1024));
+        for _ in 0..count.div_ceil(2) {
+            let byte = u8::decode(buf)?;
+            pads.push((byte >> 4) & 0b111);
+            pads.push(byte & 0b111);
+        }
+        pads.truncate(count);
+
+        Ok(Padb { pads })
+    }
+
+    fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        (self.pads.len() as u32).encode(buf)?;
+        for pair in self.pads.chunks(2) {
+            let second = pair.get(1).copied().unwrap_or(0);
+            ((pair[0] & 0b111) << 4 | (second & 0b111)).encode(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padb() {
+        let expected = Padb {
+            pads: vec![1, 7, 0, 3, 5],
+        };
+        let mut buf = Vec::new();
+        expected.encode(&mut buf).unwrap();
+        assert_eq!(&buf[16..], [0x17, 0x03, 0x50]);
+
+        let mut buf = buf.as_ref();
+        let decoded = Padb::decode(&mut buf).unwrap();
+        assert_eq!(decoded, expected);
+    }
+}
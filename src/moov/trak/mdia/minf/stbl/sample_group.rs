@@ -0,0 +1,589 @@
+use std::fmt;
+
+use crate::*;
+
+/// A trait for user-defined sample group entries that can be decoded through [Sgpd].
+///
+/// Register the type with [CustomSampleGroupEntry::register] and entries of its grouping type will be returned as [AnySampleGroupEntry::Custom] instead of [AnySampleGroupEntry::UnknownGroupingType].
+/// When the `sgpd` has a description length, the entry is decoded from exactly that many bytes.
+pub trait SampleGroupEntry:
+    Decode + Encode + Clone + PartialEq + fmt::Debug + Send + Sync + 'static
+{
+    const GROUPING_TYPE: FourCC;
+}
+
+// Stores a SampleGroupEntry as an Erased, which encodes the whole entry.
+#[derive(Clone, PartialEq)]
+struct Typed<T>(T);
+
+impl<T: SampleGroupEntry> ErasedValue for Typed<T> {
+    fn kind(&self) -> FourCC {
+        T::GROUPING_TYPE
+    }
+
+    fn encode(&self, mut buf: &mut dyn BufMut) -> Result<()> {
+        self.0.encode(&mut buf)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Typed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+type Decoder = fn(&mut dyn Buf) -> Result<CustomSampleGroupEntry>;
+
+static REGISTRY: Registry<Decoder> = Registry::new();
+
+fn decode_custom<T: SampleGroupEntry>(mut buf: &mut dyn Buf) -> Result<CustomSampleGroupEntry> {
+    Ok(CustomSampleGroupEntry::new(T::decode(&mut buf)?))
+}
+
+/// A user-defined sample group entry, registered with [CustomSampleGroupEntry::register].
+///
+/// Use [CustomSampleGroupEntry::downcast_ref] to get the typed entry back.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CustomSampleGroupEntry(Erased);
+
+impl CustomSampleGroupEntry {
+    /// Decode entries of this grouping type as `T` from now on, for the whole process.
+    ///
+    /// Only grouping types that this library doesn't support are looked up, so built-in entries can't be replaced.
+    /// Registering the same grouping type again replaces the previous type.
+    pub fn register<T: SampleGroupEntry>() {
+        REGISTRY.insert(T::GROUPING_TYPE, decode_custom::<T>);
+    }
+
+    /// Stop decoding entries of this grouping type, returning true if it was registered.
+    pub fn unregister(grouping_type: FourCC) -> bool {
+        REGISTRY.remove(grouping_type)
+    }
+
+    /// Returns true if the grouping type has been registered.
+    pub fn is_registered(grouping_type: FourCC) -> bool {
+        REGISTRY.get(grouping_type).is_some()
+    }
+
+    pub fn new<T: SampleGroupEntry>(entry: T) -> Self {
+        Self(Erased::new(Typed(entry)))
+    }
+
+    pub fn grouping_type(&self) -> FourCC {
+        self.0.kind()
+    }
+
+    pub fn is<T: SampleGroupEntry>(&self) -> bool {
+        self.0.is::<Typed<T>>()
+    }
+
+    pub fn downcast_ref<T: SampleGroupEntry>(&self) -> Option<&T> {
+        self.0.downcast_ref().map(|typed: &Typed<T>| &typed.0)
+    }
+
+    pub fn downcast_mut<T: SampleGroupEntry>(&mut self) -> Option<&mut T> {
+        self.0
+            .downcast_mut()
+            .map(|typed: &mut Typed<T>| &mut typed.0)
+    }
+
+    /// Returns the typed entry, or self if it's a different type.
+    pub fn downcast<T: SampleGroupEntry>(self) -> std::result::Result<T, Self> {
+        match self.0.downcast::<Typed<T>>() {
+            Ok(typed) => Ok(typed.0),
+            Err(entry) => Err(Self(entry)),
+        }
+    }
+
+    // Returns the function used to decode the entry, if the grouping type is registered.
+    pub(crate) fn decoder(grouping_type: FourCC) -> Option<Decoder> {
+        REGISTRY.get(grouping_type)
+    }
+}
+
+impl Encode for CustomSampleGroupEntry {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        self.0.encode(buf)
+    }
+}
+
+impl fmt::Debug for CustomSampleGroupEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Serialized as the grouping type and the encoded entry, like [Custom].
+#[cfg(feature = "serde")]
+impl serde::Serialize for CustomSampleGroupEntry {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.0
+            .raw()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CustomSampleGroupEntry {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let raw = Raw::deserialize(deserializer)?;
+
+        let Some(decode) = Self::decoder(raw.kind) else {
+            return Ok(Self(Erased::new(raw)));
+        };
+
+        let mut body = raw.body.as_slice();
+        let entry = decode(&mut body).map_err(serde::de::Error::custom)?;
+        if !body.is_empty() {
+            return Err(serde::de::Error::custom(Error::UnderDecode(raw.kind)));
+        }
+
+        Ok(entry)
+    }
+}
+
+/// Added to a `sbgp` group description index in a `traf` to refer to the `sgpd` in the same `traf`.
+pub const FRAGMENT_LOCAL_GROUP_INDEX: u32 = 0x10000;
+
+/// A sample group that a sample belongs to, resolved from the `sbgp` and `sgpd` atoms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleGroup<'a> {
+    pub grouping_type: FourCC,
+
+    /// The sub-type of the grouping from the `sbgp`, if any.
+    pub grouping_type_parameter: Option<u32>,
+
+    /// The 1-based index of the entry within its `sgpd`, without the fragment-local offset.
+    pub group_description_index: u32,
+
+    /// True if the entry is from the `sgpd` in the `traf` rather than the `stbl`.
+    pub fragment_local: bool,
+
+    pub entry: &'a AnySampleGroupEntry,
+}
+
+impl Stbl {
+    /// The sample groups of a sample, given its 0-based index in decode order.
+    ///
+    /// Samples that aren't mapped by a `sbgp` use the default entry of a version 2 `sgpd`, if any.
+    /// Use [Stbl::iter_sample_groups] for every sample, as this scans the runs from the start each time.
+    pub fn sample_groups(&self, sample: u32) -> Result<Vec<SampleGroup<'_>>> {
+        let indexes = self.sbgp.iter().map(|sbgp| lookup(sbgp, sample));
+        resolve(&self.sbgp, indexes, &self.sgpd, None)
+    }
+
+    /// The sample groups of every sample in decode order, walking the runs of each `sbgp` once.
+    pub fn iter_sample_groups(&self) -> SampleGroupsIter<'_> {
+        SampleGroupsIter::new(&self.sbgp, &self.sgpd, None, self.sample_count())
+    }
+}
+
+impl Traf {
+    /// The sample groups of a sample in this fragment, given its 0-based index across every `trun`.
+    ///
+    /// Group description indexes above [FRAGMENT_LOCAL_GROUP_INDEX] refer to the `sgpd` in this `traf`, while the rest refer to the `sgpd` in the `stbl` of the track.
+    /// The `sbgp` atoms in the `stbl` only apply to the samples in the `moov`, so they're ignored.
+    pub fn sample_groups<'a>(
+        &'a self,
+        stbl: &'a Stbl,
+        sample: u32,
+    ) -> Result<Vec<SampleGroup<'a>>> {
+        let indexes = self.sbgp.iter().map(|sbgp| lookup(sbgp, sample));
+        resolve(&self.sbgp, indexes, &stbl.sgpd, Some(&self.sgpd))
+    }
+
+    /// The sample groups of every sample in this fragment, across every `trun`, walking the runs of each `sbgp` once.
+    pub fn iter_sample_groups<'a>(&'a self, stbl: &'a Stbl) -> SampleGroupsIter<'a> {
+        let count: usize = self.trun.iter().map(|trun| trun.entries.len()).sum();
+        SampleGroupsIter::new(&self.sbgp, &stbl.sgpd, Some(&self.sgpd), count as u32)
+    }
+}
+
+/// The sample groups of each sample in decode order, see [Stbl::iter_sample_groups] and [Traf::iter_sample_groups].
+pub struct SampleGroupsIter<'a> {
+    sbgps: &'a [Sbgp],
+    global: &'a [Sgpd],
+    local: Option<&'a [Sgpd]>,
+
+    // The current run of each sbgp, and the number of its samples already returned.
+    runs: Vec<(usize, u32)>,
+    remaining: u32,
+}
+
+impl<'a> SampleGroupsIter<'a> {
+    fn new(sbgps: &'a [Sbgp], global: &'a [Sgpd], local: Option<&'a [Sgpd]>, count: u32) -> Self {
+        Self {
+            sbgps,
+            global,
+            local,
+            runs: vec![(0, 0); sbgps.len()],
+            remaining: count,
+        }
+    }
+}
+
+impl<'a> Iterator for SampleGroupsIter<'a> {
+    type Item = Result<Vec<SampleGroup<'a>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+
+        let indexes = self
+            .sbgps
+            .iter()
+            .zip(&mut self.runs)
+            .map(|(sbgp, (run, used))| {
+                // Skip past the runs that have been used up, including empty ones.
+                loop {
+                    let entry = sbgp.entries.get(*run)?;
+                    if *used < entry.sample_count {
+                        *used += 1;
+                        return Some(entry.group_description_index);
+                    }
+
+                    *run += 1;
+                    *used = 0;
+                }
+            });
+
+        Some(resolve(self.sbgps, indexes, self.global, self.local))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl ExactSizeIterator for SampleGroupsIter<'_> {}
+
+// The group description index of a sample, or None if it's after the last run.
+fn lookup(sbgp: &Sbgp, sample: u32) -> Option<u32> {
+    let mut remaining = sample as u64;
+    sbgp.entries.iter().find_map(
+        |entry| match remaining.checked_sub(entry.sample_count as u64) {
+            Some(rest) => {
+                remaining = rest;
+                None
+            }
+            None => Some(entry.group_description_index),
+        },
+    )
+}
+
+// Resolve the group description index of each sbgp, given in the same order, to the entries.
+fn resolve<'a>(
+    sbgps: &[Sbgp],
+    indexes: impl Iterator<Item = Option<u32>>,
+    global: &'a [Sgpd],
+    local: Option<&'a [Sgpd]>,
+) -> Result<Vec<SampleGroup<'a>>> {
+    let mut groups = Vec::new();
+    let mut mapped = Vec::new();
+
+    for (sbgp, index) in sbgps.iter().zip(indexes) {
+        // A sample after the last run isn't mapped, so the default applies.
+        let Some(index) = index else {
+            continue;
+        };
+        mapped.push(sbgp.grouping_type);
+
+        // An index of zero means the sample isn't a member of any group of this type.
+        if index == 0 {
+            continue;
+        }
+
+        let (sgpds, index, fragment_local) = match local {
+            Some(local) if index > FRAGMENT_LOCAL_GROUP_INDEX => {
+                (local, index - FRAGMENT_LOCAL_GROUP_INDEX, true)
+            }
+            _ => (global, index, false),
+        };
+
+        let entry = find(sgpds, sbgp.grouping_type)
+            .and_then(|sgpd| sgpd.entries.get(index as usize - 1))
+            .ok_or(Error::InvalidCombination(
+                "sbgp references a missing sgpd entry",
+            ))?;
+
+        groups.push(SampleGroup {
+            grouping_type: sbgp.grouping_type,
+            grouping_type_parameter: sbgp.grouping_type_parameter,
+            group_description_index: index,
+            fragment_local,
+            entry: &entry.entry,
+        });
+    }
+
+    // The fragment's defaults take precedence over the track's.
+    let defaults = local
+        .into_iter()
+        .flatten()
+        .map(|sgpd| (sgpd, true))
+        .chain(global.iter().map(|sgpd| (sgpd, false)));
+
+    for (sgpd, fragment_local) in defaults {
+        let index = match sgpd.default_group_description_index {
+            Some(index) if index > 0 => index,
+            _ => continue,
+        };
+
+        if mapped.contains(&sgpd.grouping_type) {
+            continue;
+        }
+        mapped.push(sgpd.grouping_type);
+
+        let entry = sgpd
+            .entries
+            .get(index as usize - 1)
+            .ok_or(Error::InvalidCombination(
+                "sgpd default references a missing entry",
+            ))?;
+
+        groups.push(SampleGroup {
+            grouping_type: sgpd.grouping_type,
+            grouping_type_parameter: None,
+            group_description_index: index,
+            fragment_local,
+            entry: &entry.entry,
+        });
+    }
+
+    Ok(groups)
+}
+
+fn find(sgpds: &[Sgpd], grouping_type: FourCC) -> Option<&Sgpd> {
+    sgpds
+        .iter()
+        .find(|sgpd| sgpd.grouping_type == grouping_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A made up entry with a variable length name.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Xgrp {
+        id: u16,
+        name: String,
+    }
+
+    impl SampleGroupEntry for Xgrp {
+        const GROUPING_TYPE: FourCC = FourCC::new(b"xgrp");
+    }
+
+    impl Decode for Xgrp {
+        fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+            Ok(Self {
+                id: u16::decode(buf)?,
+                name: String::decode(buf)?,
+            })
+        }
+    }
+
+    impl Encode for Xgrp {
+        fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+            self.id.encode(buf)?;
+            self.name.as_str().encode(buf)
+        }
+    }
+
+    // The registry is global, so unregister when done, even on panic.
+    struct Registered;
+
+    impl Drop for Registered {
+        fn drop(&mut self) {
+            CustomSampleGroupEntry::unregister(Xgrp::GROUPING_TYPE);
+        }
+    }
+
+    #[test]
+    fn registered() {
+        CustomSampleGroupEntry::register::<Xgrp>();
+        let _registered = Registered;
+        assert!(CustomSampleGroupEntry::is_registered(Xgrp::GROUPING_TYPE));
+
+        let entry = |id, name: &str| SgpdEntry {
+            description_length: None,
+            entry: AnySampleGroupEntry::Custom(CustomSampleGroupEntry::new(Xgrp {
+                id,
+                name: name.into(),
+            })),
+        };
+
+        let sgpd = Sgpd {
+            grouping_type: Xgrp::GROUPING_TYPE,
+            default_length: Some(0),
+            default_group_description_index: None,
+            static_group_description: false,
+            static_mapping: false,
+            essential: false,
+            entries: vec![entry(1, "one"), entry(2, "three")],
+        };
+
+        let mut buf = Vec::new();
+        sgpd.encode(&mut buf).unwrap();
+
+        let decoded = Sgpd::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded.entries[0].description_length, Some(6));
+        assert_eq!(decoded.entries[1].description_length, Some(8));
+
+        let AnySampleGroupEntry::Custom(custom) = &decoded.entries[1].entry else {
+            panic!("expected a custom entry: {decoded:?}");
+        };
+        assert_eq!(
+            custom.downcast_ref::<Xgrp>(),
+            Some(&Xgrp {
+                id: 2,
+                name: "three".into()
+            })
+        );
+
+        // Serialized as the encoded entry and decoded with the registered type.
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&decoded).unwrap();
+            assert_eq!(serde_json::from_str::<Sgpd>(&json).unwrap(), decoded);
+        }
+    }
+
+    fn sgpd(grouping_type: &[u8; 4], roll: &[i16], default: Option<u32>) -> Sgpd {
+        Sgpd {
+            grouping_type: FourCC::new(grouping_type),
+            default_length: Some(2),
+            default_group_description_index: default,
+            static_group_description: false,
+            static_mapping: false,
+            essential: false,
+            entries: roll
+                .iter()
+                .map(|&roll| SgpdEntry {
+                    description_length: Some(2),
+                    entry: AnySampleGroupEntry::RollRecovery(roll),
+                })
+                .collect(),
+        }
+    }
+
+    fn sbgp(grouping_type: &[u8; 4], runs: &[(u32, u32)]) -> Sbgp {
+        Sbgp {
+            grouping_type: FourCC::new(grouping_type),
+            grouping_type_parameter: None,
+            entries: runs
+                .iter()
+                .map(|&(sample_count, group_description_index)| SbgpEntry {
+                    sample_count,
+                    group_description_index,
+                })
+                .collect(),
+        }
+    }
+
+    fn rolls(groups: &[SampleGroup<'_>]) -> Vec<(bool, i16)> {
+        groups
+            .iter()
+            .map(|group| match group.entry {
+                AnySampleGroupEntry::RollRecovery(roll) => (group.fragment_local, *roll),
+                entry => panic!("unexpected entry: {entry:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn progressive() {
+        // An empty run is skipped.
+        let stbl = Stbl {
+            sbgp: vec![sbgp(b"roll", &[(2, 1), (0, 2), (1, 0), (2, 2)])],
+            sgpd: vec![sgpd(b"roll", &[-1, -2], None), sgpd(b"prol", &[5], Some(1))],
+            stsz: Some(Stsz {
+                samples: StszSamples::Identical { count: 6, size: 1 },
+            }),
+            ..Default::default()
+        };
+
+        let expected: [&[(bool, i16)]; 6] = [
+            &[(false, -1), (false, 5)],
+            &[(false, -1), (false, 5)],
+            &[(false, 5)],
+            &[(false, -2), (false, 5)],
+            &[(false, -2), (false, 5)],
+            &[(false, 5)],
+        ];
+        for (sample, expected) in expected.into_iter().enumerate() {
+            let groups = stbl.sample_groups(sample as u32).unwrap();
+            assert_eq!(rolls(&groups), expected, "sample {sample}");
+        }
+
+        let all: Vec<_> = stbl
+            .iter_sample_groups()
+            .map(|groups| groups.unwrap())
+            .collect();
+        assert_eq!(all.len(), expected.len());
+        for (groups, expected) in all.iter().zip(expected) {
+            assert_eq!(rolls(groups), expected);
+        }
+
+        let groups = stbl.sample_groups(3).unwrap();
+        assert_eq!(groups[0].grouping_type, FourCC::new(b"roll"));
+        assert_eq!(groups[0].group_description_index, 2);
+    }
+
+    #[test]
+    fn fragmented() {
+        let stbl = Stbl {
+            sgpd: vec![sgpd(b"roll", &[-1], Some(1))],
+            ..Default::default()
+        };
+        let traf = Traf {
+            sbgp: vec![sbgp(
+                b"roll",
+                &[(1, FRAGMENT_LOCAL_GROUP_INDEX + 2), (1, 1), (1, 0)],
+            )],
+            sgpd: vec![sgpd(b"roll", &[-3, -4], None)],
+            ..Default::default()
+        };
+
+        assert_eq!(rolls(&traf.sample_groups(&stbl, 0).unwrap()), [(true, -4)]);
+        assert_eq!(rolls(&traf.sample_groups(&stbl, 1).unwrap()), [(false, -1)]);
+        assert_eq!(rolls(&traf.sample_groups(&stbl, 2).unwrap()), []);
+
+        // Past the last run, the track's default applies.
+        assert_eq!(rolls(&traf.sample_groups(&stbl, 3).unwrap()), [(false, -1)]);
+
+        let traf = Traf {
+            trun: vec![Trun {
+                entries: vec![TrunEntry::default(); 4],
+                ..Default::default()
+            }],
+            ..traf
+        };
+        let all: Vec<_> = traf
+            .iter_sample_groups(&stbl)
+            .map(|groups| rolls(&groups.unwrap()))
+            .collect();
+        assert_eq!(
+            all,
+            [
+                vec![(true, -4)],
+                vec![(false, -1)],
+                vec![],
+                vec![(false, -1)]
+            ]
+        );
+
+        let traf = Traf {
+            sbgp: vec![sbgp(b"roll", &[(1, FRAGMENT_LOCAL_GROUP_INDEX + 3)])],
+            ..traf
+        };
+        assert!(matches!(
+            traf.sample_groups(&stbl, 0),
+            Err(Error::InvalidCombination(_))
+        ));
+    }
+}
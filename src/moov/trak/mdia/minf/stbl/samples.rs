@@ -0,0 +1,422 @@
+use crate::*;
+
+/// A single sample, expanded from the run-length encoded sample table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StblSample {
+    /// The absolute offset of the sample in the file.
+    pub offset: u64,
+    pub size: u32,
+
+    /// The decode time in the media timescale, starting at zero.
+    pub decode_time: u64,
+    pub duration: u32,
+
+    /// The composition time minus the decode time.
+    pub composition_offset: i64,
+
+    /// Whether this is a sync sample (keyframe).
+    pub sync: bool,
+
+    /// The 1-based index into the `stsd` entries.
+    pub description_index: u32,
+
+    /// The 0-based chunk containing the sample.
+    pub chunk: u32,
+
+    /// The dependency flags from the `sdtp`, if present.
+    pub dependency: Option<SdtpEntry>,
+
+    /// The degradation priority from the `stdp`, if present.
+    pub priority: Option<u16>,
+
+    /// The number of padding bits from the `padb`, if present.
+    pub padding: Option<u8>,
+}
+
+impl StblSample {
+    /// The composition (presentation) time in the media timescale.
+    pub fn composition_time(&self) -> i64 {
+        self.decode_time as i64 + self.composition_offset
+    }
+}
+
+// A chunk of samples, expanded from the stsc runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct StblChunk {
+    pub offset: u64,
+    pub samples: u32,
+    pub description_index: u32,
+}
+
+impl Stbl {
+    // Pair each chunk offset with the stsc entry that applies to it, in a single pass over both.
+    pub(crate) fn chunks(&self) -> Result<Vec<StblChunk>> {
+        let entries = &self.stsc.entries;
+        let mut entry = 0;
+
+        self.chunk_offsets()
+            .into_iter()
+            .enumerate()
+            .map(|(index, offset)| {
+                // The entries are 1-indexed and in ascending order.
+                let chunk = index as u64 + 1;
+                while entries
+                    .get(entry + 1)
+                    .is_some_and(|next| next.first_chunk as u64 <= chunk)
+                {
+                    entry += 1;
+                }
+
+                let entry = entries
+                    .get(entry)
+                    .filter(|entry| entry.first_chunk as u64 <= chunk)
+                    .ok_or(Error::MissingContent("stsc entry for the first chunk"))?;
+
+                Ok(StblChunk {
+                    offset,
+                    samples: entry.samples_per_chunk,
+                    description_index: entry.sample_description_index,
+                })
+            })
+            .collect()
+    }
+
+    /// Expand the sample table into a list of samples in decode order.
+    ///
+    /// The sample count isn't bounded by the size of the tables, so check it against the media data first, ex. with [Stbl::sample_data_size].
+    pub fn samples(&self) -> Result<Vec<StblSample>> {
+        let chunks = self.chunks()?;
+
+        // Reject a count that can't fit in the chunks before expanding anything.
+        let capacity: u64 = chunks.iter().map(|chunk| chunk.samples as u64).sum();
+        let count = self.sample_count();
+        if count as u64 > capacity {
+            return Err(Error::InvalidCombination(
+                "stsz has more samples than the chunks",
+            ));
+        }
+
+        let mut samples = Vec::with_capacity((count as usize).min(4096));
+        let mut sizes = self.sample_sizes();
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut offset = chunk.offset;
+            for _ in 0..chunk.samples {
+                let Some(size) = sizes.next() else { break };
+
+                samples.push(StblSample {
+                    offset,
+                    size,
+                    sync: self.stss.is_none(),
+                    description_index: chunk.description_index,
+                    chunk: index as u32,
+                    ..Default::default()
+                });
+
+                offset = offset.checked_add(size as u64).ok_or(Error::OutOfBounds)?;
+            }
+        }
+
+        let mut iter = samples.iter_mut();
+        let mut decode_time = 0;
+
+        for entry in &self.stts.entries {
+            for _ in 0..entry.sample_count {
+                let sample = iter
+                    .next()
+                    .ok_or(Error::InvalidCombination("stts has more samples than stsz"))?;
+                sample.decode_time = decode_time;
+                sample.duration = entry.sample_delta;
+                decode_time += entry.sample_delta as u64;
+            }
+        }
+
+        if iter.next().is_some() {
+            return Err(Error::InvalidCombination(
+                "stts has fewer samples than stsz",
+            ));
+        }
+
+        if let Some(ctts) = &self.ctts {
+            let mut iter = samples.iter_mut();
+            for entry in &ctts.entries {
+                for _ in 0..entry.sample_count {
+                    let sample = iter
+                        .next()
+                        .ok_or(Error::InvalidCombination("ctts has more samples than stsz"))?;
+                    sample.composition_offset = entry.sample_offset;
+                }
+            }
+        }
+
+        if let Some(sdtp) = &self.sdtp {
+            for (sample, &entry) in samples.iter_mut().zip(&sdtp.entries) {
+                sample.dependency = Some(entry);
+            }
+        }
+
+        if let Some(stdp) = &self.stdp {
+            for (sample, &priority) in samples.iter_mut().zip(&stdp.priorities) {
+                sample.priority = Some(priority);
+            }
+        }
+
+        if let Some(padb) = &self.padb {
+            for (sample, &padding) in samples.iter_mut().zip(&padb.pads) {
+                sample.padding = Some(padding);
+            }
+        }
+
+        if let Some(stss) = &self.stss {
+            for &index in &stss.entries {
+                // Sync samples are 1-indexed.
+                let sample = index
+                    .checked_sub(1)
+                    .and_then(|index| samples.get_mut(index as usize))
+                    .ok_or(Error::InvalidCombination(
+                        "stss references a missing sample",
+                    ))?;
+                sample.sync = true;
+            }
+        }
+
+        Ok(samples)
+    }
+
+    /// Rebuild the sample tables from a list of samples in decode order.
+    ///
+    /// Consecutive samples with the same `chunk` form a chunk, starting at the offset of its first sample.
+    /// Only the durations are used for timing; the decode times are implied.
+    ///
+    /// This replaces `stts`, `ctts`, `stss`, `stsc`, `stsz` (or `stz2`) and the chunk offsets, and removes the stale `cslg` and `stsh`.
+    /// The `sdtp`, `stdp` and `padb` are rebuilt when every sample has a value for them, and removed otherwise.
+    pub fn set_samples(&mut self, samples: &[StblSample]) {
+        let mut stts: Vec<SttsEntry> = Vec::new();
+        let mut ctts: Vec<CttsEntry> = Vec::new();
+        let mut stsc: Vec<StscEntry> = Vec::new();
+        let mut chunks = Vec::new();
+
+        for (index, sample) in samples.iter().enumerate() {
+            match stts.last_mut() {
+                Some(entry) if entry.sample_delta == sample.duration => entry.sample_count += 1,
+                _ => stts.push(SttsEntry {
+                    sample_count: 1,
+                    sample_delta: sample.duration,
+                }),
+            }
+
+            match ctts.last_mut() {
+                Some(entry) if entry.sample_offset == sample.composition_offset => {
+                    entry.sample_count += 1
+                }
+                _ => ctts.push(CttsEntry {
+                    sample_count: 1,
+                    sample_offset: sample.composition_offset,
+                }),
+            }
+
+            let first = index == 0 || samples[index - 1].chunk != sample.chunk;
+            if first {
+                chunks.push((sample.offset, 0, sample.description_index));
+            }
+
+            if let Some(chunk) = chunks.last_mut() {
+                chunk.1 += 1;
+            }
+        }
+
+        for (index, &(_, samples_per_chunk, sample_description_index)) in chunks.iter().enumerate()
+        {
+            let same = stsc.last().is_some_and(|entry| {
+                entry.samples_per_chunk == samples_per_chunk
+                    && entry.sample_description_index == sample_description_index
+            });
+
+            if !same {
+                stsc.push(StscEntry {
+                    first_chunk: index as u32 + 1,
+                    samples_per_chunk,
+                    sample_description_index,
+                });
+            }
+        }
+
+        let sizes: Vec<u32> = samples.iter().map(|sample| sample.size).collect();
+
+        self.stts = Stts { entries: stts };
+        self.ctts = match ctts.iter().any(|entry| entry.sample_offset != 0) {
+            true => Some(Ctts { entries: ctts }),
+            false => None,
+        };
+        self.stss = match samples.iter().all(|sample| sample.sync) {
+            true => None,
+            false => Some(Stss {
+                entries: (1..=samples.len() as u32)
+                    .filter(|&index| samples[index as usize - 1].sync)
+                    .collect(),
+            }),
+        };
+        self.stsc = Stsc { entries: stsc };
+        self.stsz = Some(Stsz {
+            samples: match sizes.first() {
+                Some(&size) if sizes.iter().all(|&s| s == size) => StszSamples::Identical {
+                    count: sizes.len() as u32,
+                    size,
+                },
+                _ => StszSamples::Different { sizes },
+            },
+        });
+        self.stz2 = None;
+        self.set_chunk_offsets(chunks.iter().map(|chunk| chunk.0).collect());
+        self.cslg = None;
+
+        // The shadow sync samples are referenced by number, so they can't be carried over.
+        self.stsh = None;
+
+        self.sdtp = samples
+            .iter()
+            .map(|sample| sample.dependency)
+            .collect::<Option<_>>()
+            .map(|entries| Sdtp { entries });
+        self.stdp = samples
+            .iter()
+            .map(|sample| sample.priority)
+            .collect::<Option<_>>()
+            .map(|priorities| Stdp { priorities });
+        self.padb = samples
+            .iter()
+            .map(|sample| sample.padding)
+            .collect::<Option<_>>()
+            .map(|pads| Padb { pads });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stbl() -> Stbl {
+        Stbl {
+            stts: Stts {
+                entries: vec![
+                    SttsEntry {
+                        sample_count: 4,
+                        sample_delta: 1000,
+                    },
+                    SttsEntry {
+                        sample_count: 1,
+                        sample_delta: 500,
+                    },
+                ],
+            },
+            ctts: Some(Ctts {
+                entries: vec![
+                    CttsEntry {
+                        sample_count: 1,
+                        sample_offset: 2000,
+                    },
+                    CttsEntry {
+                        sample_count: 4,
+                        sample_offset: 0,
+                    },
+                ],
+            }),
+            stss: Some(Stss {
+                entries: vec![1, 4],
+            }),
+            stsc: Stsc {
+                entries: vec![
+                    StscEntry {
+                        first_chunk: 1,
+                        samples_per_chunk: 2,
+                        sample_description_index: 1,
+                    },
+                    StscEntry {
+                        first_chunk: 3,
+                        samples_per_chunk: 1,
+                        sample_description_index: 2,
+                    },
+                ],
+            },
+            stsz: Some(Stsz {
+                samples: StszSamples::Different {
+                    sizes: vec![10, 20, 30, 40, 50],
+                },
+            }),
+            stco: Some(Stco {
+                entries: vec![100, 200, 300],
+            }),
+            padb: Some(Padb {
+                pads: vec![0, 0, 1, 2, 0],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn expand() {
+        let samples = stbl().samples().unwrap();
+        assert_eq!(samples.len(), 5);
+
+        assert_eq!(
+            samples[1],
+            StblSample {
+                offset: 110,
+                size: 20,
+                decode_time: 1000,
+                duration: 1000,
+                composition_offset: 0,
+                sync: false,
+                description_index: 1,
+                chunk: 0,
+                dependency: None,
+                priority: None,
+                padding: Some(0),
+            }
+        );
+
+        assert_eq!(samples[0].composition_time(), 2000);
+        assert!(samples[3].sync);
+        assert_eq!(samples[4].offset, 300);
+        assert_eq!(samples[4].description_index, 2);
+        assert_eq!(samples[4].decode_time, 4000);
+    }
+
+    #[test]
+    fn rebuild() {
+        let expected = stbl();
+
+        let mut stbl = Stbl::default();
+        stbl.set_samples(&expected.samples().unwrap());
+        assert_eq!(stbl, expected);
+
+        // A sample without padding drops the padb, and the stsh can't be carried over.
+        let mut samples = expected.samples().unwrap();
+        samples[2].padding = None;
+        stbl.stsh = Some(Stsh::default());
+        stbl.set_samples(&samples);
+        assert_eq!(stbl.padb, None);
+        assert_eq!(stbl.stsh, None);
+    }
+
+    #[test]
+    fn mismatch() {
+        let mut stbl = stbl();
+        stbl.stts.entries[0].sample_count = 3;
+        assert!(matches!(stbl.samples(), Err(Error::InvalidCombination(_))));
+    }
+
+    #[test]
+    fn too_many() {
+        // The count is rejected before anything is expanded.
+        let mut stbl = stbl();
+        stbl.stsz = Some(Stsz {
+            samples: StszSamples::Identical {
+                count: u32::MAX,
+                size: 4,
+            },
+        });
+        assert!(matches!(stbl.samples(), Err(Error::InvalidCombination(_))));
+        assert_eq!(stbl.sample_data_size(), u32::MAX as u64 * 4);
+    }
+}
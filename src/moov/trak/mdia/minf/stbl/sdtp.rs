@@ -0,0 +1,115 @@
+use crate::*;
+
+/// The dependency flags of a single sample, using the same values as [SampleFlags].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SdtpEntry {
+    pub is_leading: u8,
+    pub sample_depends_on: u8,
+    pub sample_is_depended_on: u8,
+    pub sample_has_redundancy: u8,
+}
+
+impl SdtpEntry {
+    /// True if nothing depends on the sample, so it can be dropped.
+    pub fn is_disposable(&self) -> bool {
+        self.sample_is_depended_on == 2
+    }
+}
+
+impl From<u8> for SdtpEntry {
+    fn from(value: u8) -> Self {
+        Self {
+            is_leading: (value >> 6) & 0b11,
+            sample_depends_on: (value >> 4) & 0b11,
+            sample_is_depended_on: (value >> 2) & 0b11,
+            sample_has_redundancy: value & 0b11,
+        }
+    }
+}
+
+impl From<SdtpEntry> for u8 {
+    fn from(entry: SdtpEntry) -> Self {
+        ((entry.is_leading & 0b11) << 6)
+            | ((entry.sample_depends_on & 0b11) << 4)
+            | ((entry.sample_is_depended_on & 0b11) << 2)
+            | (entry.sample_has_redundancy & 0b11)
+    }
+}
+
+impl From<SampleFlags> for SdtpEntry {
+    fn from(flags: SampleFlags) -> Self {
+        Self {
+            is_leading: flags.is_leading,
+            sample_depends_on: flags.sample_depends_on,
+            sample_is_depended_on: flags.sample_is_depended_on,
+            sample_has_redundancy: flags.sample_has_redundancy,
+        }
+    }
+}
+
+/// Independent and Disposable Samples Box (sdtp)
+///
+/// One entry per sample, used for trick play and dropping frames.
+/// The sample count isn't stored, so it must match the `stsz` or `trun`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sdtp {
+    pub entries: Vec<SdtpEntry>,
+}
+
+impl AtomExt for Sdtp {
+    type Ext = ();
+
+    const KIND_EXT: FourCC = FourCC::new(b"sdtp");
+
+    fn decode_body_ext<B: Buf>(buf: &mut B, _ext: ()) -> Result<Self> {
+        let mut entries = Vec::with_capacity(buf.remaining());
+        while buf.has_remaining() {
+            entries.push(u8::decode(buf)?.into());
+        }
+
+        Ok(Sdtp { entries })
+    }
+
+    fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        for &entry in &self.entries {
+            u8::from(entry).encode(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sdtp() {
+        let expected = Sdtp {
+            entries: vec![
+                SdtpEntry {
+                    is_leading: 0,
+                    sample_depends_on: 2,
+                    sample_is_depended_on: 1,
+                    sample_has_redundancy: 0,
+                },
+                SdtpEntry {
+                    is_leading: 3,
+                    sample_depends_on: 1,
+                    sample_is_depended_on: 2,
+                    sample_has_redundancy: 2,
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        expected.encode(&mut buf).unwrap();
+        assert_eq!(&buf[12..], [0x24, 0xda]);
+
+        let mut buf = buf.as_ref();
+        let decoded = Sdtp::decode(&mut buf).unwrap();
+        assert_eq!(decoded, expected);
+        assert!(decoded.entries[1].is_disposable());
+    }
+}
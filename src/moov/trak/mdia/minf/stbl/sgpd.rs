@@ -16,6 +16,7 @@ pub struct Sgpd {
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SgpdEntry {
+    /// The size of the entry when the `sgpd` has a description length, which is recomputed when encoding.
     pub description_length: Option<u32>,
     pub entry: AnySampleGroupEntry,
 }
@@ -74,7 +75,10 @@ impl AtomExt for Sgpd {
             } else {
                 default_length
             };
-            let entry = AnySampleGroupEntry::decode(grouping_type, buf)?;
+            let entry = match description_length {
+                Some(size) => AnySampleGroupEntry::decode_exact(grouping_type, buf, size as usize)?,
+                None => AnySampleGroupEntry::decode(grouping_type, buf)?,
+            };
             entries.push(SgpdEntry {
                 description_length,
                 entry,
@@ -118,27 +122,137 @@ impl AtomExt for Sgpd {
         }
         (self.entries.len() as u32).encode(buf)?;
         for entry in &self.entries {
-            if self.default_length == Some(0) {
-                if let Some(description_length) = entry.description_length {
-                    description_length.encode(buf)?
+            let mut encoded = Vec::new();
+            entry.entry.encode(&mut encoded)?;
+
+            // The description length is always computed, rather than trusting the stored value.
+            match self.default_length {
+                Some(0) => (encoded.len() as u32).encode(buf)?,
+                Some(size) if size as usize != encoded.len() => {
+                    return Err(Error::InvalidSize);
                 }
+                _ => {}
             }
-            entry.entry.encode(buf)?
+
+            encoded.encode(buf)?;
         }
         Ok(ext)
     }
 }
 
 const REFS_4CC: FourCC = FourCC::new(b"refs");
+const ROLL_4CC: FourCC = FourCC::new(b"roll");
+const PROL_4CC: FourCC = FourCC::new(b"prol");
+const RAP_4CC: FourCC = FourCC::new(b"rap ");
+const SYNC_4CC: FourCC = FourCC::new(b"sync");
+const SAP_4CC: FourCC = FourCC::new(b"sap ");
+const TELE_4CC: FourCC = FourCC::new(b"tele");
+const ALST_4CC: FourCC = FourCC::new(b"alst");
+const TSCL_4CC: FourCC = FourCC::new(b"tscl");
+const OINF_4CC: FourCC = FourCC::new(b"oinf");
+const LINF_4CC: FourCC = FourCC::new(b"linf");
+const TRIF_4CC: FourCC = FourCC::new(b"trif");
+const TSIF_4CC: FourCC = FourCC::new(b"tsif");
+const SEIG_4CC: FourCC = FourCC::new(b"seig");
 
+/// A sample group description entry, decoded based on the grouping type of the [Sgpd].
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AnySampleGroupEntry {
+    /// `refs`: the sample ID and the IDs of the samples it directly references.
     DirectReferenceSampleList(u32, Vec<u32>),
+
+    /// `roll`: the number of samples to decode before (if negative) or after a random access point to recover.
+    RollRecovery(i16),
+
+    /// `prol`: the number of samples to decode before a sample to get correct output, such as AAC priming.
+    PreRoll(i16),
+
+    /// `rap `: a random access point that isn't a sync sample, such as an open GOP.
+    RandomAccess {
+        num_leading_samples_known: bool,
+        num_leading_samples: u8,
+    },
+
+    /// `sync`: the NAL unit type of a sync sample.
+    SyncSample {
+        nal_unit_type: u8,
+    },
+
+    /// `sap `: the type of stream access point, from 1 to 6.
+    StreamAccessPoint {
+        dependent_flag: bool,
+        sap_type: u8,
+    },
+
+    /// `tele`: whether the samples of a temporal level can be decoded independently of higher levels.
+    TemporalLevel {
+        level_independently_decodable: bool,
+    },
+
+    /// `alst`: an alternative startup sequence that skips some samples when starting at a random access point.
+    AlternativeStartup(AlternativeStartupEntry),
+
+    /// `tscl`: the temporal layer of HEVC samples.
+    TemporalLayer(TemporalLayerEntry),
+
+    /// `oinf`: the operating points of a layered HEVC stream.
+    OperatingPoints(OperatingPointsEntry),
+
+    /// `linf`: the layers carried by a layered HEVC track.
+    LayerInfo(LayerInfoEntry),
+
+    /// `trif`: a rectangular region of HEVC tiles.
+    TileRegion(TileRegionEntry),
+
+    /// `tsif`: a set of HEVC tile regions.
+    TileSet(TileSetEntry),
+
+    /// `seig`: the Common Encryption parameters, used for key rotation.
+    Encryption(EncryptionInfo),
+
+    /// A grouping type registered with [CustomSampleGroupEntry::register].
+    Custom(CustomSampleGroupEntry),
+
     UnknownGroupingType(FourCC, Vec<u8>),
 }
 
+/// An alternative startup sequence (`alst`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlternativeStartupEntry {
+    pub first_output_sample: u16,
+
+    /// The offset of each sample in the sequence, one per rolled sample.
+    pub sample_offsets: Vec<u32>,
+
+    /// Pairs of `num_output_samples` and `num_total_samples`, which are optional.
+    pub output_samples: Vec<(u16, u16)>,
+}
+
 impl AnySampleGroupEntry {
+    // Decode an entry with a known description length.
+    fn decode_exact<B: Buf>(grouping_type: FourCC, buf: &mut B, size: usize) -> Result<Self> {
+        if buf.remaining() < size {
+            return Err(Error::OutOfBounds);
+        }
+
+        let data = buf.slice(size);
+        let mut inner = data;
+        let entry = Self::decode(grouping_type, &mut inner)?;
+
+        // A newer version of the entry may append fields, which are kept by falling back to the raw bytes.
+        let entry = match inner.has_remaining() {
+            true => Self::UnknownGroupingType(grouping_type, data.to_vec()),
+            false => entry,
+        };
+
+        buf.advance(size);
+
+        Ok(entry)
+    }
+
+    // Decode an entry, which consumes the rest of the buffer if its size isn't implied by the grouping type.
     fn decode<B: Buf>(grouping_type: FourCC, buf: &mut B) -> Result<Self> {
         match grouping_type {
             REFS_4CC => {
@@ -154,7 +268,59 @@ impl AnySampleGroupEntry {
                     direct_reference_samples,
                 ))
             }
-            _ => Ok(Self::UnknownGroupingType(grouping_type, Vec::decode(buf)?)),
+            ROLL_4CC => Ok(Self::RollRecovery(i16::decode(buf)?)),
+            PROL_4CC => Ok(Self::PreRoll(i16::decode(buf)?)),
+            RAP_4CC => {
+                let byte = u8::decode(buf)?;
+                Ok(Self::RandomAccess {
+                    num_leading_samples_known: byte & 0x80 != 0,
+                    num_leading_samples: byte & 0x7f,
+                })
+            }
+            SYNC_4CC => Ok(Self::SyncSample {
+                nal_unit_type: u8::decode(buf)? & 0x3f,
+            }),
+            SAP_4CC => {
+                let byte = u8::decode(buf)?;
+                Ok(Self::StreamAccessPoint {
+                    dependent_flag: byte & 0x80 != 0,
+                    sap_type: byte & 0x0f,
+                })
+            }
+            TELE_4CC => Ok(Self::TemporalLevel {
+                level_independently_decodable: u8::decode(buf)? & 0x80 != 0,
+            }),
+            ALST_4CC => {
+                let roll_count = u16::decode(buf)?;
+                let first_output_sample = u16::decode(buf)?;
+
+                let mut sample_offsets = Vec::with_capacity(roll_count.min(1024) as usize);
+                for _ in 0..roll_count {
+                    sample_offsets.push(u32::decode(buf)?);
+                }
+
+                // The remaining pairs are optional, so they continue until the end of the entry.
+                let mut output_samples = Vec::new();
+                while buf.has_remaining() {
+                    output_samples.push((u16::decode(buf)?, u16::decode(buf)?));
+                }
+
+                Ok(Self::AlternativeStartup(AlternativeStartupEntry {
+                    first_output_sample,
+                    sample_offsets,
+                    output_samples,
+                }))
+            }
+            TSCL_4CC => Ok(Self::TemporalLayer(TemporalLayerEntry::decode(buf)?)),
+            OINF_4CC => Ok(Self::OperatingPoints(OperatingPointsEntry::decode(buf)?)),
+            LINF_4CC => Ok(Self::LayerInfo(LayerInfoEntry::decode(buf)?)),
+            TRIF_4CC => Ok(Self::TileRegion(TileRegionEntry::decode(buf)?)),
+            TSIF_4CC => Ok(Self::TileSet(TileSetEntry::decode(buf)?)),
+            SEIG_4CC => Ok(Self::Encryption(EncryptionInfo::decode(buf)?)),
+            _ => match CustomSampleGroupEntry::decoder(grouping_type) {
+                Some(decode) => Ok(Self::Custom(decode(buf)?)),
+                None => Ok(Self::UnknownGroupingType(grouping_type, Vec::decode(buf)?)),
+            },
         }
     }
 
@@ -172,9 +338,72 @@ impl AnySampleGroupEntry {
                 }
                 Ok(())
             }
+            Self::RollRecovery(roll_distance) | Self::PreRoll(roll_distance) => {
+                roll_distance.encode(buf)
+            }
+            Self::RandomAccess {
+                num_leading_samples_known,
+                num_leading_samples,
+            } => {
+                ((*num_leading_samples_known as u8) << 7 | (num_leading_samples & 0x7f)).encode(buf)
+            }
+            Self::SyncSample { nal_unit_type } => (nal_unit_type & 0x3f).encode(buf),
+            Self::StreamAccessPoint {
+                dependent_flag,
+                sap_type,
+            } => ((*dependent_flag as u8) << 7 | (sap_type & 0x0f)).encode(buf),
+            Self::TemporalLevel {
+                level_independently_decodable,
+            } => ((*level_independently_decodable as u8) << 7).encode(buf),
+            Self::AlternativeStartup(entry) => {
+                let roll_count: u16 = entry
+                    .sample_offsets
+                    .len()
+                    .try_into()
+                    .map_err(|_| Error::TooLarge(ALST_4CC))?;
+                roll_count.encode(buf)?;
+                entry.first_output_sample.encode(buf)?;
+                for sample_offset in &entry.sample_offsets {
+                    sample_offset.encode(buf)?;
+                }
+                for (num_output_samples, num_total_samples) in &entry.output_samples {
+                    num_output_samples.encode(buf)?;
+                    num_total_samples.encode(buf)?;
+                }
+                Ok(())
+            }
+            Self::TemporalLayer(entry) => entry.encode(buf),
+            Self::OperatingPoints(entry) => entry.encode(buf),
+            Self::LayerInfo(entry) => entry.encode(buf),
+            Self::TileRegion(entry) => entry.encode(buf),
+            Self::TileSet(entry) => entry.encode(buf),
+            Self::Encryption(entry) => entry.encode(buf),
+            Self::Custom(entry) => entry.encode(buf),
             Self::UnknownGroupingType(_, bytes) => bytes.encode(buf),
         }
     }
+
+    /// The grouping type of the entry, which is implied for built-in entries.
+    pub fn grouping_type(&self) -> FourCC {
+        match self {
+            Self::DirectReferenceSampleList(..) => REFS_4CC,
+            Self::RollRecovery(_) => ROLL_4CC,
+            Self::PreRoll(_) => PROL_4CC,
+            Self::RandomAccess { .. } => RAP_4CC,
+            Self::SyncSample { .. } => SYNC_4CC,
+            Self::StreamAccessPoint { .. } => SAP_4CC,
+            Self::TemporalLevel { .. } => TELE_4CC,
+            Self::AlternativeStartup(_) => ALST_4CC,
+            Self::TemporalLayer(_) => TSCL_4CC,
+            Self::OperatingPoints(_) => OINF_4CC,
+            Self::LayerInfo(_) => LINF_4CC,
+            Self::TileRegion(_) => TRIF_4CC,
+            Self::TileSet(_) => TSIF_4CC,
+            Self::Encryption(_) => SEIG_4CC,
+            Self::Custom(entry) => entry.grouping_type(),
+            Self::UnknownGroupingType(grouping_type, _) => *grouping_type,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -206,10 +435,7 @@ mod tests {
                 essential: false,
                 entries: vec![SgpdEntry {
                     description_length: Some(2),
-                    entry: AnySampleGroupEntry::UnknownGroupingType(
-                        FourCC::from(b"roll"),
-                        SIMPLE_SGPD[24..].to_vec()
-                    )
+                    entry: AnySampleGroupEntry::RollRecovery(-1),
                 }],
             }
         )
@@ -226,10 +452,7 @@ mod tests {
             essential: false,
             entries: vec![SgpdEntry {
                 description_length: Some(2),
-                entry: AnySampleGroupEntry::UnknownGroupingType(
-                    FourCC::from(b"roll"),
-                    SIMPLE_SGPD[24..].to_vec(),
-                ),
+                entry: AnySampleGroupEntry::RollRecovery(-1),
             }],
         };
         let mut buf = Vec::new();
@@ -276,4 +499,172 @@ mod tests {
 
         assert_eq!(encoded, SGPD_ENCODED_C041);
     }
+
+    fn roundtrip(
+        grouping_type: &[u8; 4],
+        default_length: Option<u32>,
+        entries: Vec<AnySampleGroupEntry>,
+    ) {
+        let sgpd = Sgpd {
+            grouping_type: FourCC::new(grouping_type),
+            default_length,
+            default_group_description_index: None,
+            static_group_description: false,
+            static_mapping: false,
+            essential: false,
+            entries: entries
+                .into_iter()
+                .map(|entry| SgpdEntry {
+                    description_length: default_length,
+                    entry,
+                })
+                .collect(),
+        };
+
+        let mut buf = Vec::new();
+        sgpd.encode(&mut buf).unwrap();
+
+        let decoded = Sgpd::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded.entries.len(), sgpd.entries.len());
+        for (decoded, expected) in decoded.entries.iter().zip(&sgpd.entries) {
+            assert_eq!(decoded.entry, expected.entry);
+            assert_eq!(decoded.entry.grouping_type(), sgpd.grouping_type);
+        }
+    }
+
+    #[test]
+    fn sgpd_typed_entries() {
+        roundtrip(b"prol", Some(2), vec![AnySampleGroupEntry::PreRoll(-2)]);
+        roundtrip(
+            b"rap ",
+            None,
+            vec![
+                AnySampleGroupEntry::RandomAccess {
+                    num_leading_samples_known: true,
+                    num_leading_samples: 5,
+                },
+                AnySampleGroupEntry::RandomAccess {
+                    num_leading_samples_known: false,
+                    num_leading_samples: 0,
+                },
+            ],
+        );
+        roundtrip(
+            b"sync",
+            Some(1),
+            vec![AnySampleGroupEntry::SyncSample { nal_unit_type: 20 }],
+        );
+        roundtrip(
+            b"sap ",
+            Some(1),
+            vec![AnySampleGroupEntry::StreamAccessPoint {
+                dependent_flag: true,
+                sap_type: 3,
+            }],
+        );
+        roundtrip(
+            b"tele",
+            Some(1),
+            vec![AnySampleGroupEntry::TemporalLevel {
+                level_independently_decodable: true,
+            }],
+        );
+    }
+
+    #[test]
+    fn sgpd_alst_lengths() {
+        // The optional pairs depend on the description length to know where the entry ends.
+        let entries = vec![
+            AnySampleGroupEntry::AlternativeStartup(AlternativeStartupEntry {
+                first_output_sample: 1,
+                sample_offsets: vec![0, 4096],
+                output_samples: vec![(2, 3)],
+            }),
+            AnySampleGroupEntry::AlternativeStartup(AlternativeStartupEntry {
+                first_output_sample: 2,
+                sample_offsets: vec![512],
+                output_samples: vec![],
+            }),
+        ];
+        roundtrip(b"alst", Some(0), entries.clone());
+
+        // A fixed length must match every entry.
+        let sgpd = Sgpd {
+            grouping_type: FourCC::new(b"alst"),
+            default_length: Some(8),
+            default_group_description_index: None,
+            static_group_description: false,
+            static_mapping: false,
+            essential: false,
+            entries: entries
+                .into_iter()
+                .map(|entry| SgpdEntry {
+                    description_length: Some(8),
+                    entry,
+                })
+                .collect(),
+        };
+        assert!(matches!(
+            sgpd.encode(&mut Vec::new()),
+            Err(Error::InvalidSize)
+        ));
+    }
+
+    #[test]
+    fn sgpd_unknown_lengths() {
+        // Unknown entries are split using the per-entry description length.
+        let encoded = [
+            0x00, 0x00, 0x00, 0x23, b's', b'g', b'p', b'd', 0x01, 0x00, 0x00, 0x00, b'x', b'y',
+            b'z', b'w', 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01,
+            0xaa, 0x00, 0x00, 0x00, 0x02, 0xbb, 0xcc,
+        ];
+        let sgpd = Sgpd::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(
+            sgpd.entries,
+            [
+                SgpdEntry {
+                    description_length: Some(1),
+                    entry: AnySampleGroupEntry::UnknownGroupingType(
+                        FourCC::new(b"xyzw"),
+                        vec![0xaa]
+                    ),
+                },
+                SgpdEntry {
+                    description_length: Some(2),
+                    entry: AnySampleGroupEntry::UnknownGroupingType(
+                        FourCC::new(b"xyzw"),
+                        vec![0xbb, 0xcc]
+                    ),
+                },
+            ]
+        );
+
+        let mut buf = Vec::new();
+        sgpd.encode(&mut buf).unwrap();
+        assert_eq!(buf, encoded);
+    }
+
+    #[test]
+    fn sgpd_extended_entry() {
+        // A roll entry with two extra bytes is kept as is, instead of dropping them.
+        let encoded = [
+            0x00, 0x00, 0x00, 0x1c, b's', b'g', b'p', b'd', 0x01, 0x00, 0x00, 0x00, b'r', b'o',
+            b'l', b'l', 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0xff, 0xfe, 0xaa, 0xbb,
+        ];
+        let sgpd = Sgpd::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(
+            sgpd.entries,
+            [SgpdEntry {
+                description_length: Some(4),
+                entry: AnySampleGroupEntry::UnknownGroupingType(
+                    FourCC::new(b"roll"),
+                    vec![0xff, 0xfe, 0xaa, 0xbb]
+                ),
+            }]
+        );
+
+        let mut buf = Vec::new();
+        sgpd.encode(&mut buf).unwrap();
+        assert_eq!(buf, encoded);
+    }
 }
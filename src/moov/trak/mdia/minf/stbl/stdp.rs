@@ -0,0 +1,52 @@
+use crate::*;
+
+/// Degradation Priority Box (stdp)
+///
+/// The degradation priority of each sample.
+/// The sample count isn't stored, so it must match the `stsz`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stdp {
+    pub priorities: Vec<u16>,
+}
+
+impl AtomExt for Stdp {
+    type Ext = ();
+
+    const KIND_EXT: FourCC = FourCC::new(b"stdp");
+
+    fn decode_body_ext<B: Buf>(buf: &mut B, _ext: ()) -> Result<Self> {
+        let mut priorities = Vec::with_capacity(buf.remaining() / 2);
+        while buf.has_remaining() {
+            priorities.push(u16::decode(buf)?);
+        }
+
+        Ok(Stdp { priorities })
+    }
+
+    fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        for priority in &self.priorities {
+            priority.encode(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stdp() {
+        let expected = Stdp {
+            priorities: vec![0, 3, 0x7fff],
+        };
+        let mut buf = Vec::new();
+        expected.encode(&mut buf).unwrap();
+
+        let mut buf = buf.as_ref();
+        let decoded = Stdp::decode(&mut buf).unwrap();
+        assert_eq!(decoded, expected);
+    }
+}
@@ -35,6 +35,12 @@ impl Atom for Ac3 {
         self.dac3.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ dac3 ],
+        optional: [],
+        multiple: [],
+    }
 }
 
 // AC-3 specific data
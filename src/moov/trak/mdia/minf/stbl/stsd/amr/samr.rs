@@ -32,6 +32,12 @@ impl Atom for Samr {
         self.damr.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ damr ],
+        optional: [],
+        multiple: [],
+    }
 }
 
 #[cfg(test)]
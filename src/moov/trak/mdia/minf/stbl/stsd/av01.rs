@@ -1,5 +1,5 @@
 use crate::coding::{Decode, Encode};
-use crate::{Any, Atom, Buf, BufMut, Ccst, DecodeMaybe, Error, FourCC, Result};
+use crate::{visit, Any, Atom, Buf, BufMut, Ccst, DecodeMaybe, Error, FourCC, Result};
 
 use super::{Btrt, Colr, Pasp, Taic, Visual};
 
@@ -61,6 +61,12 @@ impl Atom for Av01 {
 
         Ok(())
     }
+
+    visit! {
+        required: [ av1c ],
+        optional: [ btrt, ccst, colr, pasp, taic ],
+        multiple: [],
+    }
 }
 
 // https://aomediacodec.github.io/av1-isobmff/#av1codecconfigurationbox-section
@@ -35,6 +35,12 @@ impl Atom for Eac3 {
         self.dec3.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ dec3 ],
+        optional: [],
+        multiple: [],
+    }
 }
 
 // EAC-3 specific data
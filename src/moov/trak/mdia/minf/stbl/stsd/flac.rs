@@ -32,6 +32,12 @@ impl Atom for Flac {
         self.dfla.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ dfla ],
+        optional: [],
+        multiple: [],
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
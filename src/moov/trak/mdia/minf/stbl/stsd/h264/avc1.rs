@@ -57,6 +57,12 @@ impl Atom for Avc1 {
         self.fiel.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ avcc ],
+        optional: [ btrt, colr, pasp, taic, fiel ],
+        multiple: [],
+    }
 }
 
 #[cfg(test)]
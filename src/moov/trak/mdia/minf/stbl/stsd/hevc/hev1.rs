@@ -62,6 +62,12 @@ impl Atom for Hev1 {
         self.fiel.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ hvcc ],
+        optional: [ lhvc, btrt, colr, pasp, taic, fiel ],
+        multiple: [],
+    }
 }
 
 #[cfg(test)]
@@ -68,6 +68,12 @@ impl Atom for Hvc1 {
         self.ccst.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ hvcc ],
+        optional: [ lhvc, btrt, colr, pasp, taic, fiel, ccst ],
+        multiple: [],
+    }
 }
 
 #[cfg(test)]
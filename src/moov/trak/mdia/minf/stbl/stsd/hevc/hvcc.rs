@@ -461,7 +461,10 @@ mod tests {
     fn test_hvcc_huge_nalu_count() {
         let buf: &mut std::io::Cursor<&&[u8]> =
             &mut std::io::Cursor::new(&ENCODED_HVCC_HUGE_NALU_COUNT);
-        assert!(matches!(Hvcc::decode(buf), Err(Error::OverDecode(_))));
+        assert!(matches!(
+            Hvcc::decode(buf).map_err(Error::into_root),
+            Err(Error::OverDecode(_))
+        ));
     }
 
     #[test]
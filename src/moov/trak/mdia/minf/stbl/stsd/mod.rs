@@ -198,6 +198,116 @@ impl Encode for Codec {
     }
 }
 
+impl Codec {
+    pub(crate) fn kind(&self) -> FourCC {
+        match self {
+            Self::Unknown(kind) => *kind,
+            Self::Avc1(_) => Avc1::KIND,
+            Self::Hev1(_) => Hev1::KIND,
+            Self::Hvc1(_) => Hvc1::KIND,
+            Self::Vp08(_) => Vp08::KIND,
+            Self::Vp09(_) => Vp09::KIND,
+            Self::Mp4a(_) => Mp4a::KIND,
+            Self::Tx3g(_) => Tx3g::KIND,
+            Self::Av01(_) => Av01::KIND,
+            Self::Opus(_) => Opus::KIND,
+            Self::Uncv(_) => Uncv::KIND,
+            Self::Flac(_) => Flac::KIND,
+            Self::Ac3(_) => Ac3::KIND,
+            Self::Eac3(_) => Eac3::KIND,
+            Self::Ipcm(_) => Ipcm::KIND,
+            Self::Fpcm(_) => Fpcm::KIND,
+            Self::Sowt(_) => Sowt::KIND,
+            Self::Twos(_) => Twos::KIND,
+            Self::Lpcm(_) => Lpcm::KIND,
+            Self::In24(_) => In24::KIND,
+            Self::In32(_) => In32::KIND,
+            Self::Fl32(_) => Fl32::KIND,
+            Self::Fl64(_) => Fl64::KIND,
+            Self::S16l(_) => S16l::KIND,
+            Self::Wvtt(_) => Wvtt::KIND,
+            Self::Samr(_) => Samr::KIND,
+        }
+    }
+
+    fn visit<'a>(
+        &'a self,
+        index: Option<usize>,
+        path: &mut AtomPath,
+        visitor: &mut dyn Visitor<'a>,
+    ) {
+        match self {
+            // Visited as the whole entry, as the body isn't kept.
+            Self::Unknown(kind) => visit_opaque(*kind, self, index, path, visitor),
+            Self::Avc1(atom) => visit_atom(atom, index, path, visitor),
+            Self::Hev1(atom) => visit_atom(atom, index, path, visitor),
+            Self::Hvc1(atom) => visit_atom(atom, index, path, visitor),
+            Self::Vp08(atom) => visit_atom(atom, index, path, visitor),
+            Self::Vp09(atom) => visit_atom(atom, index, path, visitor),
+            Self::Mp4a(atom) => visit_atom(atom, index, path, visitor),
+            Self::Tx3g(atom) => visit_atom(atom, index, path, visitor),
+            Self::Av01(atom) => visit_atom(atom, index, path, visitor),
+            Self::Opus(atom) => visit_atom(atom, index, path, visitor),
+            Self::Uncv(atom) => visit_atom(atom, index, path, visitor),
+            Self::Flac(atom) => visit_atom(atom, index, path, visitor),
+            Self::Ac3(atom) => visit_atom(atom, index, path, visitor),
+            Self::Eac3(atom) => visit_atom(atom, index, path, visitor),
+            Self::Ipcm(atom) => visit_atom(atom, index, path, visitor),
+            Self::Fpcm(atom) => visit_atom(atom, index, path, visitor),
+            Self::Sowt(atom) => visit_atom(atom, index, path, visitor),
+            Self::Twos(atom) => visit_atom(atom, index, path, visitor),
+            Self::Lpcm(atom) => visit_atom(atom, index, path, visitor),
+            Self::In24(atom) => visit_atom(atom, index, path, visitor),
+            Self::In32(atom) => visit_atom(atom, index, path, visitor),
+            Self::Fl32(atom) => visit_atom(atom, index, path, visitor),
+            Self::Fl64(atom) => visit_atom(atom, index, path, visitor),
+            Self::S16l(atom) => visit_atom(atom, index, path, visitor),
+            Self::Wvtt(atom) => visit_atom(atom, index, path, visitor),
+            Self::Samr(atom) => visit_atom(atom, index, path, visitor),
+        }
+    }
+
+    fn visit_mut(
+        &mut self,
+        index: Option<usize>,
+        path: &mut AtomPath,
+        visitor: &mut dyn VisitorMut,
+    ) {
+        if let Self::Unknown(kind) = *self {
+            return visit_opaque_mut(kind, self, index, path, visitor);
+        }
+
+        match self {
+            Self::Unknown(_) => {}
+            Self::Avc1(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Hev1(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Hvc1(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Vp08(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Vp09(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Mp4a(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Tx3g(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Av01(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Opus(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Uncv(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Flac(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Ac3(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Eac3(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Ipcm(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Fpcm(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Sowt(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Twos(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Lpcm(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::In24(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::In32(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Fl32(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Fl64(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::S16l(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Wvtt(atom) => visit_atom_mut(atom, index, path, visitor),
+            Self::Samr(atom) => visit_atom_mut(atom, index, path, visitor),
+        }
+    }
+}
+
 impl AtomExt for Stsd {
     type Ext = ();
 
@@ -223,4 +333,21 @@ impl AtomExt for Stsd {
 
         Ok(())
     }
+
+    // Each entry is indexed among the entries of the same kind.
+    fn visit_children_ext<'a>(&'a self, path: &mut AtomPath, visitor: &mut dyn Visitor<'a>) {
+        for (i, codec) in self.codecs.iter().enumerate() {
+            let kind = codec.kind();
+            let index = self.codecs[..i].iter().filter(|c| c.kind() == kind).count();
+            codec.visit(Some(index), path, visitor);
+        }
+    }
+
+    fn visit_children_mut_ext(&mut self, path: &mut AtomPath, visitor: &mut dyn VisitorMut) {
+        for i in 0..self.codecs.len() {
+            let kind = self.codecs[i].kind();
+            let index = self.codecs[..i].iter().filter(|c| c.kind() == kind).count();
+            self.codecs[i].visit_mut(Some(index), path, visitor);
+        }
+    }
 }
@@ -54,6 +54,12 @@ impl Atom for Mp4a {
 
         Ok(())
     }
+
+    visit! {
+        required: [ esds ],
+        optional: [ btrt, taic ],
+        multiple: [],
+    }
 }
 
 /// QuickTime `wave` (siDecompressionParam) container FourCC.
@@ -39,6 +39,12 @@ impl Atom for Opus {
         self.btrt.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ dops ],
+        optional: [ btrt ],
+        multiple: [],
+    }
 }
 
 /*
@@ -271,6 +271,12 @@ macro_rules! define_pcm_sample_entry {
                     buf,
                 )
             }
+
+            visit! {
+                required: [],
+                optional: [ pcmc, chnl, btrt ],
+                multiple: [],
+            }
         }
     };
 }
@@ -648,7 +654,7 @@ mod tests {
 
         let buf = &mut std::io::Cursor::new(&encoded);
         assert!(matches!(
-            Ipcm::decode(buf),
+            Ipcm::decode(buf).map_err(Error::into_root),
             Err(Error::MissingBox(PcmC::KIND))
         ));
     }
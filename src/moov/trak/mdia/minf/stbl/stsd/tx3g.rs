@@ -113,6 +113,12 @@ impl Atom for Tx3g {
 
         Ok(())
     }
+
+    visit! {
+        required: [],
+        optional: [ ftab ],
+        multiple: [],
+    }
 }
 
 #[cfg(test)]
@@ -60,6 +60,12 @@ impl Atom for Uncv {
 
         Ok(())
     }
+
+    visit! {
+        required: [ uncc ],
+        optional: [ cmpd, btrt, ccst, pasp, taic ],
+        multiple: [],
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -338,7 +344,10 @@ mod tests {
     #[test]
     fn test_cmpd_huge_count() {
         let buf = &mut std::io::Cursor::new(&ENCODED_CMPD_HUGE_COUNT);
-        assert!(matches!(Cmpd::decode(buf), Err(Error::OverDecode(_))));
+        assert!(matches!(
+            Cmpd::decode(buf).map_err(Error::into_root),
+            Err(Error::OverDecode(_))
+        ));
     }
 
     #[test]
@@ -416,7 +425,10 @@ mod tests {
     #[test]
     fn test_uncc_huge_count() {
         let buf = &mut std::io::Cursor::new(&ENCODED_UNCC_HUGE_COUNT);
-        assert!(matches!(UncC::decode(buf), Err(Error::OverDecode(_))));
+        assert!(matches!(
+            UncC::decode(buf).map_err(Error::into_root),
+            Err(Error::OverDecode(_))
+        ));
     }
 
     #[test]
@@ -49,4 +49,10 @@ impl Atom for Vp08 {
 
         Ok(())
     }
+
+    visit! {
+        required: [ vpcc ],
+        optional: [ btrt, colr, pasp ],
+        multiple: [],
+    }
 }
@@ -49,6 +49,12 @@ impl Atom for Vp09 {
 
         Ok(())
     }
+
+    visit! {
+        required: [ vpcc ],
+        optional: [ btrt, colr, pasp ],
+        multiple: [],
+    }
 }
 
 #[cfg(test)]
@@ -86,6 +86,12 @@ impl Atom for Wvtt {
         self.btrt.encode(buf)?;
         Ok(())
     }
+
+    visit! {
+        required: [ config ],
+        optional: [ label, btrt ],
+        multiple: [],
+    }
 }
 
 fn decode_boxstring<B: Buf>(buf: &mut B) -> Result<String> {
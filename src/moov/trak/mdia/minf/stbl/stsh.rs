@@ -0,0 +1,74 @@
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StshEntry {
+    pub shadowed_sample_number: u32,
+    pub sync_sample_number: u32,
+}
+
+/// Shadow Sync Sample Box (stsh)
+///
+/// Alternative sync samples that can be decoded instead of a non-sync sample when seeking.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stsh {
+    pub entries: Vec<StshEntry>,
+}
+
+impl AtomExt for Stsh {
+    type Ext = ();
+
+    const KIND_EXT: FourCC = FourCC::new(b"stsh");
+
+    fn decode_body_ext<B: Buf>(buf: &mut B, _ext: ()) -> Result<Self> {
+        let entry_count = u32::decode(buf)?;
+
+        let mut entries = Vec::with_capacity(entry_count.min(1024) as usize);
+        for _ in 0..entry_count {
+            entries.push(StshEntry {
+                shadowed_sample_number: u32::decode(buf)?,
+                sync_sample_number: u32::decode(buf)?,
+            });
+        }
+
+        Ok(Stsh { entries })
+    }
+
+    fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        (self.entries.len() as u32).encode(buf)?;
+        for entry in &self.entries {
+            entry.shadowed_sample_number.encode(buf)?;
+            entry.sync_sample_number.encode(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stsh() {
+        let expected = Stsh {
+            entries: vec![
+                StshEntry {
+                    shadowed_sample_number: 10,
+                    sync_sample_number: 1,
+                },
+                StshEntry {
+                    shadowed_sample_number: 70,
+                    sync_sample_number: 61,
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        expected.encode(&mut buf).unwrap();
+
+        let mut buf = buf.as_ref();
+        let decoded = Stsh::decode(&mut buf).unwrap();
+        assert_eq!(decoded, expected);
+    }
+}
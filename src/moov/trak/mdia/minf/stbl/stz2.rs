@@ -0,0 +1,119 @@
+use crate::*;
+
+/// Compact Sample Size Box (stz2)
+///
+/// An alternative to `stsz` that stores each sample size in 4, 8 or 16 bits.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stz2 {
+    /// The number of bits per size: 4, 8 or 16.
+    pub field_size: u8,
+    pub sizes: Vec<u16>,
+}
+
+impl AtomExt for Stz2 {
+    type Ext = ();
+
+    const KIND_EXT: FourCC = FourCC::new(b"stz2");
+
+    fn decode_body_ext<B: Buf>(buf: &mut B, _ext: ()) -> Result<Self> {
+        <[u8; 3]>::decode(buf)?;
+        let field_size = u8::decode(buf)?;
+        let count = u32::decode(buf)? as usize;
+
+        let mut sizes = Vec::with_capacity(count.min(1024));
+        match field_size {
+            4 => {
+                for _ in 0..count.div_ceil(2) {
+                    let byte = u8::decode(buf)?;
+                    sizes.push((byte >> 4) as u16);
+                    sizes.push((byte & 0x0f) as u16);
+                }
+
+                // The last byte is padded when the count is odd.
+                sizes.truncate(count);
+            }
+            8 => {
+                for _ in 0..count {
+                    sizes.push(u8::decode(buf)? as u16);
+                }
+            }
+            16 => {
+                for _ in 0..count {
+                    sizes.push(u16::decode(buf)?);
+                }
+            }
+            _ => return Err(Error::InvalidSize),
+        }
+
+        Ok(Stz2 { field_size, sizes })
+    }
+
+    fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        let max = match self.field_size {
+            4 => 0x0f,
+            8 => 0xff,
+            16 => u16::MAX,
+            _ => return Err(Error::InvalidSize),
+        };
+
+        if self.sizes.iter().any(|&size| size > max) {
+            return Err(Error::InvalidSize);
+        }
+
+        [0u8; 3].encode(buf)?;
+        self.field_size.encode(buf)?;
+        (self.sizes.len() as u32).encode(buf)?;
+
+        match self.field_size {
+            4 => {
+                for pair in self.sizes.chunks(2) {
+                    let low = pair.get(1).copied().unwrap_or(0);
+                    ((pair[0] << 4 | low) as u8).encode(buf)?;
+                }
+            }
+            8 => {
+                for &size in &self.sizes {
+                    (size as u8).encode(buf)?;
+                }
+            }
+            _ => {
+                for size in &self.sizes {
+                    size.encode(buf)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stz2() {
+        for field_size in [4, 8, 16] {
+            let expected = Stz2 {
+                field_size,
+                sizes: vec![1, 15, 7, 0, 9],
+            };
+            let mut buf = Vec::new();
+            expected.encode(&mut buf).unwrap();
+
+            let mut buf = buf.as_ref();
+            let decoded = Stz2::decode(&mut buf).unwrap();
+            assert_eq!(decoded, expected);
+        }
+
+        let invalid = Stz2 {
+            field_size: 4,
+            sizes: vec![16],
+        };
+        assert!(matches!(
+            invalid.encode(&mut Vec::new()),
+            Err(Error::InvalidSize)
+        ));
+    }
+}
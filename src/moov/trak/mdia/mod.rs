@@ -14,6 +14,9 @@ pub struct Mdia {
     pub mdhd: Mdhd,
     pub hdlr: Hdlr,
     pub minf: Minf,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Mdia {
@@ -23,5 +26,6 @@ impl Atom for Mdia {
         required: [ Mdhd, Hdlr, Minf ],
         optional: [] ,
         multiple: [],
+        custom: custom,
     }
 }
@@ -0,0 +1,203 @@
+use crate::*;
+
+impl Trak {
+    /// A video track (`vide`) with a single sample entry and no samples.
+    ///
+    /// The `tkhd` width and height are taken from the sample entry, which must be a visual sample entry.
+    pub fn video(track_id: u32, timescale: u32, codec: impl Into<Codec>) -> Result<Self> {
+        let codec = codec.into();
+        let (width, height) = visual(&codec)
+            .map(|visual| (visual.width, visual.height))
+            .ok_or(Error::InvalidCombination(
+                "video track requires a visual sample entry",
+            ))?;
+
+        let mut trak = Self::media(track_id, timescale, b"vide", "VideoHandler", codec);
+        trak.tkhd.width = width.into();
+        trak.tkhd.height = height.into();
+        trak.mdia.minf.vmhd = Some(Vmhd::default());
+
+        Ok(trak)
+    }
+
+    /// An audio track (`soun`) with a single sample entry and no samples, at full volume.
+    pub fn audio(track_id: u32, timescale: u32, codec: impl Into<Codec>) -> Self {
+        let mut trak = Self::media(track_id, timescale, b"soun", "SoundHandler", codec.into());
+        trak.tkhd.volume = 1.into();
+        trak.mdia.minf.smhd = Some(Smhd::default());
+        trak
+    }
+
+    /// A timed text track (`text`), such as WebVTT, with a single sample entry and no samples.
+    pub fn text(track_id: u32, timescale: u32, codec: impl Into<Codec>) -> Self {
+        let mut trak = Self::media(track_id, timescale, b"text", "TextHandler", codec.into());
+        trak.mdia.minf.nmhd = Some(Nmhd {});
+        trak
+    }
+
+    /// A subtitle track (`subt`) with a single sample entry and no samples.
+    pub fn subtitle(track_id: u32, timescale: u32, codec: impl Into<Codec>) -> Self {
+        let mut trak = Self::media(
+            track_id,
+            timescale,
+            b"subt",
+            "SubtitleHandler",
+            codec.into(),
+        );
+        trak.mdia.minf.sthd = Some(Sthd {});
+        trak
+    }
+
+    /// A timed metadata track (`meta`) with a single sample entry and no samples.
+    pub fn metadata(track_id: u32, timescale: u32, codec: impl Into<Codec>) -> Self {
+        let mut trak = Self::media(track_id, timescale, b"meta", "MetaHandler", codec.into());
+        trak.mdia.minf.nmhd = Some(Nmhd {});
+        trak
+    }
+
+    // The boxes shared by every kind of track, without a media header.
+    fn media(track_id: u32, timescale: u32, handler: &[u8; 4], name: &str, codec: Codec) -> Self {
+        Trak {
+            tkhd: Tkhd {
+                track_id,
+                enabled: true,
+                in_movie: true,
+                ..Default::default()
+            },
+            mdia: Mdia {
+                mdhd: Mdhd {
+                    timescale,
+                    language: "und".into(),
+                    ..Default::default()
+                },
+                hdlr: Hdlr {
+                    handler: handler.into(),
+                    name: name.into(),
+                },
+                minf: Minf {
+                    dinf: Dinf {
+                        // An empty location means the media is in the same file.
+                        dref: Dref {
+                            urls: vec![Url::default()],
+                        },
+                        ..Default::default()
+                    },
+                    stbl: Stbl {
+                        stsd: Stsd {
+                            codecs: vec![codec],
+                        },
+                        stsz: Some(Stsz::default()),
+                        stco: Some(Stco::default()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+fn visual(codec: &Codec) -> Option<&Visual> {
+    Some(match codec {
+        Codec::Avc1(avc1) => &avc1.visual,
+        Codec::Hev1(hev1) => &hev1.visual,
+        Codec::Hvc1(hvc1) => &hvc1.visual,
+        Codec::Vp08(vp08) => &vp08.visual,
+        Codec::Vp09(vp09) => &vp09.visual,
+        Codec::Av01(av01) => &av01.visual,
+        Codec::Uncv(uncv) => &uncv.visual,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(trak: &Trak) {
+        // An empty track still needs the required sample tables.
+        assert!(trak.mdia.minf.stbl.stsz.is_some());
+
+        let mut buf = Vec::new();
+        trak.encode(&mut buf).unwrap();
+
+        let decoded = Trak::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(&decoded, trak);
+    }
+
+    #[test]
+    fn video() {
+        let avc1 = Avc1 {
+            visual: Visual {
+                data_reference_index: 1,
+                width: 1280,
+                height: 720,
+                ..Default::default()
+            },
+            avcc: Avcc {
+                configuration_version: 1,
+                avc_profile_indication: 66,
+                avc_level_indication: 31,
+                length_size: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let trak = Trak::video(1, 90000, avc1).unwrap();
+        assert_eq!(trak.tkhd.width, 1280.into());
+        assert_eq!(trak.tkhd.height, 720.into());
+        assert_eq!(trak.tkhd.volume, 0.into());
+        assert_eq!(trak.mdia.hdlr.handler, b"vide".into());
+        assert!(trak.mdia.minf.vmhd.is_some());
+        roundtrip(&trak);
+    }
+
+    #[test]
+    fn audio() {
+        let mp4a = Mp4a {
+            audio: Audio {
+                data_reference_index: 1,
+                channel_count: 2,
+                sample_size: 16,
+                sample_rate: 48000.into(),
+            },
+            esds: Esds::default(),
+            btrt: None,
+            taic: None,
+        };
+
+        let trak = Trak::audio(2, 48000, mp4a.clone());
+        assert_eq!(trak.tkhd.volume, 1.into());
+        assert_eq!(trak.tkhd.width, 0.into());
+        assert_eq!(trak.mdia.hdlr.handler, b"soun".into());
+        assert!(trak.mdia.minf.smhd.is_some());
+        roundtrip(&trak);
+
+        assert!(matches!(
+            Trak::video(2, 48000, mp4a),
+            Err(Error::InvalidCombination(_))
+        ));
+    }
+
+    #[test]
+    fn text() {
+        let wvtt = Wvtt {
+            plaintext: PlainText {
+                data_reference_index: 1,
+            },
+            config: VttC {
+                config: "WEBVTT\n".into(),
+            },
+            label: None,
+            btrt: None,
+        };
+
+        let trak = Trak::text(3, 1000, wvtt);
+        assert_eq!(trak.mdia.hdlr.handler, b"text".into());
+        assert!(trak.mdia.minf.nmhd.is_some());
+        roundtrip(&trak);
+    }
+}
@@ -1,12 +1,15 @@
 mod edts;
 mod mdia;
+mod media;
 mod senc;
+mod tenc;
 mod tkhd;
 mod tref;
 
 pub use edts::*;
 pub use mdia::*;
 pub use senc::*;
+pub use tenc::*;
 pub use tkhd::*;
 pub use tref::*;
 
@@ -22,6 +25,9 @@ pub struct Trak {
     pub senc: Option<Senc>,
     pub tref: Option<Tref>,
     pub udta: Option<Udta>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Trak {
@@ -31,5 +37,6 @@ impl Atom for Trak {
         required: [ Tkhd, Mdia ],
         optional: [ Edts, Meta, Senc, Tref, Udta ],
         multiple: [],
+        custom: custom,
     }
 }
@@ -0,0 +1,318 @@
+use crate::*;
+
+/// The Common Encryption parameters of a sample, from either a `seig` sample group or the `tenc` defaults.
+///
+/// ISO/IEC 23001-7:2023 (Common Encryption), Sections 8.2 and 6
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncryptionInfo {
+    /// The number of encrypted 16-byte blocks in the pattern, or zero if there's no pattern.
+    pub crypt_byte_block: u8,
+
+    /// The number of unencrypted 16-byte blocks in the pattern.
+    pub skip_byte_block: u8,
+
+    pub is_protected: bool,
+
+    /// The size of the IV stored per sample in the `senc`, or zero if the constant IV is used.
+    pub per_sample_iv_size: u8,
+    pub kid: [u8; 16],
+
+    /// The IV used by every sample, only present when protected with a per-sample IV size of zero.
+    pub constant_iv: Option<Vec<u8>>,
+}
+
+impl EncryptionInfo {
+    // Decode everything after the crypt and skip byte blocks.
+    fn decode_rest<B: Buf>(buf: &mut B, crypt_byte_block: u8, skip_byte_block: u8) -> Result<Self> {
+        let is_protected = u8::decode(buf)? == 1;
+        let per_sample_iv_size = u8::decode(buf)?;
+        let kid = <[u8; 16]>::decode(buf)?;
+
+        let constant_iv = match is_protected && per_sample_iv_size == 0 {
+            true => {
+                let size = u8::decode(buf)? as usize;
+                if buf.remaining() < size {
+                    return Err(Error::OutOfBounds);
+                }
+
+                let iv = buf.slice(size).to_vec();
+                buf.advance(size);
+                Some(iv)
+            }
+            false => None,
+        };
+
+        Ok(Self {
+            crypt_byte_block,
+            skip_byte_block,
+            is_protected,
+            per_sample_iv_size,
+            kid,
+            constant_iv,
+        })
+    }
+
+    fn encode_rest<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        (self.is_protected as u8).encode(buf)?;
+        self.per_sample_iv_size.encode(buf)?;
+        self.kid.encode(buf)?;
+
+        if self.is_protected && self.per_sample_iv_size == 0 {
+            let iv = self.constant_iv.as_deref().unwrap_or_default();
+            let size: u8 = iv.len().try_into().map_err(|_| Error::InvalidSize)?;
+            size.encode(buf)?;
+            iv.encode(buf)?;
+        }
+
+        Ok(())
+    }
+
+    fn pattern(&self) -> u8 {
+        (self.crypt_byte_block & 0x0f) << 4 | (self.skip_byte_block & 0x0f)
+    }
+}
+
+/// The body of a `seig` sample group entry.
+impl Decode for EncryptionInfo {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        u8::decode(buf)?;
+        let pattern = u8::decode(buf)?;
+        Self::decode_rest(buf, pattern >> 4, pattern & 0x0f)
+    }
+}
+
+impl Encode for EncryptionInfo {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        0u8.encode(buf)?;
+        self.pattern().encode(buf)?;
+        self.encode_rest(buf)
+    }
+}
+
+/// The version of a `tenc`, where version 1 adds pattern encryption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TencBoxVersion {
+    #[default]
+    V0,
+    V1,
+}
+
+/// TrackEncryptionBox (`tenc`), ISO/IEC 23001-7:2023 Section 8.2
+///
+/// The default encryption parameters of a track, found in `sinf/schi` of an encrypted sample entry.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tenc {
+    /// Version 0 can't signal a pattern, so version 1 is used regardless when the pattern isn't zero.
+    pub version: TencBoxVersion,
+    pub default: EncryptionInfo,
+}
+
+ext! {
+    name: Tenc,
+    versions: [0, 1],
+    flags: {}
+}
+
+impl AtomExt for Tenc {
+    type Ext = TencExt;
+
+    const KIND_EXT: FourCC = FourCC::new(b"tenc");
+
+    fn decode_body_ext<B: Buf>(buf: &mut B, ext: TencExt) -> Result<Self> {
+        u8::decode(buf)?;
+
+        // Version 0 doesn't support pattern encryption.
+        let pattern = u8::decode(buf)?;
+        let (version, default) = match ext.version {
+            TencVersion::V0 => (TencBoxVersion::V0, EncryptionInfo::decode_rest(buf, 0, 0)?),
+            TencVersion::V1 => (
+                TencBoxVersion::V1,
+                EncryptionInfo::decode_rest(buf, pattern >> 4, pattern & 0x0f)?,
+            ),
+        };
+
+        Ok(Tenc { version, default })
+    }
+
+    fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<TencExt> {
+        let pattern = self.default.pattern();
+        let version = match (self.version, pattern) {
+            (TencBoxVersion::V0, 0) => TencVersion::V0,
+            _ => TencVersion::V1,
+        };
+
+        0u8.encode(buf)?;
+        pattern.encode(buf)?;
+        self.default.encode_rest(buf)?;
+
+        Ok(TencExt { version })
+    }
+}
+
+impl Stbl {
+    /// The encryption parameters of each sample in decode order, using `seig` sample groups for key rotation.
+    ///
+    /// Samples without a `seig` group use the defaults from the `tenc`.
+    pub fn sample_encryption(&self, tenc: &Tenc) -> Result<Vec<EncryptionInfo>> {
+        self.iter_sample_groups()
+            .map(|groups| Ok(resolve(&groups?, tenc)))
+            .collect()
+    }
+}
+
+impl Traf {
+    /// The encryption parameters of each sample in this fragment, using `seig` sample groups for key rotation.
+    ///
+    /// Samples without a `seig` group use the defaults from the `tenc`.
+    pub fn sample_encryption(&self, stbl: &Stbl, tenc: &Tenc) -> Result<Vec<EncryptionInfo>> {
+        self.iter_sample_groups(stbl)
+            .map(|groups| Ok(resolve(&groups?, tenc)))
+            .collect()
+    }
+}
+
+fn resolve(groups: &[SampleGroup<'_>], tenc: &Tenc) -> EncryptionInfo {
+    groups
+        .iter()
+        .find_map(|group| match group.entry {
+            AnySampleGroupEntry::Encryption(info) => Some(info),
+            _ => None,
+        })
+        .unwrap_or(&tenc.default)
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(kid: u8, per_sample_iv_size: u8) -> EncryptionInfo {
+        EncryptionInfo {
+            crypt_byte_block: 1,
+            skip_byte_block: 9,
+            is_protected: true,
+            per_sample_iv_size,
+            kid: [kid; 16],
+            constant_iv: match per_sample_iv_size {
+                0 => Some(vec![0xaa; 16]),
+                _ => None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_tenc() {
+        for expected in [
+            Tenc {
+                version: TencBoxVersion::V1,
+                default: info(1, 0),
+            },
+            Tenc {
+                version: TencBoxVersion::V0,
+                default: EncryptionInfo {
+                    crypt_byte_block: 0,
+                    skip_byte_block: 0,
+                    ..info(2, 8)
+                },
+            },
+            // Version 1 without a pattern, as used by cbcs with full sample encryption.
+            Tenc {
+                version: TencBoxVersion::V1,
+                default: EncryptionInfo {
+                    crypt_byte_block: 0,
+                    skip_byte_block: 0,
+                    ..info(3, 8)
+                },
+            },
+        ] {
+            let mut buf = Vec::new();
+            expected.encode(&mut buf).unwrap();
+
+            let mut buf = buf.as_ref();
+            let decoded = Tenc::decode(&mut buf).unwrap();
+            assert_eq!(decoded, expected);
+        }
+
+        // A pattern needs version 1.
+        let tenc = Tenc {
+            version: TencBoxVersion::V0,
+            default: info(1, 0),
+        };
+        let mut buf = Vec::new();
+        tenc.encode(&mut buf).unwrap();
+        assert_eq!(buf[8], 1);
+    }
+
+    #[test]
+    fn key_rotation() {
+        let tenc = Tenc {
+            version: TencBoxVersion::V1,
+            default: info(1, 8),
+        };
+
+        let entry = |info: EncryptionInfo| SgpdEntry {
+            description_length: None,
+            entry: AnySampleGroupEntry::Encryption(info),
+        };
+
+        let stbl = Stbl {
+            sgpd: vec![Sgpd {
+                grouping_type: FourCC::new(b"seig"),
+                default_length: Some(0),
+                default_group_description_index: None,
+                static_group_description: false,
+                static_mapping: false,
+                essential: false,
+                entries: vec![entry(info(2, 0))],
+            }],
+            ..Default::default()
+        };
+
+        let traf = Traf {
+            trun: vec![Trun {
+                data_offset: None,
+                entries: vec![TrunEntry::default(); 3],
+            }],
+            sbgp: vec![Sbgp {
+                grouping_type: FourCC::new(b"seig"),
+                grouping_type_parameter: None,
+                entries: vec![
+                    SbgpEntry {
+                        sample_count: 1,
+                        group_description_index: 0,
+                    },
+                    SbgpEntry {
+                        sample_count: 1,
+                        group_description_index: FRAGMENT_LOCAL_GROUP_INDEX + 1,
+                    },
+                    SbgpEntry {
+                        sample_count: 1,
+                        group_description_index: 1,
+                    },
+                ],
+            }],
+            sgpd: vec![Sgpd {
+                entries: vec![entry(info(3, 16))],
+                ..stbl.sgpd[0].clone()
+            }],
+            ..Default::default()
+        };
+
+        // The sgpd atoms survive a round trip, including the constant IV.
+        for sgpd in stbl.sgpd.iter().chain(&traf.sgpd) {
+            let mut buf = Vec::new();
+            sgpd.encode(&mut buf).unwrap();
+
+            let decoded = Sgpd::decode(&mut buf.as_slice()).unwrap();
+            assert_eq!(decoded.entries[0].entry, sgpd.entries[0].entry);
+        }
+
+        let samples = traf.sample_encryption(&stbl, &tenc).unwrap();
+        let kids: Vec<u8> = samples.iter().map(|info| info.kid[0]).collect();
+        assert_eq!(kids, [1, 3, 2]);
+        assert_eq!(samples[2].constant_iv, Some(vec![0xaa; 16]));
+    }
+}
@@ -17,6 +17,9 @@ pub struct Udta {
     pub kind: Option<Kind>,
     pub meta: Option<Meta>,
     pub rtng: Option<Rtng>,
+    /// User-defined atoms registered with [Custom::register].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom: Vec<Custom>,
 }
 
 impl Atom for Udta {
@@ -26,6 +29,7 @@ impl Atom for Udta {
         required: [ ],
         optional: [ Cprt, Meta, Kind, Rtng ],
         multiple: [ ],
+        custom: custom,
     }
 }
 
@@ -40,6 +44,7 @@ mod tests {
             meta: None,
             kind: None,
             rtng: None,
+            custom: vec![],
         };
 
         let mut buf = Vec::new();
@@ -74,6 +79,7 @@ mod tests {
                 language: "eng".into(),
                 rating_info: "test info".into(),
             }),
+            custom: vec![],
         };
 
         let mut buf = Vec::new();
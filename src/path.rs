@@ -0,0 +1,92 @@
+use std::fmt;
+
+use crate::*;
+
+/// A single step in an [AtomPath].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AtomPathSegment {
+    pub kind: FourCC,
+
+    /// The zero-based position among siblings of the same kind, when the parent allows several.
+    pub index: Option<usize>,
+}
+
+impl fmt::Display for AtomPathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "{}[{}]", self.kind, index),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+/// The chain of atoms leading to an atom, ex. `moov/trak[1]/mdia/minf/stbl`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct AtomPath(pub Vec<AtomPathSegment>);
+
+impl AtomPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a new path with the child appended.
+    pub fn join(&self, kind: FourCC, index: Option<usize>) -> Self {
+        let mut path = self.clone();
+        path.push(kind, index);
+        path
+    }
+
+    pub fn push(&mut self, kind: FourCC, index: Option<usize>) {
+        self.0.push(AtomPathSegment { kind, index });
+    }
+
+    pub fn pop(&mut self) -> Option<AtomPathSegment> {
+        self.0.pop()
+    }
+
+    /// The kind of the innermost atom.
+    pub fn kind(&self) -> Option<FourCC> {
+        self.0.last().map(|segment| segment.kind)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AtomPathSegment> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for AtomPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{segment}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let path = AtomPath::new()
+            .join(Moov::KIND, None)
+            .join(Trak::KIND, Some(1))
+            .join(Mdia::KIND, None);
+
+        assert_eq!(path.to_string(), "moov/trak[1]/mdia");
+        assert_eq!(path.kind(), Some(Mdia::KIND));
+    }
+}
@@ -0,0 +1,585 @@
+use crate::*;
+
+/// An event produced by a [PushParser].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushEvent {
+    /// The header of a top-level atom, and the offset of the header in the stream.
+    Start { header: Header, offset: u64 },
+
+    /// A fully buffered and decoded atom, between its [PushEvent::Start] and [PushEvent::End].
+    Atom(Box<Any>),
+
+    /// A chunk of the body of a streamed atom, and the offset of the chunk in the stream.
+    Data { offset: u64, data: Vec<u8> },
+
+    /// The end of a top-level atom, and the offset just past it.
+    End { kind: FourCC, offset: u64 },
+}
+
+enum State {
+    // Waiting for the next header.
+    Header,
+
+    // Buffering the body, which is decoded once complete.
+    Buffered {
+        header: Header,
+        header_size: usize,
+    },
+
+    // Passing through the body as it arrives, with the remaining size if known.
+    Streamed {
+        kind: FourCC,
+        remaining: Option<u64>,
+    },
+
+    // The body is done; the end event is pending.
+    End {
+        kind: FourCC,
+    },
+
+    // Discarding the body of an atom that's too large to buffer.
+    Skipped {
+        remaining: u64,
+    },
+
+    // The stream can't be parsed any further, so everything else is discarded.
+    Failed,
+}
+
+/// An incremental parser for a stream of top-level atoms, fed with arbitrary chunks of bytes.
+///
+/// Call [PushParser::push] when bytes arrive, then [PushParser::next_event] until it returns `None`.
+/// Most atoms are buffered and decoded once complete, but streamed atoms (`mdat` by default) are passed through as [PushEvent::Data] chunks as soon as they arrive.
+/// An atom larger than [PushParser::max_buffered] returns [Error::TooLarge] instead of being buffered.
+///
+/// ```
+/// # use mp4_atom::*;
+/// # fn main() -> Result<()> {
+/// let mut parser = PushParser::new();
+/// parser.push(&[0, 0, 0, 12, b'm', b'd', b'a', b't', 1, 2]);
+///
+/// while let Some(event) = parser.next_event()? {
+///     println!("{event:?}");
+/// }
+///
+/// // Any more of the mdat is passed through as soon as it arrives.
+/// assert_eq!(parser.needed(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct PushParser {
+    buf: Vec<u8>,
+
+    // The number of bytes at the start of the buffer that have been consumed.
+    pos: usize,
+
+    // The offset of the first byte in the buffer.
+    offset: u64,
+
+    state: State,
+    streamed: Vec<FourCC>,
+    max_buffered: usize,
+    finished: bool,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushParser {
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            pos: 0,
+            offset: 0,
+            state: State::Header,
+            streamed: vec![Mdat::KIND],
+            max_buffered: Self::MAX_BUFFERED,
+            finished: false,
+        }
+    }
+
+    /// The default maximum size of an atom that is buffered, including the header.
+    pub const MAX_BUFFERED: usize = 64 * 1024 * 1024;
+
+    /// Set the maximum size of an atom that is buffered, including the header.
+    ///
+    /// A larger atom with a known size is skipped after returning [Error::TooLarge].
+    /// One that extends to the end of the stream can't be skipped, so the parser stops.
+    pub fn max_buffered(&mut self, size: usize) {
+        self.max_buffered = size;
+    }
+
+    /// Pass the body of this kind of atom through as [PushEvent::Data] instead of buffering it.
+    pub fn stream(&mut self, kind: FourCC) {
+        if !self.streamed.contains(&kind) {
+            self.streamed.push(kind);
+        }
+    }
+
+    /// Add the next chunk of the stream.
+    pub fn push(&mut self, data: &[u8]) {
+        if let State::Failed = self.state {
+            return;
+        }
+
+        // Compact once at least half the buffer is consumed, so each byte is only moved a few times.
+        if self.pos > 0 && self.pos >= self.buf.len() / 2 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Signal the end of the stream, so an atom without a size can be completed.
+    ///
+    /// [PushParser::next_event] returns [Error::UnexpectedEof] if the stream ends inside an atom.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// The offset in the stream of the next byte that hasn't been consumed.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The minimum number of bytes to push before the next event, or zero if one is ready.
+    pub fn needed(&self) -> usize {
+        let buffered = self.pending().len();
+
+        match &self.state {
+            State::Header => match self.pending().get(..4) {
+                // A size of 1 means the size follows the kind.
+                Some([0, 0, 0, 1]) => 16usize.saturating_sub(buffered),
+                _ => 8usize.saturating_sub(buffered),
+            },
+            State::Buffered {
+                header,
+                header_size,
+            } => match header.size {
+                Some(size) => (header_size + size).saturating_sub(buffered),
+                None if self.finished => 0,
+                None => 1,
+            },
+            State::Streamed { remaining, .. } => match remaining {
+                Some(0) => 0,
+                _ if buffered > 0 || self.finished => 0,
+                _ => 1,
+            },
+            State::End { .. } => 0,
+            State::Skipped { remaining } => match buffered {
+                0 if *remaining > 0 => 1,
+                _ => 0,
+            },
+            State::Failed => 0,
+        }
+    }
+
+    /// Return the next event, or `None` if more bytes are needed.
+    ///
+    /// An atom that fails to decode is skipped, so the parser can continue with the next one.
+    /// A header that fails to decode stops the parser, as the next atom can't be found; every event after the error is `None`.
+    pub fn next_event(&mut self) -> Result<Option<PushEvent>> {
+        match self.state {
+            State::Header => self.header(),
+            State::Buffered {
+                header,
+                header_size,
+            } => self.buffered(header, header_size),
+            State::Streamed { kind, remaining } => self.streamed(kind, remaining),
+            State::End { kind } => {
+                self.state = State::Header;
+                Ok(Some(PushEvent::End {
+                    kind,
+                    offset: self.offset,
+                }))
+            }
+            State::Skipped { remaining } => self.skipped(remaining),
+            State::Failed => Ok(None),
+        }
+    }
+
+    fn header(&mut self) -> Result<Option<PushEvent>> {
+        let mut peek = self.pending();
+        let header = match Header::decode_maybe(&mut peek) {
+            Ok(Some(header)) => header,
+            Err(err) => return Err(self.fail(err)),
+            Ok(None) if self.finished && !self.pending().is_empty() => {
+                return Err(Error::UnexpectedEof)
+            }
+            Ok(None) => return Ok(None),
+        };
+
+        let header_size = self.pending().len() - peek.len();
+        let offset = self.offset;
+
+        let streamed = self.streamed.contains(&header.kind);
+        let too_large = |size: &usize| size.saturating_add(header_size) > self.max_buffered;
+        if let Some(size) = header.size.filter(|size| !streamed && too_large(size)) {
+            self.consume(header_size);
+            self.state = State::Skipped {
+                remaining: size as u64,
+            };
+            return Err(Error::TooLarge(header.kind));
+        }
+
+        self.state = match streamed {
+            true => {
+                self.consume(header_size);
+                State::Streamed {
+                    kind: header.kind,
+                    remaining: header.size.map(|size| size as u64),
+                }
+            }
+            // Keep the header so the whole atom can be decoded at once.
+            false => State::Buffered {
+                header,
+                header_size,
+            },
+        };
+
+        Ok(Some(PushEvent::Start { header, offset }))
+    }
+
+    fn buffered(&mut self, header: Header, header_size: usize) -> Result<Option<PushEvent>> {
+        let size = match header.size {
+            Some(size) => header_size + size,
+            None if self.pending().len() > self.max_buffered => {
+                return Err(self.fail(Error::TooLarge(header.kind)))
+            }
+            None if self.finished => self.pending().len(),
+            None => return Ok(None),
+        };
+
+        if self.pending().len() < size {
+            return match self.finished {
+                true => Err(Error::UnexpectedEof),
+                false => Ok(None),
+            };
+        }
+
+        let offset = self.offset;
+        let atom = Any::decode(&mut &self.pending()[..size]);
+
+        self.consume(size);
+        self.state = State::End { kind: header.kind };
+
+        match atom {
+            Ok(atom) => Ok(Some(PushEvent::Atom(Box::new(atom)))),
+            Err(err) => Err(err.offset_by(offset)),
+        }
+    }
+
+    fn streamed(&mut self, kind: FourCC, remaining: Option<u64>) -> Result<Option<PushEvent>> {
+        if remaining == Some(0)
+            || (remaining.is_none() && self.finished && self.pending().is_empty())
+        {
+            self.state = State::End { kind };
+            return self.next_event();
+        }
+
+        if self.pending().is_empty() {
+            return match self.finished {
+                true => Err(Error::UnexpectedEof),
+                false => Ok(None),
+            };
+        }
+
+        let size = match remaining {
+            Some(remaining) => self.pending().len().min(remaining as usize),
+            None => self.pending().len(),
+        };
+
+        let offset = self.offset;
+        let data = self.consume(size);
+
+        self.state = State::Streamed {
+            kind,
+            remaining: remaining.map(|remaining| remaining - size as u64),
+        };
+
+        Ok(Some(PushEvent::Data { offset, data }))
+    }
+
+    fn skipped(&mut self, remaining: u64) -> Result<Option<PushEvent>> {
+        let size = self.pending().len().min(remaining as usize);
+        self.offset += size as u64;
+        self.pos += size;
+
+        self.state = match remaining - size as u64 {
+            0 => State::Header,
+            remaining => State::Skipped { remaining },
+        };
+
+        match self.state {
+            State::Header => self.next_event(),
+            _ if self.finished => Err(Error::UnexpectedEof),
+            _ => Ok(None),
+        }
+    }
+
+    // Stop parsing, dropping anything buffered.
+    fn fail(&mut self, err: Error) -> Error {
+        self.state = State::Failed;
+        self.buf = Vec::new();
+        self.pos = 0;
+        err.offset_by(self.offset)
+    }
+
+    // The bytes that haven't been consumed yet.
+    fn pending(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    fn consume(&mut self, size: usize) -> Vec<u8> {
+        self.offset += size as u64;
+
+        let data = self.pending()[..size].to_vec();
+        self.pos += size;
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &[u8] = include_bytes!("test/uncompressed.mp4");
+
+    // Feed the file in chunks of the given size, collecting every event.
+    fn parse(chunk: usize) -> Vec<PushEvent> {
+        let mut parser = PushParser::new();
+        let mut events = Vec::new();
+
+        for data in FILE.chunks(chunk) {
+            parser.push(data);
+            while let Some(event) = parser.next_event().unwrap() {
+                events.push(event);
+            }
+        }
+
+        parser.finish();
+        while let Some(event) = parser.next_event().unwrap() {
+            events.push(event);
+        }
+
+        assert_eq!(parser.offset(), FILE.len() as u64);
+        events
+    }
+
+    #[test]
+    fn compact() {
+        let mut stream = Vec::new();
+        Mdat {
+            data: vec![0; 1 << 20],
+        }
+        .encode(&mut stream)
+        .unwrap();
+
+        let mut parser = PushParser::new();
+        let mut size = 0;
+
+        for data in stream.chunks(100) {
+            parser.push(data);
+            while let Some(event) = parser.next_event().unwrap() {
+                if let PushEvent::Data { data, .. } = event {
+                    size += data.len();
+                }
+            }
+
+            // The consumed bytes are dropped instead of accumulating.
+            assert!(parser.buf.len() <= 200);
+        }
+
+        assert_eq!(size, 1 << 20);
+    }
+
+    #[test]
+    fn chunks() {
+        let mut expected = Vec::new();
+        let mut buf = FILE;
+        while let Some(atom) = Any::decode_maybe(&mut buf).unwrap() {
+            expected.push(atom);
+        }
+
+        for chunk in [1, 7, 4096, FILE.len()] {
+            let events = parse(chunk);
+
+            let mut atoms = Vec::new();
+            let mut mdat = Vec::new();
+
+            for event in events {
+                match event {
+                    PushEvent::Atom(atom) => atoms.push(*atom),
+                    PushEvent::Data { offset, data } => {
+                        assert_eq!(&FILE[offset as usize..][..data.len()], data);
+                        mdat.extend(data);
+                    }
+                    PushEvent::Start { header, offset } if header.kind == Mdat::KIND => {
+                        atoms.push(Any::Mdat(Mdat { data: Vec::new() }));
+                        assert_eq!(offset, 848);
+                    }
+                    PushEvent::End {
+                        kind: Mdat::KIND, ..
+                    } => match atoms.last_mut() {
+                        Some(Any::Mdat(atom)) => atom.data = std::mem::take(&mut mdat),
+                        _ => unreachable!(),
+                    },
+                    _ => {}
+                }
+            }
+
+            assert_eq!(atoms, expected);
+        }
+    }
+
+    #[test]
+    fn order() {
+        let events = parse(FILE.len());
+        let kinds: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                PushEvent::Start { header, .. } => format!("start {}", header.kind),
+                PushEvent::Atom(atom) => format!("atom {}", atom.kind()),
+                PushEvent::Data { .. } => "data".to_string(),
+                PushEvent::End { kind, .. } => format!("end {kind}"),
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                "start ftyp",
+                "atom ftyp",
+                "end ftyp",
+                "start moov",
+                "atom moov",
+                "end moov",
+                "start mdat",
+                "data",
+                "end mdat",
+                "start free",
+                "atom free",
+                "end free",
+            ]
+        );
+    }
+
+    #[test]
+    fn needed() {
+        let mut parser = PushParser::new();
+        assert_eq!(parser.needed(), 8);
+
+        parser.push(&FILE[..4]);
+        assert_eq!(parser.needed(), 4);
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        parser.push(&FILE[4..8]);
+        assert_eq!(parser.needed(), 0);
+        assert!(matches!(
+            parser.next_event().unwrap(),
+            Some(PushEvent::Start { offset: 0, .. })
+        ));
+
+        // The rest of the 20 byte ftyp.
+        assert_eq!(parser.needed(), 12);
+    }
+
+    #[test]
+    fn open_ended() {
+        let mut parser = PushParser::new();
+        parser.push(&[0, 0, 0, 0, b'm', b'd', b'a', b't', 1, 2, 3]);
+
+        assert!(matches!(
+            parser.next_event().unwrap(),
+            Some(PushEvent::Start { .. })
+        ));
+        assert_eq!(
+            parser.next_event().unwrap(),
+            Some(PushEvent::Data {
+                offset: 8,
+                data: vec![1, 2, 3]
+            })
+        );
+        assert_eq!(parser.next_event().unwrap(), None);
+        assert_eq!(parser.needed(), 1);
+
+        parser.finish();
+        assert_eq!(
+            parser.next_event().unwrap(),
+            Some(PushEvent::End {
+                kind: Mdat::KIND,
+                offset: 11
+            })
+        );
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut parser = PushParser::new();
+        parser.push(&FILE[..100]);
+        parser.finish();
+
+        assert!(parser.next_event().unwrap().is_some());
+        assert!(parser.next_event().unwrap().is_some());
+        assert!(parser.next_event().unwrap().is_some());
+        assert!(matches!(
+            parser.next_event().unwrap(),
+            Some(PushEvent::Start { .. })
+        ));
+        assert!(matches!(parser.next_event(), Err(Error::UnexpectedEof)));
+    }
+
+    #[test]
+    fn too_large() {
+        let mut parser = PushParser::new();
+        parser.max_buffered(64);
+
+        // A moov claiming 4 GB is skipped instead of buffered.
+        let mut stream = vec![0xff, 0xff, 0xff, 0xff, b'm', b'o', b'o', b'v'];
+        stream.extend_from_slice(&[0; 4096]);
+        parser.push(&stream);
+
+        assert!(matches!(
+            parser.next_event(),
+            Err(Error::TooLarge(kind)) if kind == Moov::KIND
+        ));
+        assert_eq!(parser.next_event().unwrap(), None);
+        assert_eq!(parser.offset(), stream.len() as u64);
+        assert!(parser.buf.len() <= stream.len());
+
+        // The size is unknown, so the rest of the stream can't be skipped.
+        let mut parser = PushParser::new();
+        parser.max_buffered(64);
+        parser.push(&[0, 0, 0, 0, b'm', b'o', b'o', b'v']);
+        parser.push(&[0; 100]);
+
+        assert!(matches!(
+            parser.next_event(),
+            Ok(Some(PushEvent::Start { .. }))
+        ));
+        assert!(matches!(parser.next_event(), Err(Error::TooLarge(_))));
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        parser.push(&[0; 100]);
+        assert!(parser.buf.is_empty());
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut parser = PushParser::new();
+        parser.push(&[0, 0, 0, 4, b'f', b'r', b'e', b'e']);
+
+        // The next atom can't be found, so the error is only returned once.
+        assert!(matches!(parser.next_event(), Err(Error::InvalidSize)));
+        assert_eq!(parser.next_event().unwrap(), None);
+        assert_eq!(parser.needed(), 0);
+
+        parser.push(FILE);
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+}
@@ -0,0 +1,158 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use crate::*;
+
+/// A run of samples from one track that are contiguous in an input.
+pub(crate) struct Chunk {
+    pub input: usize,
+    pub track: usize,
+    pub samples: Range<usize>,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Group the samples of a track into chunks, given the index of the first sample in the output track.
+pub(crate) fn chunks(
+    samples: &[StblSample],
+    start: usize,
+    input: usize,
+    track: usize,
+) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut first = 0;
+
+    for index in 1..=samples.len() {
+        if index == samples.len() || samples[index].chunk != samples[first].chunk {
+            chunks.push(Chunk {
+                input,
+                track,
+                samples: start + first..start + index,
+                offset: samples[first].offset,
+                size: samples[first..index]
+                    .iter()
+                    .map(|sample| sample.size as u64)
+                    .sum(),
+            });
+            first = index;
+        }
+    }
+
+    chunks
+}
+
+/// Write a progressive file: the prefix, the moov, then an mdat for each list of chunks.
+///
+/// The samples of each track still point into the inputs; they're rebuilt to point into the output.
+pub(crate) fn write<R: Read + Seek, W: Write + ?Sized>(
+    inputs: &mut [R],
+    w: &mut W,
+    prefix: &[u8],
+    moov: &mut Moov,
+    tracks: &[Vec<StblSample>],
+    mdats: &[Vec<Chunk>],
+) -> Result<()> {
+    let mut headers = Vec::with_capacity(mdats.len());
+    for chunks in mdats {
+        let payload: u64 = chunks.iter().map(|chunk| chunk.size).sum();
+
+        let mut header = Vec::new();
+        Header::new(Mdat::KIND, Some(payload as usize)).encode(&mut header)?;
+        headers.push(header);
+    }
+
+    // The chunk offsets depend on the size of the moov, which depends on the chunk offsets.
+    let mut moov_size = 0;
+    for _ in 0..4 {
+        let mut offset = (prefix.len() + moov_size) as u64;
+        let mut relocated = tracks.to_vec();
+        let mut number = 0;
+
+        for (chunks, header) in mdats.iter().zip(&headers) {
+            offset += header.len() as u64;
+
+            for chunk in chunks {
+                for sample in &mut relocated[chunk.track][chunk.samples.clone()] {
+                    sample.offset = offset + (sample.offset - chunk.offset);
+                    sample.chunk = number;
+                }
+
+                number += 1;
+                offset += chunk.size;
+            }
+        }
+
+        for (trak, samples) in moov.trak.iter_mut().zip(&relocated) {
+            trak.mdia.minf.stbl.set_samples(samples);
+        }
+
+        let mut encoded = Vec::new();
+        moov.encode(&mut encoded)?;
+
+        if encoded.len() != moov_size {
+            moov_size = encoded.len();
+            continue;
+        }
+
+        w.write_all(prefix)?;
+        w.write_all(&encoded)?;
+
+        for (chunks, header) in mdats.iter().zip(&headers) {
+            w.write_all(header)?;
+
+            for chunk in chunks {
+                let r = &mut inputs[chunk.input];
+                r.seek(SeekFrom::Start(chunk.offset))?;
+                let n = std::io::copy(&mut r.take(chunk.size), w)?;
+                if n != chunk.size {
+                    return Err(Error::UnexpectedEof);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    Err(Error::Unsupported("the layout didn't converge"))
+}
+
+/// Read the `ftyp` and `moov` of a progressive file.
+pub(crate) fn read<R: Read + Seek + ?Sized>(r: &mut R) -> Result<(Option<Ftyp>, Moov)> {
+    let mut ftyp = None;
+    let mut moov = None;
+    let mut available = 0;
+
+    for span in scan(r)? {
+        match span.kind {
+            Ftyp::KIND => {
+                r.seek(SeekFrom::Start(span.offset))?;
+                ftyp = Some(<Ftyp as ReadFrom>::read_from(r)?);
+            }
+            Moov::KIND => {
+                r.seek(SeekFrom::Start(span.offset))?;
+                moov = Some(<Moov as ReadFrom>::read_from(r)?);
+            }
+            Mdat::KIND => available += span.end() - span.body,
+            _ => {}
+        }
+    }
+
+    let moov = moov.ok_or(Error::MissingBox(Moov::KIND))?;
+    if moov.mvex.is_some() {
+        return Err(Error::Unsupported("remuxing a fragmented file"));
+    }
+
+    // The sample counts aren't bounded by the tables, so make sure the samples fit before expanding them.
+    let size: u64 = moov
+        .trak
+        .iter()
+        .map(|trak| trak.mdia.minf.stbl.sample_data_size())
+        .sum();
+    if size > available {
+        return Err(Error::InvalidCombination(
+            "the samples are larger than the mdat",
+        ));
+    }
+
+    Ok((ftyp, moov))
+}
@@ -0,0 +1,127 @@
+use crate::*;
+
+/// The per-sample flags used by `trex`, `tfhd` and `trun`, packed into 32 bits.
+///
+/// The two bit fields use the same values as `sdtp`:
+/// - `is_leading`: 0 unknown, 1 leading with a dependency before the sync sample, 2 not leading, 3 leading without a dependency.
+/// - `sample_depends_on`: 0 unknown, 1 depends on others (not an I-picture), 2 doesn't depend on others (an I-picture).
+/// - `sample_is_depended_on`: 0 unknown, 1 others depend on it (not disposable), 2 nothing depends on it (disposable).
+/// - `sample_has_redundancy`: 0 unknown, 1 has redundant coding, 2 no redundant coding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SampleFlags {
+    pub is_leading: u8,
+    pub sample_depends_on: u8,
+    pub sample_is_depended_on: u8,
+    pub sample_has_redundancy: u8,
+
+    /// The number of padding bits at the end of the sample, up to 7.
+    pub padding: u8,
+    pub is_non_sync_sample: bool,
+    pub degradation_priority: u16,
+}
+
+impl SampleFlags {
+    /// A sync sample that doesn't depend on others, such as an IDR frame.
+    pub const KEYFRAME: Self = Self {
+        is_leading: 0,
+        sample_depends_on: 2,
+        sample_is_depended_on: 0,
+        sample_has_redundancy: 0,
+        padding: 0,
+        is_non_sync_sample: false,
+        degradation_priority: 0,
+    };
+
+    /// A non-sync sample that depends on others, such as a P or B frame.
+    pub const NON_KEYFRAME: Self = Self {
+        is_leading: 0,
+        sample_depends_on: 1,
+        sample_is_depended_on: 0,
+        sample_has_redundancy: 0,
+        padding: 0,
+        is_non_sync_sample: true,
+        degradation_priority: 0,
+    };
+
+    /// Returns [Self::KEYFRAME] or [Self::NON_KEYFRAME].
+    pub fn keyframe(keyframe: bool) -> Self {
+        match keyframe {
+            true => Self::KEYFRAME,
+            false => Self::NON_KEYFRAME,
+        }
+    }
+
+    /// True unless the sample is flagged as non-sync.
+    pub fn is_sync(&self) -> bool {
+        !self.is_non_sync_sample
+    }
+
+    /// True if nothing depends on the sample, so it can be dropped.
+    pub fn is_disposable(&self) -> bool {
+        self.sample_is_depended_on == 2
+    }
+}
+
+impl From<u32> for SampleFlags {
+    fn from(value: u32) -> Self {
+        Self {
+            is_leading: ((value >> 26) & 0b11) as u8,
+            sample_depends_on: ((value >> 24) & 0b11) as u8,
+            sample_is_depended_on: ((value >> 22) & 0b11) as u8,
+            sample_has_redundancy: ((value >> 20) & 0b11) as u8,
+            padding: ((value >> 17) & 0b111) as u8,
+            is_non_sync_sample: (value >> 16) & 1 == 1,
+            degradation_priority: value as u16,
+        }
+    }
+}
+
+impl From<SampleFlags> for u32 {
+    fn from(flags: SampleFlags) -> Self {
+        ((flags.is_leading as u32 & 0b11) << 26)
+            | ((flags.sample_depends_on as u32 & 0b11) << 24)
+            | ((flags.sample_is_depended_on as u32 & 0b11) << 22)
+            | ((flags.sample_has_redundancy as u32 & 0b11) << 20)
+            | ((flags.padding as u32 & 0b111) << 17)
+            | ((flags.is_non_sync_sample as u32) << 16)
+            | flags.degradation_priority as u32
+    }
+}
+
+impl Decode for SampleFlags {
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self> {
+        Ok(u32::decode(buf)?.into())
+    }
+}
+
+impl Encode for SampleFlags {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        u32::from(*self).encode(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits() {
+        assert_eq!(u32::from(SampleFlags::KEYFRAME), 0x0200_0000);
+        assert_eq!(u32::from(SampleFlags::NON_KEYFRAME), 0x0101_0000);
+        assert_eq!(SampleFlags::from(0x0101_0000), SampleFlags::NON_KEYFRAME);
+
+        let flags = SampleFlags::from(0x0ecb_1234);
+        assert_eq!(flags.is_leading, 3);
+        assert_eq!(flags.sample_depends_on, 2);
+        assert_eq!(flags.sample_is_depended_on, 3);
+        assert_eq!(flags.sample_has_redundancy, 0);
+        assert_eq!(flags.padding, 5);
+        assert!(flags.is_non_sync_sample);
+        assert_eq!(flags.degradation_priority, 0x1234);
+        assert_eq!(u32::from(flags), 0x0ecb_1234);
+
+        assert!(SampleFlags::keyframe(true).is_sync());
+        assert!(!SampleFlags::keyframe(false).is_sync());
+    }
+}
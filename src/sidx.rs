@@ -124,6 +124,311 @@ impl AtomExt for Sidx {
     }
 }
 
+/// A reference resolved to absolute byte and time ranges by [Sidx::resolve].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SidxRange {
+    /// The absolute offset of the referenced subsegment, or of the nested `sidx` if `reference_type` is set.
+    pub offset: u64,
+    pub size: u64,
+
+    /// The earliest presentation time of the reference, in the `sidx` timescale.
+    pub start: u64,
+    pub duration: u64,
+
+    pub reference: SegmentReference,
+}
+
+impl SidxRange {
+    /// The presentation time at which the reference ends.
+    pub fn end(&self) -> u64 {
+        self.start + self.duration
+    }
+}
+
+impl Sidx {
+    /// Resolve each reference to absolute byte and time ranges.
+    ///
+    /// The `sidx_end` is the offset of the first byte after this `sidx`, which its `first_offset` is relative to.
+    /// Use the decoded size rather than encoding it again, as the version and header size can differ.
+    /// Returns [Error::OutOfBounds] if an offset or time doesn't fit in a u64.
+    pub fn resolve(&self, sidx_end: u64) -> Result<Vec<SidxRange>> {
+        let mut offset = sidx_end
+            .checked_add(self.first_offset)
+            .ok_or(Error::OutOfBounds)?;
+        let mut start = self.earliest_presentation_time;
+
+        let mut ranges = Vec::with_capacity(self.references.len());
+
+        for reference in &self.references {
+            let range = SidxRange {
+                offset,
+                size: reference.reference_size as u64,
+                start,
+                duration: reference.subsegment_duration as u64,
+                reference: reference.clone(),
+            };
+
+            offset = offset.checked_add(range.size).ok_or(Error::OutOfBounds)?;
+            start = start
+                .checked_add(range.duration)
+                .ok_or(Error::OutOfBounds)?;
+            ranges.push(range);
+        }
+
+        Ok(ranges)
+    }
+}
+
+// A fragment added to a SidxBuilder, before the durations are known.
+#[derive(Debug, Clone)]
+struct SidxFragment {
+    earliest_presentation_time: u64,
+    duration: u64,
+    size: u64,
+    starts_with_sap: bool,
+    sap_type: u8,
+    sap_delta_time: u32,
+}
+
+/// Build a `sidx` from a sequence of fragments, each a `moof` and its `mdat`.
+///
+/// Each fragment becomes a subsegment, timed by the samples of the reference track.
+/// The presentation times are the composition times, without applying the edit list.
+///
+/// ```
+/// # use mp4_atom::*;
+/// # fn main() -> Result<()> {
+/// let moof = Moof {
+///     traf: vec![Traf {
+///         tfhd: Tfhd { track_id: 1, ..Default::default() },
+///         tfdt: Some(Tfdt { base_media_decode_time: 0 }),
+///         trun: vec![Trun {
+///             data_offset: None,
+///             entries: vec![TrunEntry { duration: Some(1000), ..Default::default() }],
+///         }],
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+///
+/// let mut builder = SidxBuilder::new(1, 1000);
+/// builder.push(&moof, 5000)?;
+///
+/// let sidx = builder.build()?;
+/// assert_eq!(sidx.references[0].reference_size, 5000);
+/// assert_eq!(sidx.references[0].subsegment_duration, 1000);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SidxBuilder {
+    reference_id: u32,
+    timescale: u32,
+    trex: Option<Trex>,
+    fragments: Vec<SidxFragment>,
+}
+
+impl SidxBuilder {
+    /// Index the track with this ID, using its media timescale.
+    pub fn new(reference_id: u32, timescale: u32) -> Self {
+        Self {
+            reference_id,
+            timescale,
+            trex: None,
+            fragments: Vec::new(),
+        }
+    }
+
+    /// Use the sample defaults from the `mvex` for samples that don't override them.
+    pub fn trex(&mut self, trex: &[Trex]) {
+        self.trex = trex
+            .iter()
+            .find(|trex| trex.track_id == self.reference_id)
+            .cloned();
+    }
+
+    /// Add the next fragment, with the size of everything up to the next fragment, including the `moof` and `mdat`.
+    pub fn push(&mut self, moof: &Moof, size: u64) -> Result<()> {
+        let traf = moof
+            .traf
+            .iter()
+            .find(|traf| traf.tfhd.track_id == self.reference_id)
+            .ok_or(Error::InvalidCombination(
+                "moof doesn't contain the reference track",
+            ))?;
+        let tfhd = &traf.tfhd;
+        let tfdt = traf.tfdt.as_ref().ok_or(Error::MissingBox(Tfdt::KIND))?;
+        let trex = self.trex.as_ref();
+
+        let default_duration = tfhd
+            .default_sample_duration
+            .or(trex.map(|trex| trex.default_sample_duration))
+            .unwrap_or(0);
+        let default_flags = tfhd
+            .default_sample_flags
+            .or(trex.map(|trex| trex.default_sample_flags))
+            .unwrap_or_default();
+
+        // The presentation time and sync flag of each sample, in decode order.
+        let mut samples = Vec::new();
+        let mut decode_time = tfdt.base_media_decode_time;
+
+        for entry in traf.trun.iter().flat_map(|trun| &trun.entries) {
+            let time = decode_time as i64 + entry.cts.unwrap_or(0) as i64;
+            let sync = entry.flags.unwrap_or(default_flags).is_sync();
+            samples.push((time.max(0) as u64, sync));
+            decode_time += entry.duration.unwrap_or(default_duration) as u64;
+        }
+
+        let earliest_presentation_time =
+            samples
+                .iter()
+                .map(|(time, _)| *time)
+                .min()
+                .ok_or(Error::InvalidCombination(
+                    "fragment has no samples for the reference track",
+                ))?;
+
+        let mut fragment = SidxFragment {
+            earliest_presentation_time,
+            duration: decode_time - tfdt.base_media_decode_time,
+            size,
+            starts_with_sap: false,
+            sap_type: 0,
+            sap_delta_time: 0,
+        };
+
+        if let Some(index) = samples.iter().position(|(_, sync)| *sync) {
+            let time = samples[index].0;
+
+            // Samples in the same GOP that are presented before the SAP are leading, as in an open GOP.
+            // Only the GOP up to the next sync sample is checked, so later GOPs don't affect the type.
+            let leading = samples[index + 1..]
+                .iter()
+                .take_while(|(_, sync)| !sync)
+                .any(|(other, _)| *other < time);
+
+            fragment.starts_with_sap = index == 0;
+            fragment.sap_type = if leading { 3 } else { 1 };
+
+            // The delta time is limited to 28 bits.
+            fragment.sap_delta_time = u32::try_from(time - earliest_presentation_time)
+                .ok()
+                .filter(|delta| *delta <= 0x0FFF_FFFF)
+                .ok_or(Error::TooLarge(Sidx::KIND))?;
+        }
+
+        self.fragments.push(fragment);
+
+        Ok(())
+    }
+
+    /// Build a single `sidx` that references every fragment, which are expected to immediately follow it.
+    pub fn build(&self) -> Result<Sidx> {
+        let references = self.references()?;
+        Ok(self.sidx(0, references))
+    }
+
+    /// Build a hierarchy: a top-level `sidx` that references a nested `sidx` for every group of fragments.
+    ///
+    /// The first `sidx` is the top-level one, followed by the nested ones in order.
+    /// Each nested `sidx` is expected to be written immediately before the fragments it references.
+    pub fn build_hierarchy(&self, fragments_per_sidx: usize) -> Result<Vec<Sidx>> {
+        let references = self.references()?;
+        let count = fragments_per_sidx.max(1);
+
+        let mut nested = Vec::new();
+        let mut top = Vec::new();
+
+        for (index, group) in references.chunks(count).enumerate() {
+            let sidx = self.sidx(index * count, group.to_vec());
+
+            let mut buf = Vec::new();
+            sidx.encode(&mut buf)?;
+
+            let size = group
+                .iter()
+                .map(|reference| reference.reference_size as u64)
+                .sum::<u64>()
+                + buf.len() as u64;
+            let duration: u64 = group
+                .iter()
+                .map(|reference| reference.subsegment_duration as u64)
+                .sum();
+
+            top.push(SegmentReference {
+                reference_type: true,
+                reference_size: reference_size(size)?,
+                subsegment_duration: duration
+                    .try_into()
+                    .map_err(|_| Error::TooLarge(Sidx::KIND))?,
+                starts_with_sap: group[0].starts_with_sap,
+                sap_type: group[0].sap_type,
+                sap_delta_time: group[0].sap_delta_time,
+            });
+
+            nested.push(sidx);
+        }
+
+        let mut sidxs = vec![self.sidx(0, top)];
+        sidxs.extend(nested);
+
+        Ok(sidxs)
+    }
+
+    // A reference for each fragment, in order.
+    fn references(&self) -> Result<Vec<SegmentReference>> {
+        self.fragments
+            .iter()
+            .enumerate()
+            .map(|(index, fragment)| {
+                // The duration lasts until the next subsegment starts, or until the last sample ends.
+                let duration = match self.fragments.get(index + 1) {
+                    Some(next) => next
+                        .earliest_presentation_time
+                        .saturating_sub(fragment.earliest_presentation_time),
+                    None => fragment.duration,
+                };
+
+                Ok(SegmentReference {
+                    reference_type: false,
+                    reference_size: reference_size(fragment.size)?,
+                    subsegment_duration: duration
+                        .try_into()
+                        .map_err(|_| Error::TooLarge(Sidx::KIND))?,
+                    starts_with_sap: fragment.starts_with_sap,
+                    sap_type: fragment.sap_type,
+                    sap_delta_time: fragment.sap_delta_time,
+                })
+            })
+            .collect()
+    }
+
+    // A sidx for the references, starting at the given fragment.
+    fn sidx(&self, first: usize, references: Vec<SegmentReference>) -> Sidx {
+        Sidx {
+            reference_id: self.reference_id,
+            timescale: self.timescale,
+            earliest_presentation_time: self
+                .fragments
+                .get(first)
+                .map(|fragment| fragment.earliest_presentation_time)
+                .unwrap_or_default(),
+            first_offset: 0,
+            references,
+        }
+    }
+}
+
+// The reference size is limited to 31 bits.
+fn reference_size(size: u64) -> Result<u32> {
+    match u32::try_from(size) {
+        Ok(size) if size <= 0x7FFF_FFFF => Ok(size),
+        _ => Err(Error::TooLarge(Sidx::KIND)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,4 +507,176 @@ mod tests {
 
         assert_eq!(buf.as_slice(), ENCODED_SIDX);
     }
+
+    fn fragment(decode_time: u64, cts: &[i32]) -> Moof {
+        let entries = cts
+            .iter()
+            .enumerate()
+            .map(|(index, &cts)| TrunEntry {
+                duration: None,
+                size: Some(100),
+                flags: Some(SampleFlags::keyframe(index == 0)),
+                cts: Some(cts),
+            })
+            .collect();
+
+        Moof {
+            mfhd: Mfhd { sequence_number: 1 },
+            traf: vec![Traf {
+                tfhd: Tfhd {
+                    track_id: 1,
+                    ..Default::default()
+                },
+                tfdt: Some(Tfdt {
+                    base_media_decode_time: decode_time,
+                }),
+                trun: vec![Trun {
+                    data_offset: None,
+                    entries,
+                }],
+                ..Default::default()
+            }],
+            custom: vec![],
+        }
+    }
+
+    #[test]
+    fn build_and_resolve() {
+        let mut builder = SidxBuilder::new(1, 1000);
+        builder.trex(&[Trex {
+            track_id: 1,
+            default_sample_duration: 10,
+            ..Default::default()
+        }]);
+
+        // A closed GOP with B-frames, then an open GOP with a leading sample.
+        builder.push(&fragment(0, &[10, 30, 0, 10]), 1000).unwrap();
+        builder.push(&fragment(40, &[20, 0, 10]), 2000).unwrap();
+
+        let sidx = builder.build().unwrap();
+        assert_eq!(sidx.earliest_presentation_time, 10);
+        assert_eq!(
+            sidx.references,
+            [
+                SegmentReference {
+                    reference_type: false,
+                    reference_size: 1000,
+                    subsegment_duration: 40,
+                    starts_with_sap: true,
+                    sap_type: 1,
+                    sap_delta_time: 0,
+                },
+                SegmentReference {
+                    reference_type: false,
+                    reference_size: 2000,
+                    subsegment_duration: 30,
+                    starts_with_sap: true,
+                    sap_type: 3,
+                    sap_delta_time: 10,
+                },
+            ]
+        );
+
+        let ranges = sidx.resolve(100).unwrap();
+        assert_eq!(ranges[0].offset, 100);
+        assert_eq!(ranges[1].offset, ranges[0].offset + 1000);
+        assert_eq!(ranges[1].start, 50);
+        assert_eq!(ranges[1].end(), 80);
+
+        // The offsets and times come from the file, so they can overflow.
+        assert!(matches!(
+            sidx.resolve(u64::MAX - 1500),
+            Err(Error::OutOfBounds)
+        ));
+
+        let mut late = sidx.clone();
+        late.earliest_presentation_time = u64::MAX - 50;
+        assert!(matches!(late.resolve(100), Err(Error::OutOfBounds)));
+    }
+
+    #[test]
+    fn resolve_v1() {
+        let sidx = Sidx {
+            reference_id: 1,
+            timescale: 1000,
+            earliest_presentation_time: 0,
+            first_offset: 10,
+            references: vec![SegmentReference {
+                reference_type: false,
+                reference_size: 100,
+                subsegment_duration: 1000,
+                starts_with_sap: true,
+                sap_type: 1,
+                sap_delta_time: 0,
+            }],
+        };
+
+        // A V1 sidx with 64-bit fields, even though they fit in 32 bits.
+        let mut buf = Vec::new();
+        sidx.encode(&mut buf).unwrap();
+        buf[3] += 8;
+        buf[8] = 1;
+        buf.splice(20..28, [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 10]);
+
+        let decoded = Sidx::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded, sidx);
+
+        let ranges = decoded.resolve(buf.len() as u64).unwrap();
+        assert_eq!(ranges[0].offset, 32 + 8 + 12 + 10);
+    }
+
+    #[test]
+    fn sap_type() {
+        let mut builder = SidxBuilder::new(1, 1000);
+        builder.trex(&[Trex {
+            track_id: 1,
+            default_sample_duration: 10,
+            ..Default::default()
+        }]);
+
+        // A closed GOP, then a second GOP with a sample presented before the first SAP.
+        let mut moof = fragment(0, &[10, 10, 20, -25]);
+        moof.traf[0].trun[0].entries[2].flags = Some(SampleFlags::keyframe(true));
+        builder.push(&moof, 100).unwrap();
+
+        let sidx = builder.build().unwrap();
+        assert_eq!(sidx.references[0].sap_type, 1);
+        assert_eq!(sidx.references[0].sap_delta_time, 5);
+
+        // The delta time is limited to 28 bits.
+        let mut builder = SidxBuilder::new(1, 1000);
+        let mut moof = fragment(0, &[0, 0x1000_0000]);
+        moof.traf[0].trun[0].entries[0].flags = Some(SampleFlags::keyframe(false));
+        moof.traf[0].trun[0].entries[1].flags = Some(SampleFlags::keyframe(true));
+        assert!(matches!(builder.push(&moof, 100), Err(Error::TooLarge(_))));
+    }
+
+    #[test]
+    fn build_hierarchy() {
+        let mut builder = SidxBuilder::new(1, 1000);
+        for index in 0..3 {
+            let mut moof = fragment(index * 20, &[0, 0]);
+            moof.traf[0].tfhd.default_sample_duration = Some(10);
+            builder.push(&moof, 100).unwrap();
+        }
+
+        let sidxs = builder.build_hierarchy(2).unwrap();
+        assert_eq!(sidxs.len(), 3);
+
+        let top = &sidxs[0];
+        assert!(top
+            .references
+            .iter()
+            .all(|reference| reference.reference_type));
+        assert_eq!(top.references[0].reference_size, 32 + 2 * 12 + 200);
+        assert_eq!(top.references[0].subsegment_duration, 40);
+        assert_eq!(top.references[1].reference_size, 32 + 12 + 100);
+        assert_eq!(sidxs[2].earliest_presentation_time, 40);
+
+        // The nested sidx are found at the resolved offsets, after the top-level sidx.
+        let ranges = top.resolve(32 + 2 * 12).unwrap();
+        assert_eq!(ranges[0].offset, 32 + 2 * 12);
+        assert_eq!(ranges[1].offset, ranges[0].offset + 256);
+        assert_eq!(ranges[1].start, sidxs[2].earliest_presentation_time);
+    }
 }
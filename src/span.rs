@@ -0,0 +1,307 @@
+use crate::*;
+
+/// The position of a decoded atom in the input, along with the atoms within it.
+///
+/// The span tree mirrors the bytes rather than the decoded structs, so every atom is included in the order it appears, even if the struct reorders or drops it.
+/// Use [AtomSpan::get] to find the span for an [AtomPath], such as one reported by a [Visitor].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AtomSpan {
+    pub kind: FourCC,
+
+    /// The absolute offset of the header.
+    pub offset: u64,
+
+    /// The size of the header, including the 64-bit size and the `uuid` user type when present.
+    pub header_size: usize,
+
+    /// The size of the body, after the header.
+    pub body_size: u64,
+
+    /// The atoms within the body, if it's a known container.
+    pub children: Vec<AtomSpan>,
+}
+
+impl AtomSpan {
+    /// Find the spans of every atom in the buffer, recursing into known containers.
+    ///
+    /// The offset is the position of the buffer in the file, which is added to every span.
+    pub fn scan(buf: &[u8], offset: u64) -> Result<Vec<AtomSpan>> {
+        let mut spans = Vec::new();
+        Self::scan_into(buf, offset, &mut spans)?;
+        Ok(spans)
+    }
+
+    // Append the spans found in the buffer, keeping those before any error.
+    fn scan_into(buf: &[u8], offset: u64, spans: &mut Vec<AtomSpan>) -> Result<()> {
+        let mut pos = 0;
+
+        // Anything smaller than a header is padding, such as the zero terminator of a udta.
+        while buf.len() - pos >= 8 {
+            let span = Self::parse(&buf[pos..], offset + pos as u64)?;
+            pos += span.size() as usize;
+            spans.push(span);
+        }
+
+        Ok(())
+    }
+
+    // Parse a single atom at the start of the buffer, which must contain all of it.
+    fn parse(buf: &[u8], offset: u64) -> Result<AtomSpan> {
+        let mut cursor = buf;
+        let header = Header::decode_maybe(&mut cursor)?.ok_or(Error::OutOfBounds)?;
+
+        let mut header_size = buf.len() - cursor.len();
+        let mut body_size = header.size.unwrap_or(cursor.len());
+        if body_size > cursor.len() {
+            return Err(Error::OutOfBounds);
+        }
+
+        // The user type is part of the header, although the decoder treats it as the body.
+        if header.kind == b"uuid".into() {
+            if body_size < 16 {
+                return Err(Error::InvalidSize);
+            }
+
+            header_size += 16;
+            body_size -= 16;
+        }
+
+        let body = &buf[header_size..header_size + body_size];
+        let body_offset = offset + header_size as u64;
+
+        // Anything that doesn't parse cleanly is treated as opaque data, after the children before it.
+        let mut children = Vec::new();
+        if let Some(skip) = children_offset(header.kind, body).filter(|&skip| skip <= body.len()) {
+            Self::scan_into(&body[skip..], body_offset + skip as u64, &mut children).ok();
+        }
+
+        Ok(AtomSpan {
+            kind: header.kind,
+            offset,
+            header_size,
+            body_size: body_size as u64,
+            children,
+        })
+    }
+
+    /// The absolute offset of the body.
+    pub fn body(&self) -> u64 {
+        self.offset + self.header_size as u64
+    }
+
+    /// The size of the atom, including the header.
+    pub fn size(&self) -> u64 {
+        self.header_size as u64 + self.body_size
+    }
+
+    /// The offset just past the atom.
+    pub fn end(&self) -> u64 {
+        self.offset + self.size()
+    }
+
+    /// Find the span of this atom or a descendant, given a path that starts with this atom, as reported when visiting it.
+    ///
+    /// The index of the first segment is ignored, as a span doesn't know its position among its siblings.
+    /// Any other segment without an index matches the first sibling of that kind.
+    pub fn get(&self, path: &AtomPath) -> Option<&AtomSpan> {
+        let (root, rest) = path.0.split_first()?;
+        if root.kind != self.kind {
+            return None;
+        }
+
+        match rest.is_empty() {
+            true => Some(self),
+            false => find(&self.children, rest),
+        }
+    }
+}
+
+/// Find the span for a path starting at the top level, such as `moov/trak[1]/mdia`.
+pub fn find_span<'a>(spans: &'a [AtomSpan], path: &AtomPath) -> Option<&'a AtomSpan> {
+    find(spans, path.0.as_slice())
+}
+
+fn find<'a>(spans: &'a [AtomSpan], path: &[AtomPathSegment]) -> Option<&'a AtomSpan> {
+    let (segment, rest) = path.split_first()?;
+
+    let span = spans
+        .iter()
+        .filter(|span| span.kind == segment.kind)
+        .nth(segment.index.unwrap_or(0))?;
+
+    match rest.is_empty() {
+        true => Some(span),
+        false => find(&span.children, rest),
+    }
+}
+
+/// Decode every top-level atom in the buffer, along with the span of each atom within it.
+pub fn decode_with_spans(buf: &[u8]) -> Result<Vec<(Any, AtomSpan)>> {
+    let spans = AtomSpan::scan(buf, 0)?;
+    let mut atoms = Vec::with_capacity(spans.len());
+
+    for span in spans {
+        let mut body = &buf[span.offset as usize..span.end() as usize];
+        let atom = Any::decode(&mut body).map_err(|err| err.offset_by(span.offset))?;
+        atoms.push((atom, span));
+    }
+
+    Ok(atoms)
+}
+
+/// The number of bytes between the start of a body and its first child, or `None` if the atom isn't a known container.
+///
+/// Only the first 16 bytes of the body are needed, to check the version of an audio sample entry.
+pub fn children_offset(kind: FourCC, body: &[u8]) -> Option<usize> {
+    match kind.as_ref() {
+        b"moov" | b"trak" | b"mdia" | b"minf" | b"stbl" | b"dinf" | b"edts" | b"mvex" | b"moof"
+        | b"traf" | b"mfra" | b"udta" | b"ilst" | b"iprp" | b"ipco" | b"sinf" | b"schi"
+        | b"wave" | b"tref" => Some(0),
+
+        // QuickTime uses a regular box, ISO uses a full box.
+        b"meta" if body.get(4..8) == Some(b"hdlr") => Some(0),
+        b"meta" => Some(4),
+
+        // Full box followed by an entry count.
+        b"stsd" | b"dref" => Some(8),
+
+        // Full box followed by a 16-bit entry count in version 0, or 32-bit otherwise.
+        b"iinf" => match body.first() {
+            Some(0) => Some(6),
+            _ => Some(8),
+        },
+
+        // Visual sample entries.
+        b"avc1" | b"avc3" | b"hvc1" | b"hev1" | b"vp08" | b"vp09" | b"av01" | b"mp4v" | b"encv"
+        | b"uncv" => Some(78),
+
+        // Audio sample entries, where QuickTime versions 1 and 2 add more fields.
+        b"mp4a" | b"enca" | b"Opus" | b"fLaC" | b"ac-3" | b"ec-3" | b"ipcm" | b"fpcm" | b"sowt"
+        | b"twos" | b"lpcm" | b"in24" | b"in32" | b"fl32" | b"fl64" | b"s16l" | b"samr" => {
+            match body.get(8..10) {
+                Some([0, 1]) => Some(28 + 16),
+                Some([0, 2]) => Some(28 + 36),
+                _ => Some(28),
+            }
+        }
+
+        // Text sample entries.
+        b"tx3g" => Some(38),
+        b"wvtt" => Some(8),
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree() {
+        let buf = include_bytes!("test/bbb.mp4");
+        let atoms = decode_with_spans(buf).unwrap();
+
+        let (ftyp, span) = &atoms[0];
+        assert_eq!(ftyp.kind(), Ftyp::KIND);
+        assert_eq!(span.offset, 0);
+        assert_eq!(span.header_size, 8);
+
+        let (moov, span) = &atoms[1];
+        assert_eq!(span.offset, atoms[0].1.end());
+        assert_eq!(span.children[0].kind, Mvhd::KIND);
+        assert_eq!(span.children[0].offset, span.body());
+
+        // Every span maps back to the exact bytes of the atom.
+        let mut count = 0;
+        moov.visit(&mut |path: &AtomPath, atom: AtomRef<'_>| {
+            let child = span.get(path).unwrap();
+
+            let bytes = &buf[child.offset as usize..child.end() as usize];
+            assert_eq!(&bytes[4..8], atom.kind().as_ref());
+            count += 1;
+        });
+
+        let path = [
+            Trak::KIND,
+            Mdia::KIND,
+            Minf::KIND,
+            Stbl::KIND,
+            Stsd::KIND,
+            Avc1::KIND,
+            Avcc::KIND,
+        ]
+        .into_iter()
+        .fold(AtomPath::new().join(Moov::KIND, None), |path, kind| {
+            path.join(kind, None)
+        });
+        let avcc = span.get(&path).unwrap();
+        let decoded = Avcc::decode(&mut &buf[avcc.offset as usize..avcc.end() as usize]);
+        assert_eq!(&decoded.unwrap(), moov.find_first::<Avcc>().unwrap().1);
+
+        assert!(count > 20);
+    }
+
+    #[test]
+    fn header_sizes() {
+        let mut buf = Vec::new();
+
+        // A free atom with a 64-bit size.
+        buf.extend_from_slice(&1u32.to_be_bytes());
+        buf.extend_from_slice(b"free");
+        buf.extend_from_slice(&20u64.to_be_bytes());
+        buf.extend_from_slice(&[0; 4]);
+
+        // A uuid atom with a 4 byte body.
+        buf.extend_from_slice(&28u32.to_be_bytes());
+        buf.extend_from_slice(b"uuid");
+        buf.extend_from_slice(&[0xab; 16]);
+        buf.extend_from_slice(&[1, 2, 3, 4]);
+
+        let spans = AtomSpan::scan(&buf, 100).unwrap();
+        assert_eq!(spans[0].offset, 100);
+        assert_eq!(spans[0].header_size, 16);
+        assert_eq!(spans[0].body_size, 4);
+
+        assert_eq!(spans[1].offset, 120);
+        assert_eq!(spans[1].header_size, 24);
+        assert_eq!(spans[1].body_size, 4);
+        assert_eq!(spans[1].body(), 144);
+
+        assert!(matches!(
+            AtomSpan::scan(&buf[..30], 0),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn padding() {
+        let mut udta = Vec::new();
+        udta.extend_from_slice(&32u32.to_be_bytes());
+        udta.extend_from_slice(b"udta");
+
+        // A complete child, a child that's cut short, then the zero terminator.
+        udta.extend_from_slice(&12u32.to_be_bytes());
+        udta.extend_from_slice(b"free");
+        udta.extend_from_slice(&[0; 4]);
+        udta.extend_from_slice(&[0, 0, 0, 64]);
+        udta.extend_from_slice(b"skip");
+        udta.extend_from_slice(&[0; 4]);
+
+        let spans = AtomSpan::scan(&udta, 0).unwrap();
+        assert_eq!(spans[0].children.len(), 1);
+        assert_eq!(spans[0].children[0].kind, Free::KIND);
+
+        // A trailing terminator is padding rather than an error.
+        let mut buf = udta[..20].to_vec();
+        buf[3] = 24;
+        buf.extend_from_slice(&[0; 4]);
+
+        let spans = AtomSpan::scan(&buf, 0).unwrap();
+        assert_eq!(spans[0].children.len(), 1);
+
+        buf.extend_from_slice(&[0; 4]);
+        assert_eq!(AtomSpan::scan(&buf, 0).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::*;
+
+// SubsegmentIndexBox, ISO/IEC 14496-12 Section 8.16.4
+// This follows a sidx and splits each subsegment into byte ranges by level, as assigned by the leva.
+
+/// A contiguous byte range within a subsegment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubsegmentRange {
+    pub level: u8,
+
+    /// The size of the range, limited to 24 bits.
+    ///
+    /// A size of 0 in the last range means the rest of the subsegment.
+    pub range_size: u32,
+}
+
+/// The byte ranges of a subsegment, in order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Subsegment {
+    pub ranges: Vec<SubsegmentRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ssix {
+    /// A subsegment for each reference in the preceding `sidx`.
+    pub subsegments: Vec<Subsegment>,
+}
+
+impl AtomExt for Ssix {
+    type Ext = ();
+
+    const KIND_EXT: FourCC = FourCC::new(b"ssix");
+
+    fn decode_body_ext<B: Buf>(buf: &mut B, _ext: ()) -> Result<Self> {
+        let subsegment_count = u32::decode(buf)?;
+        if subsegment_count as usize > buf.remaining() / 4 {
+            return Err(Error::OutOfBounds);
+        }
+
+        let mut subsegments = Vec::with_capacity(subsegment_count as usize);
+        for _ in 0..subsegment_count {
+            let range_count = u32::decode(buf)?;
+            if range_count as usize > buf.remaining() / 4 {
+                return Err(Error::OutOfBounds);
+            }
+
+            let mut ranges = Vec::with_capacity(range_count as usize);
+            for _ in 0..range_count {
+                let level_and_size = u32::decode(buf)?;
+                ranges.push(SubsegmentRange {
+                    level: (level_and_size >> 24) as u8,
+                    range_size: level_and_size & u24::MAX,
+                });
+            }
+
+            subsegments.push(Subsegment { ranges });
+        }
+
+        Ok(Ssix { subsegments })
+    }
+
+    fn encode_body_ext<B: BufMut>(&self, buf: &mut B) -> Result<()> {
+        let subsegment_count: u32 = self
+            .subsegments
+            .len()
+            .try_into()
+            .map_err(|_| Error::TooLarge(Self::KIND))?;
+        subsegment_count.encode(buf)?;
+
+        for subsegment in &self.subsegments {
+            let range_count: u32 = subsegment
+                .ranges
+                .len()
+                .try_into()
+                .map_err(|_| Error::TooLarge(Self::KIND))?;
+            range_count.encode(buf)?;
+
+            for range in &subsegment.ranges {
+                if range.range_size > u24::MAX {
+                    return Err(Error::TooLarge(Self::KIND));
+                }
+
+                ((range.level as u32) << 24 | range.range_size).encode(buf)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The byte range of a level within a subsegment, as resolved by [Ssix::level_ranges].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelRange {
+    /// The 0-based index of the subsegment, matching the `sidx` reference.
+    pub subsegment: u32,
+    pub level: u8,
+
+    /// The absolute offset of the range in the file.
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl Ssix {
+    /// Combine with the preceding `sidx` to find the absolute byte range of each level in each subsegment.
+    ///
+    /// The `sidx_end` is the offset of the first byte after the `sidx`, which its `first_offset` is relative to.
+    /// Use [Ssix::level] to select the ranges of a single level, such as the I-frames for trick play.
+    pub fn level_ranges(&self, sidx: &Sidx, sidx_end: u64) -> Result<Vec<LevelRange>> {
+        if self.subsegments.len() != sidx.references.len() {
+            return Err(Error::InvalidCombination(
+                "ssix subsegments don't match the sidx references",
+            ));
+        }
+
+        let mut ranges = Vec::new();
+        let mut start = sidx_end
+            .checked_add(sidx.first_offset)
+            .ok_or(Error::OutOfBounds)?;
+
+        for (index, (subsegment, reference)) in
+            self.subsegments.iter().zip(&sidx.references).enumerate()
+        {
+            // A hierarchical sidx would need the nested sidx to resolve.
+            if reference.reference_type {
+                return Err(Error::InvalidCombination(
+                    "ssix requires the sidx to only reference media",
+                ));
+            }
+
+            let end = start
+                .checked_add(reference.reference_size as u64)
+                .ok_or(Error::OutOfBounds)?;
+
+            let mut offset = start;
+            for (position, range) in subsegment.ranges.iter().enumerate() {
+                let size = match range.range_size {
+                    // The rest of the subsegment; an overflow is caught below.
+                    0 if position + 1 == subsegment.ranges.len() => end.saturating_sub(offset),
+                    size => size as u64,
+                };
+
+                ranges.push(LevelRange {
+                    subsegment: index as u32,
+                    level: range.level,
+                    offset,
+                    size,
+                });
+
+                offset = offset.checked_add(size).ok_or(Error::OutOfBounds)?;
+            }
+
+            if offset > end {
+                return Err(Error::InvalidCombination(
+                    "ssix ranges exceed the sidx reference size",
+                ));
+            }
+
+            start = end;
+        }
+
+        Ok(ranges)
+    }
+
+    /// The absolute byte ranges of a single level, in order.
+    pub fn level(&self, sidx: &Sidx, sidx_end: u64, level: u8) -> Result<Vec<LevelRange>> {
+        let mut ranges = self.level_ranges(sidx, sidx_end)?;
+        ranges.retain(|range| range.level == level);
+        Ok(ranges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(reference_size: u32) -> SegmentReference {
+        SegmentReference {
+            reference_type: false,
+            reference_size,
+            subsegment_duration: 1000,
+            starts_with_sap: true,
+            sap_type: 1,
+            sap_delta_time: 0,
+        }
+    }
+
+    fn range(level: u8, range_size: u32) -> SubsegmentRange {
+        SubsegmentRange { level, range_size }
+    }
+
+    #[test]
+    fn test_ssix() {
+        let expected = Ssix {
+            subsegments: vec![
+                Subsegment {
+                    ranges: vec![range(1, 0x123456), range(2, 20)],
+                },
+                Subsegment { ranges: vec![] },
+            ],
+        };
+
+        let mut buf = Vec::new();
+        expected.encode(&mut buf).unwrap();
+        assert_eq!(buf.len(), 12 + 4 + 4 + 8 + 4);
+
+        let decoded = Ssix::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded, expected);
+
+        let invalid = Ssix {
+            subsegments: vec![Subsegment {
+                ranges: vec![range(1, 0x1000000)],
+            }],
+        };
+        assert!(matches!(
+            invalid.encode(&mut Vec::new()),
+            Err(Error::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn level_ranges() {
+        let sidx = Sidx {
+            reference_id: 1,
+            timescale: 1000,
+            first_offset: 10,
+            references: vec![reference(100), reference(50)],
+            ..Default::default()
+        };
+
+        let ssix = Ssix {
+            subsegments: vec![
+                Subsegment {
+                    ranges: vec![range(1, 60), range(2, 40)],
+                },
+                Subsegment {
+                    ranges: vec![range(1, 30), range(2, 20)],
+                },
+            ],
+        };
+
+        let ranges = ssix.level(&sidx, 1000, 1).unwrap();
+        assert_eq!(
+            ranges,
+            [
+                LevelRange {
+                    subsegment: 0,
+                    level: 1,
+                    offset: 1010,
+                    size: 60,
+                },
+                LevelRange {
+                    subsegment: 1,
+                    level: 1,
+                    offset: 1110,
+                    size: 30,
+                },
+            ]
+        );
+
+        // The last range can extend to the end of the subsegment.
+        let mut ssix = ssix;
+        ssix.subsegments[1].ranges[1].range_size = 0;
+        let ranges = ssix.level(&sidx, 1000, 2).unwrap();
+        assert_eq!(ranges[1].offset, 1140);
+        assert_eq!(ranges[1].size, 20);
+
+        let mut sidx = sidx;
+        sidx.references[0].reference_size = 90;
+        assert!(matches!(
+            ssix.level_ranges(&sidx, 1000),
+            Err(Error::InvalidCombination(_))
+        ));
+
+        // The offsets come from the file, so they can overflow.
+        sidx.references[0].reference_size = 100;
+        assert!(matches!(
+            ssix.level_ranges(&sidx, u64::MAX - 150),
+            Err(Error::OutOfBounds)
+        ));
+    }
+}
@@ -52,8 +52,10 @@ fn av1() {
                     default_sample_description_index: 1,
                     default_sample_duration: 0,
                     default_sample_size: 0,
-                    default_sample_flags: 0
-                }]
+                    default_sample_flags: SampleFlags::default()
+                }],
+                leva: None,
+                custom: vec![],
             }),
             trak: vec![Trak {
                 tkhd: Tkhd {
@@ -107,7 +109,8 @@ fn av1() {
                                 urls: vec![Url {
                                     location: "".into()
                                 }]
-                            }
+                            },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -150,7 +153,8 @@ fn av1() {
                             ctts: None,
                             stss: None,
                             stsc: Stsc::default(),
-                            stsz: Stsz::default(),
+                            stsz: Some(Stsz::default()),
+                            stz2: None,
                             stco: Some(Stco::default()),
                             co64: None,
                             sbgp: vec![],
@@ -159,9 +163,15 @@ fn av1() {
                             saio: vec![],
                             saiz: vec![],
                             cslg: None,
+                            sdtp: None,
+                            stsh: None,
+                            stdp: None,
+                            padb: None,
+                            custom: vec![],
                         },
                         ..Default::default()
-                    }
+                    },
+                    custom: vec![],
                 },
                 ..Default::default()
             }],
@@ -203,7 +213,7 @@ fn av1() {
                     sample_description_index: Some(1),
                     default_sample_duration: Some(1000),
                     default_sample_size: Some(252),
-                    default_sample_flags: Some(16842752),
+                    default_sample_flags: Some(SampleFlags::NON_KEYFRAME),
                     duration_is_empty: false,
                     default_base_is_moof: true,
                 },
@@ -215,7 +225,7 @@ fn av1() {
                     entries: vec![TrunEntry {
                         duration: None,
                         size: None,
-                        flags: Some(33554432),
+                        flags: Some(SampleFlags::KEYFRAME),
                         cts: None
                     }]
                 }],
@@ -226,8 +236,11 @@ fn av1() {
                 saio: vec![],
                 meta: None,
                 senc: None,
+                sdtp: None,
                 udta: None,
-            }]
+                custom: vec![],
+            }],
+            custom: vec![],
         }
     );
 
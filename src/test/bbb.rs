@@ -73,6 +73,7 @@ fn bbb() {
                                     location: "".into(),
                                 }],
                             },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -114,14 +115,16 @@ fn bbb() {
                             stsc: Stsc {
                                 ..Default::default()
                             },
-                            stsz: Stsz {
+                            stsz: Some(Stsz {
                                 ..Default::default()
-                            },
+                            }),
+                            stz2: None,
                             stco: Some(Stco { ..Default::default() }),
                             ..Default::default()
                         },
                         ..Default::default()
                     },
+                    custom: vec![],
                 },
                 ..Default::default()
             },
@@ -154,6 +157,7 @@ fn bbb() {
                                     location: "".into(),
                                 }],
                             },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -194,14 +198,16 @@ fn bbb() {
                             stsc: Stsc {
                                 ..Default::default()
                             },
-                            stsz: Stsz {
+                            stsz: Some(Stsz {
                                 ..Default::default()
-                            },
+                            }),
+                            stz2: None,
                             stco: Some(Stco { ..Default::default() }),
                             ..Default::default()
                         },
                         ..Default::default()
                     },
+                    custom: vec![],
                 },
                 ..Default::default()
             }],
@@ -227,7 +233,7 @@ fn bbb() {
                     track_id: 1,
                     sample_description_index: 1.into(),
                     default_sample_duration: 1000.into(),
-                    default_sample_flags: 0x1010000.into(),
+                    default_sample_flags: Some(SampleFlags::NON_KEYFRAME),
                     default_sample_size: 215.into(),
                     default_base_is_moof: true,
                     ..Default::default()
@@ -238,7 +244,7 @@ fn bbb() {
                 trun: vec![Trun {
                     data_offset: 116.into(),
                     entries: vec![TrunEntry {
-                        flags: Some(33554432),
+                        flags: Some(SampleFlags::KEYFRAME),
                         ..Default::default()
                     }],
                 }],
@@ -249,8 +255,11 @@ fn bbb() {
                 saio: vec![],
                 meta: None,
                 senc: None,
+                sdtp: None,
                 udta: None,
+                custom: vec![],
             }],
+            custom: vec![],
         },
     );
 
@@ -289,7 +298,7 @@ fn bbb() {
                     track_id: 2,
                     sample_description_index: 1.into(),
                     default_sample_duration: 1024.into(),
-                    default_sample_flags: 0x2000000.into(),
+                    default_sample_flags: Some(SampleFlags::KEYFRAME),
                     default_sample_size: 9.into(),
                     default_base_is_moof: true,
                     ..Default::default()
@@ -308,8 +317,11 @@ fn bbb() {
                 saio: vec![],
                 meta: None,
                 senc: None,
+                sdtp: None,
                 udta: None,
+                custom: vec![],
             }],
+            custom: vec![],
         },
     );
 
@@ -73,6 +73,7 @@ fn esds() {
                                     location: "".into(),
                                 }],
                             },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -114,14 +115,16 @@ fn esds() {
                             stsc: Stsc {
                                 ..Default::default()
                             },
-                            stsz: Stsz {
+                            stsz: Some(Stsz {
                                 ..Default::default()
-                            },
+                            }),
+                            stz2: None,
                             stco: Some(Stco { ..Default::default() }),
                             ..Default::default()
                         },
                         ..Default::default()
                     },
+                    custom: vec![],
                 },
                 ..Default::default()
             },
@@ -154,6 +157,7 @@ fn esds() {
                                     location: "".into(),
                                 }],
                             },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -194,14 +198,16 @@ fn esds() {
                             stsc: Stsc {
                                 ..Default::default()
                             },
-                            stsz: Stsz {
+                            stsz: Some(Stsz {
                                 ..Default::default()
-                            },
+                            }),
+                            stz2: None,
                             stco: Some(Stco { ..Default::default() }),
                             ..Default::default()
                         },
                         ..Default::default()
                     },
+                    custom: vec![],
                 },
                 ..Default::default()
             }],
@@ -220,7 +226,7 @@ fn esds() {
                     track_id: 1,
                     sample_description_index: 1.into(),
                     default_sample_duration: 512.into(),
-                    default_sample_flags: 0x1010000.into(),
+                    default_sample_flags: Some(SampleFlags::NON_KEYFRAME),
                     default_sample_size: 3377.into(),
                     default_base_is_moof: true,
                     ..Default::default()
@@ -231,7 +237,7 @@ fn esds() {
                 trun: vec![Trun {
                     data_offset: 116.into(),
                     entries: vec![TrunEntry {
-                        flags: Some(33554432),
+                        flags: Some(SampleFlags::KEYFRAME),
                         ..Default::default()
                     }],
                 }],
@@ -242,8 +248,11 @@ fn esds() {
                 saio: vec![],
                 meta: None,
                 senc: None,
+                sdtp: None,
                 udta: None,
+                custom: vec![],
             }],
+            custom: vec![],
         },
     );
 
@@ -48,8 +48,10 @@ fn flac() {
                     default_sample_description_index: 1,
                     default_sample_duration: 0,
                     default_sample_size: 0,
-                    default_sample_flags: 0
-                }]
+                    default_sample_flags: SampleFlags::default()
+                }],
+                leva: None,
+                custom: vec![],
             }),
             trak: vec![Trak {
                 tkhd: Tkhd {
@@ -96,7 +98,8 @@ fn flac() {
                                 urls: vec![Url {
                                     location: "".into()
                                 }]
-                            }
+                            },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -140,7 +143,8 @@ fn flac() {
                             ctts: None,
                             stss: None,
                             stsc: Stsc { entries: vec![] },
-                            stsz: Stsz::default(),
+                            stsz: Some(Stsz::default()),
+                            stz2: None,
                             stco: Some(Stco { entries: [].into() }),
                             co64: None,
                             sbgp: vec![],
@@ -149,9 +153,15 @@ fn flac() {
                             saio: vec![],
                             saiz: vec![],
                             cslg: None,
+                            sdtp: None,
+                            stsh: None,
+                            stdp: None,
+                            padb: None,
+                            custom: vec![],
                         },
                         ..Default::default()
-                    }
+                    },
+                    custom: vec![],
                 },
                 ..Default::default()
             }],
@@ -48,16 +48,18 @@ fn avcc_ext() {
                     default_sample_description_index: 1,
                     default_sample_duration: 0,
                     default_sample_size: 0,
-                    default_sample_flags: 0,
+                    default_sample_flags: SampleFlags::default(),
                 },
                 Trex {
                     track_id: 2,
                     default_sample_description_index: 1,
                     default_sample_duration: 0,
                     default_sample_size: 0,
-                    default_sample_flags: 0,
+                    default_sample_flags: SampleFlags::default(),
                 },
             ],
+            leva: None,
+            custom: vec![],
         }),
         trak: vec![
             Trak {
@@ -111,6 +113,7 @@ fn avcc_ext() {
                             dref: Dref {
                                 urls: vec![Url::default()],
                             },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -167,7 +170,8 @@ fn avcc_ext() {
                             ctts: None,
                             stss: None,
                             stsc: Stsc::default(),
-                            stsz: Stsz::default(),
+                            stsz: Some(Stsz::default()),
+                            stz2: None,
                             stco: Some(Stco::default()),
                             co64: None,
                             sbgp: vec![],
@@ -176,9 +180,15 @@ fn avcc_ext() {
                             saio: vec![],
                             saiz: vec![],
                             cslg: None,
+                            sdtp: None,
+                            stsh: None,
+                            stdp: None,
+                            padb: None,
+                            custom: vec![],
                         },
                         ..Default::default()
                     },
+                    custom: vec![],
                 },
                 ..Default::default()
             },
@@ -226,6 +236,7 @@ fn avcc_ext() {
                             dref: Dref {
                                 urls: vec![Url::default()],
                             },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -269,7 +280,8 @@ fn avcc_ext() {
                             ctts: None,
                             stss: None,
                             stsc: Stsc::default(),
-                            stsz: Stsz::default(),
+                            stsz: Some(Stsz::default()),
+                            stz2: None,
                             stco: Some(Stco::default()),
                             co64: None,
                             sbgp: vec![],
@@ -278,9 +290,15 @@ fn avcc_ext() {
                             saio: vec![],
                             saiz: vec![],
                             cslg: None,
+                            sdtp: None,
+                            stsh: None,
+                            stdp: None,
+                            padb: None,
+                            custom: vec![],
                         },
                         ..Default::default()
                     },
+                    custom: vec![],
                 },
                 ..Default::default()
             },
@@ -521,8 +539,11 @@ fn avc_encrypted_segment() {
                         0, 1, 0, 119, 0, 0, 6, 60, 0, 1, 0, 114, 0, 0, 3, 59
                     ]
                 }),
+                sdtp: None,
                 udta: None,
-            }]
+                custom: vec![],
+            }],
+            custom: vec![],
         }
     );
 
@@ -47,8 +47,10 @@ fn hevc() {
                     default_sample_description_index: 1,
                     default_sample_duration: 0,
                     default_sample_size: 0,
-                    default_sample_flags: 0
-                }]
+                    default_sample_flags: SampleFlags::default()
+                }],
+                leva: None,
+                custom: vec![],
             }),
             trak: vec![Trak {
                 tkhd: Tkhd {
@@ -102,7 +104,8 @@ fn hevc() {
                                 urls: vec![Url {
                                     location: "".to_string()
                                 }]
-                            }
+                            },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -375,11 +378,13 @@ fn hevc() {
                                 }
                                 .into()],
                             },
+                            stsz: Some(Stsz::default()),
                             stco: Some(Stco { entries: vec![] }),
                             ..Default::default()
                         },
                         ..Default::default()
-                    }
+                    },
+                    custom: vec![],
                 },
                 ..Default::default()
             }],
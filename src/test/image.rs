@@ -254,7 +254,8 @@ fn heif() {
                                 ]
                             }
                         ]
-                    }]
+                    }],
+                    custom: vec![],
                 }
                 .into(),
                 Iref {
@@ -409,7 +410,8 @@ fn avif() {
                                 }
                             ]
                         }]
-                    }]
+                    }],
+                    custom: vec![],
                 }
                 .into(),
             ],
@@ -118,7 +118,8 @@ fn av1_anim() {
                                 }
                             ]
                         }]
-                    }]
+                    }],
+                    custom: vec![],
                 }
                 .into()
             ]
@@ -184,7 +185,8 @@ fn av1_anim() {
                             media_time: Some(0),
                             media_rate: 1.into(),
                         }]
-                    })
+                    }),
+                    custom: vec![],
                 }),
                 mdia: Mdia {
                     mdhd: Mdhd {
@@ -212,7 +214,8 @@ fn av1_anim() {
                                 urls: vec![Url {
                                     location: "".into()
                                 }]
-                            }
+                            },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -269,16 +272,18 @@ fn av1_anim() {
                                     sample_description_index: 1
                                 }]
                             },
-                            stsz: Stsz {
+                            stsz: Some(Stsz {
                                 samples: StszSamples::Different {
                                     sizes: vec![3335, 2803]
                                 }
-                            },
+                            }),
+                            stz2: None,
                             stco: Some(Stco { entries: vec![997] }),
                             ..Default::default()
                         },
                         ..Default::default()
                     },
+                    custom: vec![],
                 },
                 ..Default::default()
             }],
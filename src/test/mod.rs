@@ -8,3 +8,115 @@ mod image;
 mod libavif_anim;
 mod uncompressed;
 mod vp9;
+
+use crate::*;
+
+/// Build a progressive file with two tracks of 60 frames at 30fps, using a movie timescale of 600.
+/// There's a keyframe every 10 frames and the tracks are interleaved every 10 frames.
+/// Each 4 byte sample contains the tag, the track index and the sample index.
+pub(crate) fn progressive(tag: u8, edit: impl FnOnce(&mut Moov)) -> Vec<u8> {
+    let mut moov =
+        <Moov as ReadUntil>::read_until(&mut include_bytes!("uncompressed.mp4").as_slice())
+            .unwrap();
+
+    moov.mvhd.timescale = 600;
+    moov.mvhd.duration = 1200;
+    moov.mvhd.next_track_id = 3;
+
+    let mut trak = moov.trak.remove(0);
+    trak.tkhd.duration = 1200;
+    trak.mdia.mdhd.timescale = 30;
+    trak.mdia.mdhd.duration = 60;
+
+    moov.trak = vec![trak.clone(), trak];
+    moov.trak[1].tkhd.track_id = 2;
+    edit(&mut moov);
+
+    let samples = |track: u64, offset: u64| -> Vec<StblSample> {
+        (0..60)
+            .map(|index| {
+                let chunk = index / 10;
+                StblSample {
+                    offset: offset + (chunk * 20 + track * 10 + index % 10) * 4,
+                    size: 4,
+                    decode_time: index,
+                    duration: 1,
+                    composition_offset: 0,
+                    sync: index % 10 == 0,
+                    description_index: 1,
+                    chunk: chunk as u32,
+                    ..Default::default()
+                }
+            })
+            .collect()
+    };
+
+    let ftyp = Ftyp {
+        major_brand: b"isom".into(),
+        minor_version: 0,
+        compatible_brands: vec![b"isom".into()],
+    };
+
+    let mut out = Vec::new();
+    ftyp.encode(&mut out).unwrap();
+
+    // The size of the moov doesn't depend on the offsets, so encode it once to measure.
+    let mut offset = 0;
+    for _ in 0..2 {
+        for (track, trak) in moov.trak.iter_mut().enumerate() {
+            trak.mdia
+                .minf
+                .stbl
+                .set_samples(&samples(track as u64, offset));
+        }
+
+        let mut buf = Vec::new();
+        moov.encode(&mut buf).unwrap();
+        offset = (out.len() + buf.len() + 8) as u64;
+    }
+
+    let mut data = Vec::new();
+    for chunk in 0..6 {
+        for track in 0..2 {
+            for index in 0..10 {
+                data.extend_from_slice(&[tag, track, chunk * 10 + index, 0]);
+            }
+        }
+    }
+
+    moov.encode(&mut out).unwrap();
+    Mdat { data }.encode(&mut out).unwrap();
+    out
+}
+
+/// Decode a progressive file, returning the moov and the contents of every sample per track.
+pub(crate) fn samples(data: &[u8]) -> (Moov, Vec<Vec<[u8; 4]>>) {
+    let mut buf = data;
+    let mut moov = None;
+    while let Some(atom) = Any::decode_maybe(&mut buf).unwrap() {
+        if let Any::Moov(atom) = atom {
+            moov = Some(atom);
+        }
+    }
+
+    let moov = moov.unwrap();
+    let samples = moov
+        .trak
+        .iter()
+        .map(|trak| {
+            trak.mdia
+                .minf
+                .stbl
+                .samples()
+                .unwrap()
+                .iter()
+                .map(|sample| {
+                    let offset = sample.offset as usize;
+                    data[offset..offset + 4].try_into().unwrap()
+                })
+                .collect()
+        })
+        .collect();
+
+    (moov, samples)
+}
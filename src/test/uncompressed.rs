@@ -96,7 +96,8 @@ fn uncompressed() {
                                 urls: vec![Url {
                                     location: "".into()
                                 }],
-                            }
+                            },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -197,9 +198,10 @@ fn uncompressed() {
                                 ]
                                 .into()
                             },
-                            stsz: Stsz {
+                            stsz: Some(Stsz {
                                 samples: StszSamples::Identical { count: 2, size: 6 },
-                            },
+                            }),
+                            stz2: None,
                             stco: Some(Stco {
                                 entries: [856, 862].into()
                             }),
@@ -210,9 +212,15 @@ fn uncompressed() {
                             saiz: vec![],
                             saio: vec![],
                             cslg: None,
+                            sdtp: None,
+                            stsh: None,
+                            stdp: None,
+                            padb: None,
+                            custom: vec![],
                         },
                         ..Default::default()
-                    }
+                    },
+                    custom: vec![],
                 },
                 ..Default::default()
             }]
@@ -72,8 +72,10 @@ fn vp9() {
                     default_sample_description_index: 1,
                     default_sample_duration: 33000,
                     default_sample_size: 0,
-                    default_sample_flags: 0
-                }]
+                    default_sample_flags: SampleFlags::default()
+                }],
+                leva: None,
+                custom: vec![],
             }),
             trak: vec![Trak {
                 tkhd: Tkhd {
@@ -127,7 +129,8 @@ fn vp9() {
                                 urls: vec![Url {
                                     location: "".to_string()
                                 }]
-                            }
+                            },
+                            custom: vec![],
                         },
                         stbl: Stbl {
                             stsd: Stsd {
@@ -163,7 +166,8 @@ fn vp9() {
                             ctts: None,
                             stss: None,
                             stsc: Stsc { entries: vec![] },
-                            stsz: Stsz::default(),
+                            stsz: Some(Stsz::default()),
+                            stz2: None,
                             stco: Some(Stco { entries: vec![] }),
                             co64: None,
                             sbgp: vec![],
@@ -172,9 +176,15 @@ fn vp9() {
                             saio: vec![],
                             saiz: vec![],
                             cslg: None,
+                            sdtp: None,
+                            stsh: None,
+                            stdp: None,
+                            padb: None,
+                            custom: vec![],
                         },
                         ..Default::default()
-                    }
+                    },
+                    custom: vec![],
                 },
                 ..Default::default()
             }],
@@ -14,12 +14,12 @@ impl AsyncReadFrom for Any {
 
 impl AsyncReadFrom for Option<Any> {
     async fn read_from<R: AsyncRead + Unpin + ?Sized>(r: &mut R) -> Result<Self> {
-        let header = match Option::<Header>::read_from(r).await? {
+        let (header, header_size) = match Header::read_sized_tokio(r).await? {
             Some(header) => header,
             None => return Ok(None),
         };
         let mut buf = header.read_body_tokio(r).await?;
-        Ok(Some(Any::decode_atom(&header, &mut buf)?))
+        Ok(Some(Any::decode_sized(&header, header_size, &mut buf)?))
     }
 }
 
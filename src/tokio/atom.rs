@@ -1,6 +1,6 @@
 use super::*;
 
-use crate::{Atom, Buf, DecodeAtom, Encode, Error, Header, Result};
+use crate::{decode_atom_body, Atom, DecodeAtom, Encode, Error, Header, Result};
 
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
@@ -23,23 +23,13 @@ impl<T: Atom> AsyncReadFrom for T {
 
 impl<T: Atom> AsyncReadFrom for Option<T> {
     async fn read_from<R: AsyncRead + Unpin + ?Sized>(r: &mut R) -> Result<Self> {
-        let header = match Option::<Header>::read_from(r).await? {
+        let (header, header_size) = match Header::read_sized_tokio(r).await? {
             Some(header) => header,
             None => return Ok(None),
         };
 
         let mut buf = header.read_body_tokio(r).await?;
-
-        let atom = match T::decode_body(&mut buf) {
-            Ok(atom) => atom,
-            Err(Error::OutOfBounds) => return Err(Error::OverDecode(T::KIND)),
-            Err(Error::ShortRead) => return Err(Error::UnderDecode(T::KIND)),
-            Err(err) => return Err(err),
-        };
-
-        if buf.has_remaining() {
-            return Err(Error::UnderDecode(T::KIND));
-        }
+        let atom = decode_atom_body(T::KIND, header_size, &mut buf, T::decode_body)?;
 
         Ok(Some(atom))
     }
@@ -55,11 +45,19 @@ impl<T: Atom> AsyncReadUntil for T {
 
 impl<T: Atom> AsyncReadUntil for Option<T> {
     async fn read_until<R: AsyncRead + Unpin + ?Sized>(r: &mut R) -> Result<Self> {
-        while let Some(header) = Option::<Header>::read_from(r).await? {
+        // The offset of the current atom from where reading started, for any error.
+        let mut offset = 0;
+
+        while let Some((header, header_size)) = Header::read_sized_tokio(r).await? {
             if header.kind == T::KIND {
                 let mut buf = header.read_body_tokio(r).await?;
-                return Ok(Some(T::decode_atom(&header, &mut buf)?));
+                let atom = decode_atom_body(T::KIND, header_size, &mut buf, T::decode_body)
+                    .map_err(|err| err.offset_by(offset))?;
+                return Ok(Some(atom));
             }
+
+            let size = header.skip_body_tokio(r).await?;
+            offset += (header_size + size) as u64;
         }
 
         Ok(None)
@@ -14,6 +14,15 @@ impl AsyncReadFrom for Header {
 
 impl AsyncReadFrom for Option<Header> {
     async fn read_from<R: AsyncRead + Unpin + ?Sized>(r: &mut R) -> Result<Self> {
+        Ok(Header::read_sized_tokio(r).await?.map(|(header, _)| header))
+    }
+}
+
+impl Header {
+    // Read a header, along with the number of bytes it occupied.
+    pub(crate) async fn read_sized_tokio<R: AsyncRead + Unpin + ?Sized>(
+        r: &mut R,
+    ) -> Result<Option<(Self, usize)>> {
         let mut buf = [0u8; 8];
         let n = r.read(&mut buf).await?;
         if n == 0 {
@@ -25,19 +34,22 @@ impl AsyncReadFrom for Option<Header> {
         let size = u32::from_be_bytes(buf[0..4].try_into().unwrap());
         let kind = u32::from_be_bytes(buf[4..8].try_into().unwrap()).into();
 
-        let size = match size {
-            0 => None,
+        let (size, header_size) = match size {
+            0 => (None, 8),
             1 => {
                 // Read another 8 bytes
                 r.read_exact(&mut buf).await?;
                 let size = u64::from_be_bytes(buf);
                 let size = size.checked_sub(16).ok_or(Error::InvalidSize)?;
 
-                Some(size as usize)
+                (Some(size as usize), 16)
             }
-            _ => Some(size.checked_sub(8).ok_or(Error::InvalidSize)? as usize),
+            _ => (
+                Some(size.checked_sub(8).ok_or(Error::InvalidSize)? as usize),
+                8,
+            ),
         };
 
-        Ok(Some(Header { kind, size }))
+        Ok(Some((Header { kind, size }, header_size)))
     }
 }
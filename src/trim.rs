@@ -0,0 +1,451 @@
+use std::io::{Read, Seek, Write};
+
+use crate::*;
+
+/// Cut a time range out of a progressive file without re-encoding.
+///
+/// `start` and `end` are presentation times in the movie timescale (`mvhd`).
+/// Each track starts at the sync sample preceding `start`, and an edit list hides the extra frames.
+/// Existing edits are clipped to the range rather than replaced, so empty edits, dwells and multiple edits are kept.
+/// The track, media and movie durations are recomputed to match.
+///
+/// The output contains the `ftyp`, the rebuilt `moov` and a single `mdat` with the kept samples in their original order.
+/// Other top-level atoms are dropped, as are the per-sample tables that can't be trimmed (`subs`, `stsh`, `saiz` and `saio`).
+pub fn trim<R: Read + Seek + ?Sized, W: Write + ?Sized>(
+    r: &mut R,
+    w: &mut W,
+    start: u64,
+    end: u64,
+) -> Result<()> {
+    if start >= end {
+        return Err(Error::InvalidCombination(
+            "trim start must be before the end",
+        ));
+    }
+
+    let (ftyp, mut moov) = remux::read(r)?;
+
+    let movie_timescale = moov.mvhd.timescale;
+    let mut tracks = Vec::with_capacity(moov.trak.len());
+    for trak in &mut moov.trak {
+        tracks.push(trim_track(trak, movie_timescale, start, end)?);
+    }
+
+    moov.mvhd.duration = moov
+        .trak
+        .iter()
+        .map(|trak| trak.tkhd.duration)
+        .max()
+        .unwrap_or(0);
+
+    // Keep the chunks interleaved in their original order.
+    let mut chunks: Vec<_> = tracks
+        .iter()
+        .enumerate()
+        .flat_map(|(track, samples)| remux::chunks(samples, 0, 0, track))
+        .collect();
+    chunks.sort_by_key(|chunk| chunk.offset);
+
+    let mut prefix = Vec::new();
+    if let Some(ftyp) = &ftyp {
+        ftyp.encode(&mut prefix)?;
+    }
+
+    remux::write(&mut [r], w, &prefix, &mut moov, &tracks, &[chunks])
+}
+
+// Trim the tables of a single track, returning the kept samples with their original offsets.
+fn trim_track(
+    trak: &mut Trak,
+    movie_timescale: u32,
+    start: u64,
+    end: u64,
+) -> Result<Vec<StblSample>> {
+    let media_timescale = trak.mdia.mdhd.timescale;
+    if media_timescale == 0 || movie_timescale == 0 {
+        return Err(Error::Unsupported("trim timescale is zero"));
+    }
+
+    let timeline = Timeline::from_trak(trak, movie_timescale)?;
+
+    let stbl = &mut trak.mdia.minf.stbl;
+    let samples = stbl.samples()?;
+
+    // The presentation range in the media timescale.
+    let from = rescale(start, movie_timescale, media_timescale);
+    let to = rescale(end, movie_timescale, media_timescale);
+
+    // The end of the media, which bounds open-ended edits and those longer than the track.
+    let media_end = samples
+        .iter()
+        .map(|sample| (sample.composition_time() + sample.duration as i64).max(0) as u64)
+        .max()
+        .unwrap_or(0);
+
+    let edits = clip(&timeline, from, to, media_end)?;
+
+    // The range of media times shown by the clipped edits; a dwell shows a single instant.
+    let shown = edits.iter().filter_map(|edit| {
+        let media_time = edit.media_time?;
+        let duration = edit.media_duration(edit.duration.unwrap_or(0));
+        Some((media_time, media_time + duration.max(1)))
+    });
+    let media_from = shown.clone().map(|(from, _)| from).min();
+    let media_to = shown.map(|(_, to)| to).max().unwrap_or(0) as i64;
+
+    let range = media_from.and_then(|media_from| {
+        // Start at the preceding sync sample, so the first frame can be decoded.
+        let first = samples
+            .iter()
+            .rposition(|sample| sample.sync && sample.composition_time() <= media_from as i64)
+            .or_else(|| samples.iter().position(|sample| sample.sync))?;
+        let last = samples
+            .iter()
+            .rposition(|sample| sample.composition_time() < media_to)?;
+
+        (first <= last).then_some(first..last + 1)
+    });
+
+    let range = range.unwrap_or(0..0);
+
+    for sbgp in &mut stbl.sbgp {
+        sbgp.entries = trim_groups(&sbgp.entries, range.clone());
+    }
+    stbl.subs.clear();
+    stbl.saiz.clear();
+    stbl.saio.clear();
+
+    if range.is_empty() {
+        trak.edts = None;
+        trak.tkhd.duration = 0;
+        trak.mdia.mdhd.duration = 0;
+        return Ok(Vec::new());
+    }
+
+    let base = samples[range.start].decode_time;
+    let kept: Vec<StblSample> = samples[range]
+        .iter()
+        .map(|sample| StblSample {
+            decode_time: sample.decode_time - base,
+            ..*sample
+        })
+        .collect();
+
+    // Convert each boundary to the movie timescale, so the durations don't drift.
+    let entries: Vec<ElstEntry> = edits
+        .iter()
+        .map(|edit| {
+            let end = edit.end().unwrap_or(edit.start);
+            let rate = edit.media_rate;
+
+            ElstEntry {
+                segment_duration: rescale(end, media_timescale, movie_timescale)
+                    - rescale(edit.start, media_timescale, movie_timescale),
+                // Shift the media times along with the kept samples.
+                media_time: edit
+                    .media_time
+                    .map(|time| (time as i64 - base as i64).max(0) as u64),
+                media_rate: FixedPoint::new((rate >> 16) as i16, rate as u16 as i16),
+            }
+        })
+        .collect();
+
+    trak.tkhd.duration = entries.iter().map(|entry| entry.segment_duration).sum();
+    trak.edts = Some(Edts {
+        elst: Some(Elst { entries }),
+        ..Default::default()
+    });
+    trak.mdia.mdhd.duration = kept.iter().map(|sample| sample.duration as u64).sum();
+
+    Ok(kept)
+}
+
+// Clip the timeline to the presentation range, so it starts at zero and only shows media that exists.
+//
+// Every segment is kept, including empty edits and dwells, except for empty edits at the end.
+fn clip(timeline: &Timeline, from: u64, to: u64, media_end: u64) -> Result<Vec<TimelineSegment>> {
+    let mut clipped = Vec::new();
+
+    for segment in timeline.segments() {
+        if segment.media_rate < 0 {
+            return Err(Error::Unsupported("trim reverse edit"));
+        }
+
+        let start = segment.start.max(from);
+        let mut end = segment.end().unwrap_or(u64::MAX).min(to);
+        if start >= end {
+            continue;
+        }
+
+        let media_time = segment
+            .media_time
+            .map(|first| first + segment.media_duration(start - segment.start));
+
+        // Stop where the media runs out, which also closes an open-ended edit.
+        if let Some(media_time) = media_time.filter(|_| segment.media_rate > 0) {
+            let remaining = media_end.saturating_sub(media_time) as u128;
+            let remaining = (remaining << 16).div_ceil(segment.media_rate as u128);
+            end = end.min(start.saturating_add(remaining.try_into().unwrap_or(u64::MAX)));
+        }
+
+        if start < end {
+            clipped.push(TimelineSegment {
+                start: start - from,
+                duration: Some(end - start),
+                media_time,
+                media_rate: segment.media_rate,
+            });
+        }
+    }
+
+    while clipped.last().is_some_and(|segment| segment.is_empty()) {
+        clipped.pop();
+    }
+
+    Ok(clipped)
+}
+
+// Keep the group assignments for the given range of samples.
+fn trim_groups(entries: &[SbgpEntry], range: std::ops::Range<usize>) -> Vec<SbgpEntry> {
+    let mut trimmed: Vec<SbgpEntry> = Vec::new();
+    let mut index = 0;
+
+    for entry in entries {
+        let start = index.max(range.start);
+        index += entry.sample_count as usize;
+        let end = index.min(range.end);
+
+        if start < end {
+            trimmed.push(SbgpEntry {
+                sample_count: (end - start) as u32,
+                group_description_index: entry.group_description_index,
+            });
+        }
+    }
+
+    trimmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test::{progressive, samples};
+    use std::io::Cursor;
+
+    #[test]
+    fn cut() {
+        let input = progressive(0, |_| {});
+
+        // Keep from 0.5s to 1.5s.
+        let mut output = Vec::new();
+        trim(&mut Cursor::new(&input), &mut output, 300, 900).unwrap();
+
+        let (moov, samples) = samples(&output);
+        assert_eq!(moov.mvhd.duration, 600);
+
+        for (track, trak) in moov.trak.iter().enumerate() {
+            // Starts at the keyframe at frame 10, hiding the 5 frames before 0.5s.
+            let expected: Vec<_> = (10..45).map(|i| [0, track as u8, i, 0]).collect();
+            assert_eq!(samples[track], expected);
+
+            assert_eq!(trak.tkhd.duration, 600);
+            assert_eq!(trak.mdia.mdhd.duration, 35);
+            assert_eq!(
+                trak.edts.as_ref().unwrap().elst.as_ref().unwrap().entries,
+                vec![ElstEntry {
+                    segment_duration: 600,
+                    media_time: Some(5),
+                    media_rate: 1.into(),
+                }]
+            );
+
+            let stbl = &trak.mdia.minf.stbl;
+            assert_eq!(stbl.stss.as_ref().unwrap().entries, vec![1, 11, 21, 31]);
+            assert_eq!(stbl.chunk_offsets().len(), 4);
+        }
+    }
+
+    #[test]
+    fn empty_edit() {
+        // The tracks start half a second into the presentation.
+        let input = progressive(0, |moov| {
+            let elst = Elst {
+                entries: vec![
+                    ElstEntry {
+                        segment_duration: 300,
+                        media_time: None,
+                        media_rate: 1.into(),
+                    },
+                    ElstEntry {
+                        segment_duration: 1200,
+                        media_time: Some(0),
+                        media_rate: 1.into(),
+                    },
+                ],
+            };
+
+            for trak in &mut moov.trak {
+                trak.edts = Some(Edts {
+                    elst: Some(elst.clone()),
+                    custom: vec![],
+                });
+            }
+        });
+
+        let mut output = Vec::new();
+        trim(&mut Cursor::new(&input), &mut output, 0, 600).unwrap();
+
+        let (moov, samples) = samples(&output);
+        let expected: Vec<_> = (0..15).map(|i| [0, 0, i, 0]).collect();
+        assert_eq!(samples[0], expected);
+
+        let trak = &moov.trak[0];
+        assert_eq!(trak.tkhd.duration, 600);
+        assert_eq!(
+            trak.edts.as_ref().unwrap().elst.as_ref().unwrap().entries,
+            vec![
+                ElstEntry {
+                    segment_duration: 300,
+                    media_time: None,
+                    media_rate: 1.into(),
+                },
+                ElstEntry {
+                    segment_duration: 300,
+                    media_time: Some(0),
+                    media_rate: 1.into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn edits() {
+        // One second of media, a quarter second dwell, then the rest of the media from frame 40.
+        let input = progressive(0, |moov| {
+            let elst = Elst {
+                entries: vec![
+                    ElstEntry {
+                        segment_duration: 600,
+                        media_time: Some(0),
+                        media_rate: 1.into(),
+                    },
+                    ElstEntry {
+                        segment_duration: 300,
+                        media_time: Some(30),
+                        media_rate: 0.into(),
+                    },
+                    ElstEntry {
+                        segment_duration: 600,
+                        media_time: Some(40),
+                        media_rate: 1.into(),
+                    },
+                ],
+            };
+
+            for trak in &mut moov.trak {
+                trak.edts = Some(Edts {
+                    elst: Some(elst.clone()),
+                    custom: vec![],
+                });
+            }
+        });
+
+        // The end is past the media, which runs out two thirds into the last edit.
+        let mut output = Vec::new();
+        trim(&mut Cursor::new(&input), &mut output, 300, 1500).unwrap();
+
+        let (moov, samples) = samples(&output);
+        let expected: Vec<_> = (10..60).map(|i| [0, 0, i, 0]).collect();
+        assert_eq!(samples[0], expected);
+
+        let trak = &moov.trak[0];
+        assert_eq!(trak.tkhd.duration, 300 + 300 + 400);
+        assert_eq!(moov.mvhd.duration, 1000);
+        assert_eq!(
+            trak.edts.as_ref().unwrap().elst.as_ref().unwrap().entries,
+            vec![
+                ElstEntry {
+                    segment_duration: 300,
+                    media_time: Some(5),
+                    media_rate: 1.into(),
+                },
+                ElstEntry {
+                    segment_duration: 300,
+                    media_time: Some(20),
+                    media_rate: 0.into(),
+                },
+                ElstEntry {
+                    segment_duration: 400,
+                    media_time: Some(30),
+                    media_rate: 1.into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn open_ended() {
+        // A zero duration last edit extends to the end of the media.
+        let input = progressive(0, |moov| {
+            for trak in &mut moov.trak {
+                trak.edts = Some(Edts {
+                    elst: Some(Elst {
+                        entries: vec![ElstEntry {
+                            segment_duration: 0,
+                            media_time: Some(0),
+                            media_rate: 1.into(),
+                        }],
+                    }),
+                    custom: vec![],
+                });
+            }
+        });
+
+        let mut output = Vec::new();
+        trim(&mut Cursor::new(&input), &mut output, 600, u64::MAX / 2).unwrap();
+
+        let (moov, samples) = samples(&output);
+        let expected: Vec<_> = (30..60).map(|i| [0, 0, i, 0]).collect();
+        assert_eq!(samples[0], expected);
+
+        let trak = &moov.trak[0];
+        assert_eq!(trak.tkhd.duration, 600);
+        assert_eq!(
+            trak.edts.as_ref().unwrap().elst.as_ref().unwrap().entries,
+            vec![ElstEntry {
+                segment_duration: 600,
+                media_time: Some(0),
+                media_rate: 1.into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn groups() {
+        let entries = vec![
+            SbgpEntry {
+                sample_count: 5,
+                group_description_index: 1,
+            },
+            SbgpEntry {
+                sample_count: 5,
+                group_description_index: 2,
+            },
+        ];
+
+        assert_eq!(
+            trim_groups(&entries, 3..7),
+            vec![
+                SbgpEntry {
+                    sample_count: 2,
+                    group_description_index: 1,
+                },
+                SbgpEntry {
+                    sample_count: 2,
+                    group_description_index: 2,
+                },
+            ]
+        );
+    }
+}
@@ -7,9 +7,10 @@ pub use num::rational::Ratio;
 pub use num::traits::ToBytes;
 
 /// A four-character code used to identify atoms.
+///
+/// With the `serde` feature, this is serialized as a four character string.
+/// Each byte maps to the Unicode code point of the same value, so non-ASCII codes survive a round trip.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
-// TODO serialize as a string
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FourCC([u8; 4]);
 
 impl FourCC {
@@ -57,6 +58,48 @@ impl fmt::Display for FourCC {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for FourCC {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let s: String = self.0.iter().map(|&b| b as char).collect();
+        serializer.serialize_str(&s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FourCC {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// The inverse of the serde representation: each character is a single byte.
+impl std::str::FromStr for FourCC {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidString(format!("invalid FourCC: {s:?}"));
+
+        let mut bytes = [0u8; 4];
+        let mut chars = s.chars();
+        for byte in bytes.iter_mut() {
+            let c = chars.next().ok_or_else(invalid)?;
+            *byte = u8::try_from(c).map_err(|_| invalid())?;
+        }
+
+        match chars.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(FourCC(bytes)),
+        }
+    }
+}
+
 impl fmt::Debug for FourCC {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = String::from_utf8_lossy(&self.0);
@@ -0,0 +1,833 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::*;
+
+const SKIP: FourCC = FourCC::new(b"skip");
+
+/// How serious a [Diagnostic] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The file is unusual or inaccurate, but most players will cope.
+    Warning,
+
+    /// The file is broken and players are likely to fail.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by [validate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// The atom with the problem, or empty when it concerns the whole file.
+    pub path: AtomPath,
+
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}: {}", self.severity, self.message),
+            false => write!(f, "{}: {}: {}", self.severity, self.path, self.message),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn error(&mut self, path: AtomPath, message: impl Into<String>) {
+        self.push(Severity::Error, path, message);
+    }
+
+    pub fn warning(&mut self, path: AtomPath, message: impl Into<String>) {
+        self.push(Severity::Warning, path, message);
+    }
+
+    pub fn push(&mut self, severity: Severity, path: AtomPath, message: impl Into<String>) {
+        self.0.push(Diagnostic {
+            severity,
+            path,
+            message: message.into(),
+        });
+    }
+
+    /// Report an atom that failed to decode, at the path of the failing child when known.
+    pub fn decode_error(&mut self, err: Error, path: AtomPath) {
+        let path = match err.context() {
+            Some(context) => context.path.clone(),
+            None => path,
+        };
+        self.error(path, err.into_root().to_string());
+    }
+}
+
+/// Check a file for inconsistencies between atoms, returning every problem found.
+///
+/// The checks cover the sample tables, durations, fragments, item locations and brands.
+/// Atoms that fail to decode are reported as a diagnostic, with the path to the failing atom.
+/// An error is only returned when the file can't be read or its top-level atoms overrun the end.
+pub fn validate<R: Read + Seek + ?Sized>(r: &mut R) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = Diagnostics::default();
+    let spans = scan(r)?;
+
+    match spans.first().map(|span| span.kind) {
+        Some(Ftyp::KIND) | Some(Styp::KIND) => {}
+        Some(_) => diagnostics.error(AtomPath::new(), "the first atom is not ftyp or styp"),
+        None => diagnostics.error(AtomPath::new(), "the file is empty"),
+    }
+
+    let mut ftyp = None;
+    let mut moov = None;
+    let mut meta = None;
+    let mut moofs = Vec::new();
+
+    for (index, span) in spans.iter().enumerate() {
+        let path = AtomPath::new().join(span.kind, None);
+
+        match span.kind {
+            Mdat::KIND | Free::KIND | SKIP => continue,
+            Moov::KIND if moov.is_some() => {
+                diagnostics.error(path, "duplicate moov");
+                continue;
+            }
+            _ => {}
+        }
+
+        r.seek(SeekFrom::Start(span.offset))?;
+        let mut buf = vec![0; span.size as usize];
+        r.read_exact(&mut buf)?;
+
+        let atom = match Any::decode(&mut buf.as_slice()) {
+            Ok(atom) => atom,
+            Err(err) => {
+                diagnostics.decode_error(err, path);
+                continue;
+            }
+        };
+
+        match atom {
+            Any::Ftyp(decoded) if ftyp.is_none() => ftyp = Some(decoded),
+            Any::Moov(decoded) => moov = Some(decoded),
+            Any::Meta(decoded) if meta.is_none() => meta = Some(decoded),
+            Any::Moof(decoded) => moofs.push((index, decoded)),
+            _ => {}
+        }
+    }
+
+    let end = spans.last().map_or(0, Span::end);
+    let mdats: Vec<&Span> = spans
+        .iter()
+        .filter(|span| span.kind == Mdat::KIND)
+        .collect();
+
+    if let Some(ftyp) = &ftyp {
+        check_brands(ftyp, moov.as_ref(), meta.as_ref(), &mut diagnostics);
+    }
+
+    if let Some(meta) = &meta {
+        check_items(
+            meta,
+            AtomPath::new().join(Meta::KIND, None),
+            end,
+            &mut diagnostics,
+        );
+    }
+
+    match &moov {
+        Some(moov) => {
+            check_moov(moov, &mdats, end, &mut diagnostics);
+            check_fragments(moov, &spans, &moofs, &mut diagnostics);
+        }
+        // Segments and image files don't need a moov, and one that failed to decode was already reported.
+        None if meta.is_none()
+            && !spans
+                .iter()
+                .any(|span| span.kind == Styp::KIND || span.kind == Moov::KIND) =>
+        {
+            diagnostics.error(AtomPath::new(), "missing moov")
+        }
+        None => {}
+    }
+
+    Ok(diagnostics.0)
+}
+
+fn check_brands(
+    ftyp: &Ftyp,
+    moov: Option<&Moov>,
+    meta: Option<&Meta>,
+    diagnostics: &mut Diagnostics,
+) {
+    let path = AtomPath::new().join(Ftyp::KIND, None);
+    let brands: Vec<FourCC> = std::iter::once(ftyp.major_brand)
+        .chain(ftyp.compatible_brands.iter().copied())
+        .collect();
+
+    let has = |brand: &[u8; 4]| brands.contains(&brand.into());
+
+    let items = meta.is_some_and(|meta| meta.get::<Iloc>().is_some());
+    if items && !has(b"mif1") && !has(b"mif2") {
+        diagnostics.warning(
+            path.clone(),
+            "the file contains items but the brands don't include mif1",
+        );
+    }
+
+    for brand in [b"mif1", b"mif2", b"avif", b"heic", b"heix"] {
+        if has(brand) && meta.is_none() {
+            diagnostics.error(
+                path.clone(),
+                format!("brand {} requires a top-level meta", FourCC::new(brand)),
+            );
+        }
+    }
+
+    for brand in [b"msf1", b"avis", b"hevs"] {
+        if has(brand) && moov.is_none() {
+            diagnostics.error(
+                path.clone(),
+                format!("brand {} requires a moov", FourCC::new(brand)),
+            );
+        }
+    }
+
+    // CMAF tracks are always fragmented.
+    if has(b"cmfc") && moov.is_some_and(|moov| moov.mvex.is_none()) {
+        diagnostics.error(path, "brand cmfc requires an mvex");
+    }
+}
+
+fn check_items(meta: &Meta, path: AtomPath, end: u64, diagnostics: &mut Diagnostics) {
+    let Some(iloc) = meta.get::<Iloc>() else {
+        return;
+    };
+
+    let path = path.join(Iloc::KIND, None);
+    let idat = meta.get::<Idat>().map(|idat| idat.data.len() as u64);
+
+    for location in &iloc.item_locations {
+        let item_id = location.item_id;
+
+        let size = match (location.construction_method, location.data_reference_index) {
+            // Stored in this file.
+            (0, 0) => end,
+            (1, _) => match idat {
+                Some(size) => size,
+                None => {
+                    diagnostics.error(
+                        path.clone(),
+                        format!("item {item_id} is stored in a missing idat"),
+                    );
+                    continue;
+                }
+            },
+            _ => continue,
+        };
+
+        for extent in &location.extents {
+            let start = location.base_offset.checked_add(extent.offset);
+
+            // A zero length means the rest of the data.
+            let stop = match extent.length {
+                0 => start,
+                length => start.and_then(|start| start.checked_add(length)),
+            };
+
+            if stop.is_none_or(|stop| stop > size) {
+                diagnostics.error(
+                    path.clone(),
+                    format!("an extent of item {item_id} is out of bounds"),
+                );
+            }
+        }
+    }
+}
+
+fn check_moov(moov: &Moov, mdats: &[&Span], end: u64, diagnostics: &mut Diagnostics) {
+    let root = AtomPath::new().join(Moov::KIND, None);
+    let mut track_ids = HashSet::new();
+
+    if let Some(meta) = &moov.meta {
+        check_items(meta, root.join(Meta::KIND, None), end, diagnostics);
+    }
+
+    for (index, trak) in moov.trak.iter().enumerate() {
+        let path = root.join(Trak::KIND, Some(index));
+        let track_id = trak.tkhd.track_id;
+
+        if !track_ids.insert(track_id) {
+            diagnostics.error(path.clone(), format!("duplicate track ID {track_id}"));
+        }
+
+        if track_id >= moov.mvhd.next_track_id {
+            diagnostics.error(
+                path.join(Tkhd::KIND, None),
+                format!("track ID {track_id} is not less than mvhd next_track_id"),
+            );
+        }
+
+        let stbl_path = path
+            .join(Mdia::KIND, None)
+            .join(Minf::KIND, None)
+            .join(Stbl::KIND, None);
+
+        check_stbl(&trak.mdia.minf.stbl, mdats, stbl_path, diagnostics);
+    }
+
+    // Fragmented files only describe the initial samples in the moov, if any.
+    if moov.mvex.is_none() {
+        check_durations(moov, diagnostics);
+    }
+}
+
+fn check_stbl(stbl: &Stbl, mdats: &[&Span], path: AtomPath, diagnostics: &mut Diagnostics) {
+    let stsz = stbl.sample_count() as u64;
+
+    let mut consistent = true;
+    let mut compare = |kind: FourCC, count: u64| {
+        if count != stsz {
+            diagnostics.error(
+                path.join(kind, None),
+                format!("{kind} has {count} samples but stsz has {stsz}"),
+            );
+            consistent = false;
+        }
+    };
+
+    let stts = stbl.stts.entries.iter();
+    compare(
+        Stts::KIND,
+        stts.map(|entry| entry.sample_count as u64).sum(),
+    );
+
+    if let Some(ctts) = &stbl.ctts {
+        let ctts = ctts.entries.iter();
+        compare(
+            Ctts::KIND,
+            ctts.map(|entry| entry.sample_count as u64).sum(),
+        );
+    }
+
+    let chunks = stbl.chunk_offsets().len() as u64;
+    let entries = &stbl.stsc.entries;
+    let mut stsc = 0;
+    for (index, entry) in entries.iter().enumerate() {
+        let next = entries
+            .get(index + 1)
+            .map_or(chunks + 1, |next| next.first_chunk as u64);
+        stsc += next.saturating_sub(entry.first_chunk as u64) * entry.samples_per_chunk as u64;
+    }
+    compare(Stsc::KIND, stsc);
+
+    if entries.first().is_some_and(|entry| entry.first_chunk != 1) {
+        diagnostics.error(
+            path.join(Stsc::KIND, None),
+            "the first entry is not chunk 1",
+        );
+        consistent = false;
+    }
+
+    match (&stbl.stco, &stbl.co64) {
+        (Some(_), Some(_)) => diagnostics.error(path.clone(), "both stco and co64 are present"),
+        (None, None) if stsz > 0 => diagnostics.error(path.clone(), "missing stco or co64"),
+        _ => {}
+    }
+
+    if let Some(stss) = &stbl.stss {
+        let path = path.join(Stss::KIND, None);
+
+        if let Some(sample) = stss
+            .entries
+            .iter()
+            .find(|&&sample| sample == 0 || sample as u64 > stsz)
+        {
+            diagnostics.error(path.clone(), format!("sample {sample} is out of range"));
+        }
+
+        if stss.entries.windows(2).any(|pair| pair[0] >= pair[1]) {
+            diagnostics.error(path, "the entries are not in ascending order");
+        }
+    }
+
+    // The remaining checks need the sample positions.
+    if !consistent {
+        return;
+    }
+
+    // The count isn't bounded by the tables, so make sure the samples fit before expanding them.
+    let available: u64 = mdats.iter().map(|mdat| mdat.end() - mdat.body).sum();
+    if stbl.sample_data_size() > available {
+        diagnostics.error(path, "the samples are larger than the mdat");
+        return;
+    }
+
+    let samples = match stbl.samples() {
+        Ok(samples) => samples,
+        Err(err) => {
+            diagnostics.error(path, err.to_string());
+            return;
+        }
+    };
+
+    if let Some(sample) = samples
+        .iter()
+        .find(|sample| !within(mdats, sample.offset, sample.size as u64))
+    {
+        diagnostics.error(
+            path,
+            format!(
+                "a sample at offset {} in chunk {} is outside of any mdat",
+                sample.offset,
+                sample.chunk + 1
+            ),
+        );
+    }
+}
+
+fn check_durations(moov: &Moov, diagnostics: &mut Diagnostics) {
+    let root = AtomPath::new().join(Moov::KIND, None);
+
+    let mut expected = moov.clone();
+    if let Err(err) = expected.finalize(&[]) {
+        diagnostics.error(root, err.to_string());
+        return;
+    }
+
+    for (index, (trak, expected)) in moov.trak.iter().zip(&expected.trak).enumerate() {
+        let path = root.join(Trak::KIND, Some(index));
+
+        let mdhd = trak.mdia.mdhd.duration;
+        if known(mdhd) && mdhd != expected.mdia.mdhd.duration {
+            diagnostics.warning(
+                path.join(Mdia::KIND, None).join(Mdhd::KIND, None),
+                format!(
+                    "duration {mdhd} doesn't match the samples ({})",
+                    expected.mdia.mdhd.duration
+                ),
+            );
+        }
+
+        // Allow for rounding when converting to the movie timescale.
+        let tkhd = trak.tkhd.duration;
+        if known(tkhd) && tkhd.abs_diff(expected.tkhd.duration) > 1 {
+            diagnostics.warning(
+                path.join(Tkhd::KIND, None),
+                format!(
+                    "duration {tkhd} doesn't match the media and edits ({})",
+                    expected.tkhd.duration
+                ),
+            );
+        }
+    }
+
+    let longest = moov
+        .trak
+        .iter()
+        .map(|trak| trak.tkhd.duration)
+        .filter(|&duration| known(duration))
+        .max();
+    if let Some(longest) = longest {
+        if known(moov.mvhd.duration) && moov.mvhd.duration != longest {
+            diagnostics.warning(
+                root.join(Mvhd::KIND, None),
+                format!(
+                    "duration {} doesn't match the longest track ({longest})",
+                    moov.mvhd.duration
+                ),
+            );
+        }
+    }
+}
+
+// A duration of all ones means it's indefinite.
+fn known(duration: u64) -> bool {
+    duration != u64::MAX && duration != u32::MAX as u64
+}
+
+fn check_fragments(
+    moov: &Moov,
+    spans: &[Span],
+    moofs: &[(usize, Moof)],
+    diagnostics: &mut Diagnostics,
+) {
+    if moofs.is_empty() {
+        return;
+    }
+
+    let Some(mvex) = &moov.mvex else {
+        diagnostics.error(
+            AtomPath::new().join(Moov::KIND, None),
+            "the file has fragments but no mvex",
+        );
+        return;
+    };
+
+    let mvex_path = AtomPath::new()
+        .join(Moov::KIND, None)
+        .join(Mvex::KIND, None);
+
+    for trak in &moov.trak {
+        let track_id = trak.tkhd.track_id;
+        if !mvex.trex.iter().any(|trex| trex.track_id == track_id) {
+            diagnostics.error(
+                mvex_path.clone(),
+                format!("missing trex for track {track_id}"),
+            );
+        }
+    }
+
+    let mut sequence = None;
+
+    for (number, (index, moof)) in moofs.iter().enumerate() {
+        let span = &spans[*index];
+        let path = AtomPath::new().join(Moof::KIND, Some(number));
+
+        let sequence_number = moof.mfhd.sequence_number;
+        if sequence.is_some_and(|previous| sequence_number <= previous) {
+            diagnostics.error(
+                path.join(Mfhd::KIND, None),
+                format!("sequence number {sequence_number} is not increasing"),
+            );
+        }
+        sequence = Some(sequence_number);
+
+        // The samples must be in the mdat that follows, before the next moof.
+        let mdat = spans[index + 1..]
+            .iter()
+            .take_while(|span| span.kind != Moof::KIND)
+            .find(|span| span.kind == Mdat::KIND);
+
+        let Some(mdat) = mdat else {
+            diagnostics.error(path, "no mdat follows the moof");
+            continue;
+        };
+
+        // Without an explicit base, each traf continues where the previous one ended.
+        let mut next = span.offset;
+
+        for (traf_index, traf) in moof.traf.iter().enumerate() {
+            let tfhd = &traf.tfhd;
+            let track_id = tfhd.track_id;
+            let traf_path = path.join(Traf::KIND, Some(traf_index));
+
+            if !moov.trak.iter().any(|trak| trak.tkhd.track_id == track_id) {
+                diagnostics.error(
+                    traf_path.join(Tfhd::KIND, None),
+                    format!("unknown track ID {track_id}"),
+                );
+            }
+
+            let trex = mvex.trex.iter().find(|trex| trex.track_id == track_id);
+            let base = match tfhd.base_data_offset {
+                Some(base) => base,
+                None if tfhd.default_base_is_moof => span.offset,
+                None => next,
+            };
+
+            let mut offset = Some(base);
+            for (trun_index, trun) in traf.trun.iter().enumerate() {
+                if let Some(data_offset) = trun.data_offset {
+                    offset = base.checked_add_signed(data_offset as i64);
+                }
+
+                let size: u64 = trun
+                    .entries
+                    .iter()
+                    .map(|entry| {
+                        entry
+                            .size
+                            .or(tfhd.default_sample_size)
+                            .or(trex.map(|trex| trex.default_sample_size))
+                            .unwrap_or(0) as u64
+                    })
+                    .sum();
+
+                match offset {
+                    Some(start) if size == 0 || within(&[mdat], start, size) => {
+                        offset = Some(start + size);
+                    }
+                    _ => {
+                        diagnostics.error(
+                            traf_path.join(Trun::KIND, Some(trun_index)),
+                            "the samples are outside of the following mdat",
+                        );
+                        offset = None;
+                    }
+                }
+            }
+
+            next = offset.unwrap_or(next);
+        }
+    }
+}
+
+fn within(mdats: &[&Span], offset: u64, size: u64) -> bool {
+    mdats
+        .iter()
+        .any(|mdat| offset >= mdat.body && offset + size <= mdat.end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test::progressive;
+    use std::io::Cursor;
+
+    fn errors(buf: &[u8]) -> Vec<Diagnostic> {
+        validate(&mut Cursor::new(buf))
+            .unwrap()
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .collect()
+    }
+
+    // Decode every top-level atom, edit them, then encode them again in the same order.
+    fn edit(mut buf: &[u8], f: impl FnOnce(&mut Vec<Any>)) -> Vec<u8> {
+        let mut atoms = Vec::new();
+        while let Some(atom) = Any::decode_maybe(&mut buf).unwrap() {
+            atoms.push(atom);
+        }
+
+        f(&mut atoms);
+
+        let mut out = Vec::new();
+        for atom in &atoms {
+            atom.encode(&mut out).unwrap();
+        }
+        out
+    }
+
+    fn edit_moov(buf: &[u8], f: impl FnOnce(&mut Moov)) -> Vec<u8> {
+        edit(buf, |atoms| {
+            let moov = atoms.iter_mut().find_map(|atom| match atom {
+                Any::Moov(moov) => Some(moov),
+                _ => None,
+            });
+            f(moov.unwrap())
+        })
+    }
+
+    #[test]
+    fn valid() {
+        let image = include_bytes!("test/image.avif");
+        assert_eq!(validate(&mut Cursor::new(image)).unwrap(), vec![]);
+
+        let input = progressive(0, |_| {});
+        assert_eq!(validate(&mut Cursor::new(input)).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn truncated() {
+        // The fixture was cut after the first moof.
+        let errors = errors(include_bytes!("test/hevc.mp4"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "error: moof[0]: no mdat follows the moof"
+        );
+    }
+
+    #[test]
+    fn sample_tables() {
+        let input = progressive(0, |_| {});
+        let output = edit_moov(&input, |moov| {
+            let stbl = &mut moov.trak[1].mdia.minf.stbl;
+            stbl.stss.as_mut().unwrap().entries.swap(0, 1);
+            stbl.stsz = Some(Stsz {
+                samples: StszSamples::Identical { count: 59, size: 4 },
+            });
+        });
+
+        let errors = errors(&output);
+        let paths: Vec<String> = errors.iter().map(|error| error.path.to_string()).collect();
+        assert_eq!(
+            paths,
+            [
+                "moov/trak[1]/mdia/minf/stbl/stts",
+                "moov/trak[1]/mdia/minf/stbl/stsc",
+                "moov/trak[1]/mdia/minf/stbl/stss",
+            ]
+        );
+    }
+
+    #[test]
+    fn sample_count() {
+        // A count that the tables can't bound is checked against the mdat before expanding.
+        let input = progressive(0, |_| {});
+        let output = edit_moov(&input, |moov| {
+            let stbl = &mut moov.trak[1].mdia.minf.stbl;
+            stbl.stsz = Some(Stsz {
+                samples: StszSamples::Identical {
+                    count: u32::MAX,
+                    size: 4,
+                },
+            });
+            stbl.stts.entries = vec![SttsEntry {
+                sample_count: u32::MAX,
+                sample_delta: 1,
+            }];
+            stbl.ctts = None;
+            stbl.stss = None;
+            stbl.stsc.entries = vec![StscEntry {
+                first_chunk: 1,
+                samples_per_chunk: u32::MAX,
+                sample_description_index: 1,
+            }];
+            stbl.set_chunk_offsets(vec![0]);
+        });
+
+        // The shorter moov also moves the samples of the other track.
+        let errors = errors(&output);
+        assert_eq!(
+            errors.last().unwrap().to_string(),
+            "error: moov/trak[1]/mdia/minf/stbl: the samples are larger than the mdat"
+        );
+    }
+
+    #[test]
+    fn sample_sizes() {
+        let input = progressive(0, |_| {});
+
+        let output = edit_moov(&input, |moov| moov.trak[1].mdia.minf.stbl.stsz = None);
+        let diagnostics = errors(&output);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "error: moov/trak[1]/mdia/minf/stbl: missing box: stsz"
+        );
+
+        let output = edit_moov(&input, |moov| {
+            let stbl = &mut moov.trak[1].mdia.minf.stbl;
+            stbl.stz2 = Some(Stz2 {
+                field_size: 16,
+                sizes: stbl.sample_sizes().map(|size| size as u16).collect(),
+            });
+        });
+        let diagnostics = errors(&output);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].path.to_string(),
+            "moov/trak[1]/mdia/minf/stbl"
+        );
+    }
+
+    #[test]
+    fn chunk_offsets() {
+        let input = progressive(0, |_| {});
+        let output = edit_moov(&input, |moov| {
+            let stbl = &mut moov.trak[0].mdia.minf.stbl;
+            let mut offsets = stbl.chunk_offsets();
+            offsets[5] = 8;
+            stbl.set_chunk_offsets(offsets);
+        });
+
+        let errors = errors(&output);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "error: moov/trak[0]/mdia/minf/stbl: a sample at offset 8 in chunk 6 is outside of any mdat"
+        );
+    }
+
+    #[test]
+    fn durations() {
+        let input = progressive(0, |moov| {
+            moov.mvhd.duration = 600;
+            moov.trak[0].mdia.mdhd.duration = 30;
+        });
+
+        let warnings = validate(&mut Cursor::new(input)).unwrap();
+        let paths: Vec<String> = warnings.iter().map(|w| w.path.to_string()).collect();
+        assert_eq!(paths, ["moov/trak[0]/mdia/mdhd", "moov/mvhd"]);
+        assert!(warnings.iter().all(|w| w.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn items() {
+        let input = include_bytes!("test/image.avif");
+        let output = edit(input, |atoms| {
+            for atom in atoms {
+                if let Any::Meta(meta) = atom {
+                    let iloc = meta.get_mut::<Iloc>().unwrap();
+                    iloc.item_locations[0].base_offset = u32::MAX as u64;
+                }
+            }
+        });
+
+        let errors = errors(&output);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path.to_string(), "meta/iloc");
+    }
+
+    #[test]
+    fn brands() {
+        let input = progressive(0, |_| {});
+        let output = edit(&input, |atoms| {
+            if let Any::Ftyp(ftyp) = &mut atoms[0] {
+                ftyp.major_brand = b"cmfc".into();
+            }
+        });
+
+        let errors = errors(&output);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "brand cmfc requires an mvex");
+    }
+
+    #[test]
+    fn fragments() {
+        let input = progressive(0, |moov| {
+            moov.mvex = Some(Mvex {
+                mehd: None,
+                trex: vec![Trex {
+                    track_id: 1,
+                    default_sample_size: 4,
+                    ..Default::default()
+                }],
+                leva: None,
+                custom: vec![],
+            });
+        });
+
+        let moof = Moof {
+            mfhd: Mfhd { sequence_number: 1 },
+            traf: vec![Traf {
+                tfhd: Tfhd {
+                    track_id: 1,
+                    default_base_is_moof: true,
+                    ..Default::default()
+                },
+                trun: vec![Trun {
+                    // Past the end of the 8 byte mdat.
+                    data_offset: Some(1000),
+                    entries: vec![TrunEntry::default(); 2],
+                }],
+                ..Default::default()
+            }],
+            custom: vec![],
+        };
+
+        let mut output = input.clone();
+        moof.encode(&mut output).unwrap();
+        Mdat { data: vec![0; 8] }.encode(&mut output).unwrap();
+
+        let errors = errors(&output);
+        let paths: Vec<String> = errors.iter().map(|error| error.path.to_string()).collect();
+        assert_eq!(paths, ["moov/mvex", "moof[0]/traf[0]/trun[0]"]);
+        assert_eq!(errors[0].message, "missing trex for track 2");
+    }
+}
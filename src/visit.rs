@@ -0,0 +1,424 @@
+use std::any::Any as StdAny;
+
+use crate::*;
+
+/// A reference to an atom anywhere in the tree, passed to a [Visitor].
+///
+/// Use [AtomRef::downcast] to get the concrete type.
+/// A [Custom] atom downcasts to [Custom], an unknown atom to its raw body as `Vec<u8>`, and an unknown sample entry to [Codec].
+#[derive(Clone, Copy)]
+pub struct AtomRef<'a> {
+    kind: FourCC,
+    atom: &'a dyn StdAny,
+}
+
+impl<'a> AtomRef<'a> {
+    pub fn new(kind: FourCC, atom: &'a dyn StdAny) -> Self {
+        Self { kind, atom }
+    }
+
+    pub fn kind(&self) -> FourCC {
+        self.kind
+    }
+
+    pub fn downcast<T: 'static>(&self) -> Option<&'a T> {
+        self.atom.downcast_ref()
+    }
+}
+
+/// A mutable reference to an atom anywhere in the tree, passed to a [VisitorMut].
+pub struct AtomMut<'a> {
+    kind: FourCC,
+    atom: &'a mut dyn StdAny,
+}
+
+impl<'a> AtomMut<'a> {
+    pub fn new(kind: FourCC, atom: &'a mut dyn StdAny) -> Self {
+        Self { kind, atom }
+    }
+
+    pub fn kind(&self) -> FourCC {
+        self.kind
+    }
+
+    pub fn downcast<T: 'static>(&self) -> Option<&T> {
+        self.atom.downcast_ref()
+    }
+
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.atom.downcast_mut()
+    }
+}
+
+/// Called for every atom in a tree, parents before their children.
+///
+/// The path starts with the atom being traversed and ends with the atom itself.
+/// Atoms that can repeat are indexed among the siblings of the same kind, including the root, ex. `moov[0]/trak[1]/tkhd`.
+/// Resolve a path with [AtomSpan::get] on the root span, or [find_span] on the top-level spans.
+/// Implemented for closures, so `|path, atom| { ... }` works too.
+pub trait Visitor<'a> {
+    fn visit(&mut self, path: &AtomPath, atom: AtomRef<'a>);
+}
+
+impl<'a, F: FnMut(&AtomPath, AtomRef<'a>)> Visitor<'a> for F {
+    fn visit(&mut self, path: &AtomPath, atom: AtomRef<'a>) {
+        self(path, atom)
+    }
+}
+
+/// Called for every atom in a tree, parents before their children, with mutable access.
+///
+/// Any changes to an atom are visible when its children are visited next.
+pub trait VisitorMut {
+    fn visit_mut(&mut self, path: &AtomPath, atom: AtomMut<'_>);
+}
+
+impl<F: FnMut(&AtomPath, AtomMut<'_>)> VisitorMut for F {
+    fn visit_mut(&mut self, path: &AtomPath, atom: AtomMut<'_>) {
+        self(path, atom)
+    }
+}
+
+/// Walk and query a tree of atoms, without matching on every container along the way.
+///
+/// Implemented for every atom, [Any], and a slice of top-level atoms.
+///
+/// ```
+/// # use mp4_atom::*;
+/// # fn main() -> Result<()> {
+/// # let mut input = include_bytes!("test/bbb.mp4").as_slice();
+/// let mut atoms = Vec::new();
+/// while let Some(atom) = Any::decode_maybe(&mut input)? {
+///     atoms.push(atom);
+/// }
+///
+/// for (path, avcc) in atoms.find_all::<Avcc>() {
+///     println!("{path}: profile {}", avcc.avc_profile_indication);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub trait Traverse {
+    /// Call the visitor for this atom and every atom within it.
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>);
+
+    /// Call the visitor for this atom and every atom within it, with mutable access.
+    fn visit_mut(&mut self, visitor: &mut dyn VisitorMut);
+
+    /// Every atom of the given type, with its path, in the order they're encoded.
+    fn find_all<T: AnyAtom + 'static>(&self) -> Vec<(AtomPath, &T)> {
+        let mut find = FindAll(Vec::new());
+        self.visit(&mut find);
+        find.0
+    }
+
+    /// The first atom of the given type, with its path.
+    fn find_first<T: AnyAtom + 'static>(&self) -> Option<(AtomPath, &T)> {
+        self.find_all().into_iter().next()
+    }
+
+    /// Call the function for every atom of the given type, such as to patch each of them.
+    fn for_each_mut<T: AnyAtom + 'static>(&mut self, mut f: impl FnMut(&AtomPath, &mut T)) {
+        self.visit_mut(&mut |path: &AtomPath, mut atom: AtomMut<'_>| {
+            if let Some(atom) = atom.downcast_mut::<T>() {
+                f(path, atom);
+            }
+        });
+    }
+}
+
+struct FindAll<'a, T>(Vec<(AtomPath, &'a T)>);
+
+impl<'a, T: 'static> Visitor<'a> for FindAll<'a, T> {
+    fn visit(&mut self, path: &AtomPath, atom: AtomRef<'a>) {
+        if let Some(atom) = atom.downcast::<T>() {
+            self.0.push((path.clone(), atom));
+        }
+    }
+}
+
+// A single root is indexed as the first of its kind, so the paths match those of the same atom in a slice.
+impl<A: Atom + 'static> Traverse for A {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visit_atom(self, Some(0), &mut AtomPath::new(), visitor);
+    }
+
+    fn visit_mut(&mut self, visitor: &mut dyn VisitorMut) {
+        visit_atom_mut(self, Some(0), &mut AtomPath::new(), visitor);
+    }
+}
+
+impl Traverse for Any {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        self.visit_inner(Some(0), &mut AtomPath::new(), visitor);
+    }
+
+    fn visit_mut(&mut self, visitor: &mut dyn VisitorMut) {
+        self.visit_inner_mut(Some(0), &mut AtomPath::new(), visitor);
+    }
+}
+
+impl Traverse for [Any] {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visit_any(self, &mut AtomPath::new(), visitor);
+    }
+
+    fn visit_mut(&mut self, visitor: &mut dyn VisitorMut) {
+        visit_any_mut(self, &mut AtomPath::new(), visitor);
+    }
+}
+
+pub(crate) fn visit_atom<'a, T: Atom + 'static>(
+    atom: &'a T,
+    index: Option<usize>,
+    path: &mut AtomPath,
+    visitor: &mut dyn Visitor<'a>,
+) {
+    path.push(T::KIND, index);
+    visitor.visit(path, AtomRef::new(T::KIND, atom));
+    atom.visit_children(path, visitor);
+    path.pop();
+}
+
+pub(crate) fn visit_atom_mut<T: Atom + 'static>(
+    atom: &mut T,
+    index: Option<usize>,
+    path: &mut AtomPath,
+    visitor: &mut dyn VisitorMut,
+) {
+    path.push(T::KIND, index);
+    visitor.visit_mut(path, AtomMut::new(T::KIND, atom));
+    atom.visit_children_mut(path, visitor);
+    path.pop();
+}
+
+// A custom or unknown atom, which has no children that we know of.
+pub(crate) fn visit_opaque<'a>(
+    kind: FourCC,
+    atom: &'a dyn StdAny,
+    index: Option<usize>,
+    path: &mut AtomPath,
+    visitor: &mut dyn Visitor<'a>,
+) {
+    path.push(kind, index);
+    visitor.visit(path, AtomRef::new(kind, atom));
+    path.pop();
+}
+
+pub(crate) fn visit_opaque_mut(
+    kind: FourCC,
+    atom: &mut dyn StdAny,
+    index: Option<usize>,
+    path: &mut AtomPath,
+    visitor: &mut dyn VisitorMut,
+) {
+    path.push(kind, index);
+    visitor.visit_mut(path, AtomMut::new(kind, atom));
+    path.pop();
+}
+
+// A list of atoms of any kind, indexed among the siblings of the same kind.
+pub(crate) fn visit_any<'a>(atoms: &'a [Any], path: &mut AtomPath, visitor: &mut dyn Visitor<'a>) {
+    for (i, atom) in atoms.iter().enumerate() {
+        let kind = atom.kind();
+        let index = atoms[..i].iter().filter(|a| a.kind() == kind).count();
+        atom.visit_inner(Some(index), path, visitor);
+    }
+}
+
+pub(crate) fn visit_any_mut(atoms: &mut [Any], path: &mut AtomPath, visitor: &mut dyn VisitorMut) {
+    for i in 0..atoms.len() {
+        let kind = atoms[i].kind();
+        let index = atoms[..i].iter().filter(|a| a.kind() == kind).count();
+        atoms[i].visit_inner_mut(Some(index), path, visitor);
+    }
+}
+
+// Generate the [Atom::visit_children] methods for a container, given the names of its fields.
+/* example:
+visit! {
+    required: [ avcc ],
+    optional: [ btrt, colr, pasp ],
+    multiple: [],
+};
+*/
+
+macro_rules! visit {
+    (required: [$($required:ident),*$(,)?], optional: [$($optional:ident),*$(,)?], multiple: [$($multiple:ident),*$(,)?], $(any: $any:ident,)? $(custom: $custom:ident,)?) => {
+        fn visit_children<'a>(&'a self, path: &mut crate::AtomPath, visitor: &mut dyn crate::Visitor<'a>) {
+            $( crate::visit_atom(&self.$required, None, path, visitor); )*
+            $( if let Some(atom) = &self.$optional {
+                crate::visit_atom(atom, None, path, visitor);
+            } )*
+            $( for (index, atom) in self.$multiple.iter().enumerate() {
+                crate::visit_atom(atom, Some(index), path, visitor);
+            } )*
+            $( crate::visit_any(&self.$any, path, visitor); )?
+            $( for (i, atom) in self.$custom.iter().enumerate() {
+                let index = self.$custom[..i].iter().filter(|a| a.kind() == atom.kind()).count();
+                crate::visit_opaque(atom.kind(), atom, Some(index), path, visitor);
+            } )?
+        }
+
+        fn visit_children_mut(&mut self, path: &mut crate::AtomPath, visitor: &mut dyn crate::VisitorMut) {
+            $( crate::visit_atom_mut(&mut self.$required, None, path, visitor); )*
+            $( if let Some(atom) = &mut self.$optional {
+                crate::visit_atom_mut(atom, None, path, visitor);
+            } )*
+            $( for (index, atom) in self.$multiple.iter_mut().enumerate() {
+                crate::visit_atom_mut(atom, Some(index), path, visitor);
+            } )*
+            $( crate::visit_any_mut(&mut self.$any, path, visitor); )?
+            $( for i in 0..self.$custom.len() {
+                let kind = self.$custom[i].kind();
+                let index = self.$custom[..i].iter().filter(|a| a.kind() == kind).count();
+                crate::visit_opaque_mut(kind, &mut self.$custom[i], Some(index), path, visitor);
+            } )?
+        }
+    };
+}
+
+pub(crate) use visit;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file() -> Vec<Any> {
+        let mut buf = include_bytes!("test/bbb.mp4").as_slice();
+        let mut atoms = Vec::new();
+        while let Some(atom) = Any::decode_maybe(&mut buf).unwrap() {
+            atoms.push(atom);
+        }
+        atoms
+    }
+
+    #[test]
+    fn order() {
+        let moov = match &file()[1] {
+            Any::Moov(moov) => moov.clone(),
+            _ => unreachable!(),
+        };
+
+        let mut paths = Vec::new();
+        moov.visit(&mut |path: &AtomPath, _: AtomRef<'_>| paths.push(path.to_string()));
+
+        assert_eq!(paths[0], "moov[0]");
+        assert_eq!(paths[1], "moov[0]/mvhd");
+        assert!(paths.contains(&"moov[0]/trak[0]/mdia/minf/stbl/stsd/avc1[0]/avcC".to_string()));
+        assert!(paths.contains(&"moov[0]/trak[1]/mdia/minf/stbl/stsd/mp4a[0]/esds".to_string()));
+
+        // Parents come before their children.
+        let stbl = paths
+            .iter()
+            .position(|p| p == "moov[0]/trak[0]/mdia/minf/stbl");
+        let stsd = paths
+            .iter()
+            .position(|p| p == "moov[0]/trak[0]/mdia/minf/stbl/stsd");
+        assert!(stbl < stsd);
+    }
+
+    #[test]
+    fn find_all() {
+        let atoms = file();
+
+        let avcc = atoms.find_all::<Avcc>();
+        assert_eq!(avcc.len(), 1);
+        assert_eq!(
+            avcc[0].0.to_string(),
+            "moov[0]/trak[0]/mdia/minf/stbl/stsd/avc1[0]/avcC"
+        );
+
+        let tfhd = atoms.find_all::<Tfhd>();
+        let moof = atoms.iter().filter(|atom| atom.kind() == Moof::KIND);
+        assert_eq!(tfhd.len(), moof.count());
+        assert_eq!(tfhd[1].0.to_string(), "moof[1]/traf[0]/tfhd");
+
+        assert!(atoms.find_first::<Colr>().is_none());
+        assert!(atoms.find_first::<Mvhd>().is_some());
+    }
+
+    #[test]
+    fn spans() {
+        let files: [&[u8]; 3] = [
+            include_bytes!("test/bbb.mp4"),
+            include_bytes!("test/image.avif"),
+            include_bytes!("test/libavif_anim_q10.avif"),
+        ];
+
+        for buf in files {
+            let decoded = decode_with_spans(buf).unwrap();
+            let (atoms, spans): (Vec<_>, Vec<_>) = decoded.into_iter().unzip();
+
+            // Every path resolves to the exact bytes of the atom, from the top level or from the root.
+            let mut count = 0;
+            atoms.visit(&mut |path: &AtomPath, atom: AtomRef<'_>| {
+                let span = find_span(&spans, path).unwrap();
+                assert_eq!(span.kind, atom.kind(), "{path}");
+                count += 1;
+            });
+            assert!(count > atoms.len());
+
+            // Item info entries are visited too.
+            let infe = atoms
+                .find_all::<Iinf>()
+                .into_iter()
+                .flat_map(|(_, iinf)| &iinf.item_infos);
+            let mut visited = 0;
+            atoms.visit(&mut |path: &AtomPath, atom: AtomRef<'_>| {
+                if atom.downcast::<ItemInfoEntry>().is_some() {
+                    assert!(
+                        path.to_string().starts_with("meta[0]/iinf[0]/infe["),
+                        "{path}"
+                    );
+                    visited += 1;
+                }
+            });
+            assert_eq!(visited, infe.count());
+
+            for (atom, root) in atoms.iter().zip(&spans) {
+                atom.visit(&mut |path: &AtomPath, atom: AtomRef<'_>| {
+                    assert_eq!(root.get(path).unwrap().kind, atom.kind(), "{path}");
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn sample_entries() {
+        let stsd = Stsd {
+            codecs: vec![
+                Codec::Unknown(FourCC::new(b"abcd")),
+                Av01::default().into(),
+                Av01::default().into(),
+            ],
+        };
+
+        let mut paths = Vec::new();
+        stsd.visit(&mut |path: &AtomPath, atom: AtomRef<'_>| {
+            if atom.kind() == FourCC::new(b"abcd") {
+                assert!(atom.downcast::<Codec>().is_some());
+            }
+            paths.push(path.to_string());
+        });
+
+        assert!(paths.contains(&"stsd[0]/abcd[0]".to_string()));
+        assert!(paths.contains(&"stsd[0]/av01[0]".to_string()));
+        assert!(paths.contains(&"stsd[0]/av01[1]/av1C".to_string()));
+    }
+
+    #[test]
+    fn for_each_mut() {
+        let mut atoms = file();
+
+        let mut count = 0;
+        atoms.for_each_mut(|_, trun: &mut Trun| {
+            trun.data_offset = Some(0);
+            count += 1;
+        });
+
+        let truns = atoms.find_all::<Trun>();
+        assert_eq!(truns.len(), count);
+        assert!(truns.iter().all(|(_, trun)| trun.data_offset == Some(0)));
+    }
+}